@@ -1,4 +1,5 @@
 use bevy::{ecs::system::SystemId, prelude::*};
+pub use thorium_ui_core::BistableTransitionState;
 
 /// Plugin that runs the timers for bistable transitions.
 pub struct BistableTransitionPlugin;
@@ -9,36 +10,6 @@ impl Plugin for BistableTransitionPlugin {
     }
 }
 
-/// Tracks an enter / exit transition. This is useful for widgets like dialog boxes and popup
-/// menus which have an opening and closing animation.
-#[derive(Default, Clone, Copy, PartialEq, Debug)]
-pub enum BistableTransitionState {
-    /// Opening animation.
-    Entering,
-
-    /// Fully open.
-    Entered,
-
-    /// Closing animation.
-    Exiting,
-
-    /// Fully closed.
-    #[default]
-    Exited,
-}
-
-impl BistableTransitionState {
-    /// Convert the state into a readable string.
-    pub fn as_name(&self) -> &str {
-        match self {
-            BistableTransitionState::Entering => "entering",
-            BistableTransitionState::Entered => "entered",
-            BistableTransitionState::Exiting => "exiting",
-            BistableTransitionState::Exited => "exited",
-        }
-    }
-}
-
 #[derive(Component, Default)]
 #[require(TransitionTimer)]
 pub struct BistableTransition {
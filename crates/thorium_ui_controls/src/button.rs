@@ -8,7 +8,7 @@ use accesskit::Role;
 use bevy::{
     a11y::AccessibilityNode,
     color::Luminance,
-    ecs::{system::SystemId, world::DeferredWorld},
+    ecs::{spawn::SpawnableList, system::SystemId, world::DeferredWorld},
     input_focus::{tab_navigation::TabIndex, AutoFocus, IsFocused},
     prelude::*,
     ui,
@@ -16,8 +16,8 @@ use bevy::{
     winit::cursor::CursorIcon,
 };
 use thorium_ui_core::{
-    computations, Calc, DynChildren, InsertWhen, IntoSignal, Signal, SpawnArc, SpawnableListGen,
-    StyleHandle, StyleTuple, Styles, Template, TemplateContext,
+    computations, Calc, DynChildren, InsertWhen, IntoSignal, ReadTracked, Signal, SignalSlot,
+    SpawnArc, SpawnableListGen, StyleHandle, Styles, Template, TemplateContext, TemplateProps,
 };
 use thorium_ui_headless::{
     hover::{Hovering, IsHovering},
@@ -42,6 +42,7 @@ pub enum ButtonVariant {
 }
 
 /// Button widget
+#[derive(TemplateProps)]
 pub struct Button {
     /// Color variant - default, primary or danger.
     pub variant: Signal<ButtonVariant>,
@@ -75,17 +76,6 @@ pub struct Button {
 }
 
 impl Button {
-    /// Construct a new `Button`.
-    pub fn new() -> Self {
-        Self::default()
-    }
-
-    /// Set the button color variant.
-    pub fn variant(mut self, variant: impl IntoSignal<ButtonVariant>) -> Self {
-        self.variant = variant.into_signal();
-        self
-    }
-
     /// Method which switches between `default` and `selected` style variants based on a boolean.
     /// Often used for toggle buttons or toolbar items.
     pub fn selected(mut self, selected: bool) -> Self {
@@ -98,30 +88,6 @@ impl Button {
         self
     }
 
-    /// Set whether to render the button in a 'minimal' style with no background and reduced padding.
-    pub fn minimal(mut self, minimal: bool) -> Self {
-        self.minimal = minimal;
-        self
-    }
-
-    /// Set the button size.
-    pub fn size(mut self, size: Size) -> Self {
-        self.size = size;
-        self
-    }
-
-    /// Set the button disabled state.
-    pub fn disabled(mut self, disabled: impl IntoSignal<bool>) -> Self {
-        self.disabled = disabled.into_signal();
-        self
-    }
-
-    /// Set the child views for this element.
-    pub fn contents<L: SpawnableListGen + Send + Sync + 'static>(mut self, elts: L) -> Self {
-        self.contents = Some(Arc::new(elts));
-        self
-    }
-
     /// Set a child which is a text label.
     pub fn label(mut self, label: impl Into<String>) -> Self {
         let s: String = label.into();
@@ -130,61 +96,25 @@ impl Button {
         }));
         self
     }
-
-    /// Set the additional styles for the button.
-    pub fn style<S: StyleTuple + 'static>(mut self, style: S) -> Self {
-        self.style = style.into_handle();
-        self
-    }
-
-    /// Set callback when clicked
-    pub fn on_click(mut self, callback: SystemId) -> Self {
-        self.on_click = Some(callback);
-        self
-    }
-
-    /// Set the tab index of the button.
-    pub fn tab_index(mut self, tab_index: i32) -> Self {
-        self.tab_index = tab_index;
-        self
-    }
-
-    /// Set which corners to render rounded.
-    pub fn corners(mut self, corners: RoundedCorners) -> Self {
-        self.corners = corners;
-        self
-    }
-
-    /// Set whether to autofocus the button when it's added to the UI.
-    pub fn autofocus(mut self, autofocus: bool) -> Self {
-        self.autofocus = autofocus;
-        self
-    }
 }
 
-impl Default for Button {
-    fn default() -> Self {
-        Self {
-            variant: Signal::default(),
-            size: Size::default(),
-            disabled: default(),
-            contents: None,
-            style: StyleHandle::none(),
-            on_click: None,
-            tab_index: 0,
-            corners: RoundedCorners::default(),
-            autofocus: false,
-            minimal: false,
-        }
-    }
+/// The signal properties of a [`Button`], which can be replaced by [`Template::update`].
+#[derive(Component, Clone, Copy)]
+struct ButtonSlots {
+    variant: SignalSlot<ButtonVariant>,
+    disabled: SignalSlot<bool>,
 }
 
 impl Template for Button {
     fn build(&self, builder: &mut TemplateContext) {
-        let variant = self.variant;
+        let slots = ButtonSlots {
+            variant: SignalSlot::new(builder, self.variant.clone()),
+            disabled: SignalSlot::new(builder, self.disabled.clone()),
+        };
+        let variant = slots.variant.signal();
         let corners = self.corners;
         let minimal = self.minimal;
-        let disabled = self.disabled;
+        let disabled = slots.disabled.signal();
         let size = self.size;
         let on_click = self.on_click;
 
@@ -200,6 +130,7 @@ impl Template for Button {
                 ..default()
             },
             Name::new("Button"),
+            slots,
             // Marker to indicate we want to be notified when the button, or any child, is hovered.
             Hovering::default(),
             CursorIcon::System(SystemCursorIcon::Pointer),
@@ -262,9 +193,8 @@ impl Template for Button {
                             if minimal {
                                 colors::TRANSPARENT
                             } else {
-                                let entity = world.entity(button_id);
-                                let pressed = entity
-                                    .get::<CoreButtonPressed>()
+                                let pressed = world
+                                    .read_component::<CoreButtonPressed>(button_id)
                                     .map(|pressed| pressed.0)
                                     .unwrap_or_default();
                                 button_bg_color(
@@ -298,6 +228,52 @@ impl Template for Button {
             SpawnArc(self.contents.clone()),
         )));
     }
+
+    fn update(&self, prev: &Self, builder: &mut TemplateContext) -> bool {
+        // These properties are baked into the button's styles and background. Styles can't be
+        // removed once applied, so a change of user style also needs a rebuild.
+        if self.size != prev.size
+            || self.corners != prev.corners
+            || self.minimal != prev.minimal
+            || self.style != prev.style
+        {
+            return false;
+        }
+        let Some(&button_id) = builder.spawned().first() else {
+            return false;
+        };
+        let mut button = builder.entity_mut(button_id);
+        let Some(slots) = button.get::<ButtonSlots>().copied() else {
+            return false;
+        };
+        button.insert((
+            CoreButton {
+                on_click: self.on_click,
+            },
+            TabIndex(self.tab_index),
+        ));
+
+        // Replace the contents, keeping the background, which is the first child.
+        let same_contents = match (&self.contents, &prev.contents) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        };
+        let old_contents = match button.get::<DynChildren>() {
+            Some(children) if !same_contents => children.iter().skip(1).copied().collect(),
+            _ => Vec::new(),
+        };
+        let world = button.into_world_mut();
+        slots.variant.set(world, self.variant.clone());
+        slots.disabled.set(world, self.disabled.clone());
+        if !same_contents {
+            for child in old_contents {
+                world.entity_mut(child).despawn();
+            }
+            SpawnArc(self.contents.clone()).spawn(world, button_id);
+        }
+        true
+    }
 }
 
 pub(crate) fn button_bg_color(
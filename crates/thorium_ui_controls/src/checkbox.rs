@@ -11,8 +11,8 @@ use bevy::{
     winit::cursor::CursorIcon,
 };
 use thorium_ui_core::{
-    computations, dyn_children, Calc, Cond, DynChildOf, DynChildren, InsertWhen, IntoSignal,
-    Signal, SpawnArc, SpawnableListGen, StyleHandle, StyleTuple, Styles, Template, TemplateContext,
+    computations, dyn_children, Calc, Cond, DynChildren, InsertWhen, NodeRef, Signal, SpawnArc,
+    SpawnableListGen, StyleHandle, Styles, Template, TemplateContext, TemplateProps,
 };
 use thorium_ui_headless::{
     hover::{Hovering, IsHovering},
@@ -69,7 +69,7 @@ fn style_checkbox_label(ec: &mut EntityCommands) {
 }
 
 /// A checkbox widget.
-#[derive(Default)]
+#[derive(TemplateProps)]
 pub struct Checkbox {
     /// Whether the checkbox is checked.
     pub checked: Signal<bool>,
@@ -94,29 +94,6 @@ pub struct Checkbox {
 }
 
 impl Checkbox {
-    /// Create a new checkbox.
-    pub fn new() -> Self {
-        Self::default()
-    }
-
-    /// Set the checked state of the checkbox.
-    pub fn checked(mut self, checked: impl IntoSignal<bool>) -> Self {
-        self.checked = checked.into_signal();
-        self
-    }
-
-    /// Set the disabled state of the checkbox.
-    pub fn disabled(mut self, disabled: impl IntoSignal<bool>) -> Self {
-        self.disabled = disabled.into_signal();
-        self
-    }
-
-    /// Set the label of the checkbox from a [`SpawnableList`].
-    pub fn label<L: SpawnableListGen + Send + Sync + 'static>(mut self, elts: L) -> Self {
-        self.label = Some(Arc::new(elts));
-        self
-    }
-
     /// Set the label of the checkbox from a string.
     pub fn labeled(mut self, label: impl Into<String>) -> Self {
         let s: String = label.into();
@@ -125,37 +102,14 @@ impl Checkbox {
         }));
         self
     }
-
-    /// Set the ARIA label of the checkbox from a string.
-    pub fn aria_label(mut self, label: impl Into<String>) -> Self {
-        self.aria_label = Some(label.into());
-        self
-    }
-
-    /// Set the style of the checkbox.
-    pub fn style<S: StyleTuple + 'static>(mut self, style: S) -> Self {
-        self.style = style.into_handle();
-        self
-    }
-
-    /// Set the on_change callback of the checkbox.
-    pub fn on_change(mut self, on_change: SystemId<In<bool>>) -> Self {
-        self.on_change = Some(on_change);
-        self
-    }
-
-    /// Set the tab index of the checkbox.
-    pub fn tab_index(mut self, tab_index: i32) -> Self {
-        self.tab_index = tab_index;
-        self
-    }
 }
 
 impl Template for Checkbox {
     /// Construct a checkbox widget.
     fn build(&self, builder: &mut TemplateContext) {
-        let checked = self.checked;
-        let disabled = self.disabled;
+        let checked = self.checked.clone();
+        let disabled = self.disabled.clone();
+        let checkbox_ref = NodeRef::new(builder);
         let mut checkbox = builder.spawn((
             Node { ..default() },
             Hovering::default(),
@@ -173,7 +127,10 @@ impl Template for Checkbox {
             },
             computations![
                 Calc::new(
-                    move |world: DeferredWorld| checked.get(&world),
+                    {
+                        let checked = checked.clone();
+                        move |world: DeferredWorld| checked.get(&world)
+                    },
                     |checked, ent| {
                         let checkbox = ent.get::<CoreCheckbox>().unwrap();
                         ent.insert(CoreCheckbox {
@@ -183,12 +140,96 @@ impl Template for Checkbox {
                     },
                 ),
                 InsertWhen::new(
-                    move |world: DeferredWorld| disabled.get(&world),
+                    {
+                        let disabled = disabled.clone();
+                        move |world: DeferredWorld| disabled.get(&world)
+                    },
                     || InteractionDisabled,
                 ),
             ],
+            checkbox_ref,
+            DynChildren::spawn((
+                Spawn((
+                    Node { ..default() },
+                    Name::new("Checkbox::Border"),
+                    Styles(style_checkbox_border),
+                    computations![
+                        Calc::new(
+                            {
+                                let (checked, disabled) = (checked.clone(), disabled.clone());
+                                move |world: DeferredWorld| match (
+                                    checked.get(&world),
+                                    disabled.get(&world),
+                                    checkbox_ref
+                                        .get(&world)
+                                        .is_some_and(|id| world.is_hovering(id)),
+                                ) {
+                                    (true, true, _) => colors::ACCENT.with_alpha(0.2),
+                                    (true, false, true) => colors::ACCENT.darker(0.15),
+                                    (true, _, _) => colors::ACCENT.darker(0.2),
+                                    (false, true, _) => colors::U1.with_alpha(0.7),
+                                    (false, false, true) => colors::U1.lighter(0.002),
+                                    (false, false, false) => colors::U1,
+                                }
+                            },
+                            |color, ec| {
+                                ec.insert(BackgroundColor(color.into()));
+                            },
+                        ),
+                        Calc::new(
+                            move |world: DeferredWorld| {
+                                checkbox_ref
+                                    .get(&world)
+                                    .is_some_and(|id| world.is_focus_visible(id))
+                            },
+                            |is_focused, ec| {
+                                if is_focused {
+                                    ec.insert(Outline {
+                                        color: colors::FOCUS.into(),
+                                        width: ui::Val::Px(2.0),
+                                        offset: ui::Val::Px(2.0),
+                                    });
+                                } else {
+                                    ec.remove::<Outline>();
+                                };
+                            },
+                        ),
+                    ],
+                    dyn_children![Cond::new(
+                        move |world: DeferredWorld| checked.get(&world),
+                        move || Spawn((
+                            ImageNode {
+                                color: Srgba::WHITE.into(),
+                                ..default()
+                            },
+                            UiImageHandle(
+                                "embedded://thorium_ui_controls/assets/icons/checkmark.png".into(),
+                            ),
+                            Styles(style_checkbox_inner),
+                        )),
+                        || (),
+                    )],
+                )),
+                Spawn((
+                    Node::default(),
+                    Styles((typography::text_default, style_checkbox_label)),
+                    computations![Calc::new(
+                        move |world: DeferredWorld| disabled.get(&world),
+                        |disabled, ec| {
+                            ec.entry::<InheritableFontColor>()
+                                .and_modify(move |mut color| {
+                                    if disabled {
+                                        color.0 = colors::FOREGROUND.with_alpha(0.2).into();
+                                    } else {
+                                        color.0 = colors::FOREGROUND.into();
+                                    }
+                                });
+                        },
+                    ),],
+                    DynChildren::spawn(SpawnArc(self.label.clone())),
+                )),
+            )),
         ));
-        let checkbox_id = checkbox.id();
 
         // Set ARIA label.
         if let Some(aria_label) = &self.aria_label {
@@ -196,79 +237,5 @@ impl Template for Checkbox {
                 access_node.set_label(aria_label.clone());
             }
         }
-
-        checkbox.with_related::<DynChildOf>(|builder| {
-            builder.spawn((
-                Node { ..default() },
-                Name::new("Checkbox::Border"),
-                Styles(style_checkbox_border),
-                computations![
-                    Calc::new(
-                        move |world: DeferredWorld| match (
-                            checked.get(&world),
-                            disabled.get(&world),
-                            world.is_hovering(checkbox_id),
-                        ) {
-                            (true, true, _) => colors::ACCENT.with_alpha(0.2),
-                            (true, false, true) => colors::ACCENT.darker(0.15),
-                            (true, _, _) => colors::ACCENT.darker(0.2),
-                            (false, true, _) => colors::U1.with_alpha(0.7),
-                            (false, false, true) => colors::U1.lighter(0.002),
-                            (false, false, false) => colors::U1,
-                        },
-                        |color, ec| {
-                            ec.insert(BackgroundColor(color.into()));
-                        },
-                    ),
-                    Calc::new(
-                        move |world: DeferredWorld| world.is_focus_visible(checkbox_id),
-                        |is_focused, ec| {
-                            if is_focused {
-                                ec.insert(Outline {
-                                    color: colors::FOCUS.into(),
-                                    width: ui::Val::Px(2.0),
-                                    offset: ui::Val::Px(2.0),
-                                });
-                            } else {
-                                ec.remove::<Outline>();
-                            };
-                        },
-                    ),
-                ],
-                dyn_children![Cond::new(
-                    move |world: DeferredWorld| checked.get(&world),
-                    move || Spawn((
-                        ImageNode {
-                            color: Srgba::WHITE.into(),
-                            ..default()
-                        },
-                        UiImageHandle(
-                            "embedded://thorium_ui_controls/assets/icons/checkmark.png".into(),
-                        ),
-                        Styles(style_checkbox_inner),
-                    )),
-                    || (),
-                )],
-            ));
-
-            builder.spawn((
-                Node::default(),
-                Styles((typography::text_default, style_checkbox_label)),
-                computations![Calc::new(
-                    move |world: DeferredWorld| disabled.get(&world),
-                    |disabled, ec| {
-                        ec.entry::<InheritableFontColor>()
-                            .and_modify(move |mut color| {
-                                if disabled {
-                                    color.0 = colors::FOREGROUND.with_alpha(0.2).into();
-                                } else {
-                                    color.0 = colors::FOREGROUND.into();
-                                }
-                            });
-                    },
-                ),],
-                DynChildren::spawn(SpawnArc(self.label.clone())),
-            ));
-        });
     }
 }
@@ -8,16 +8,13 @@ use bevy::{
     ui::{self},
 };
 use thorium_ui_core::{
-    computations, dyn_children, Calc, Cond, DynChildren, Signal, SpawnArc, SpawnableListGen,
-    Styles, Template, TemplateContext,
+    computations, dyn_children, BistableTransitionState, Calc, DynChildren, Mutable, Signal,
+    SpawnArc, SpawnableListGen, Styles, Template, TemplateContext, TransitionCond,
 };
 use thorium_ui_headless::CoreBarrier;
 
 use crate::{
-    animation::{
-        AnimatedBackgroundColor, AnimatedScale, AnimatedTransition, BistableTransition,
-        BistableTransitionState,
-    },
+    animation::{AnimatedBackgroundColor, AnimatedScale, AnimatedTransition},
     colors,
     typography::text_default,
     InheritableFontSize,
@@ -138,49 +135,24 @@ impl Template for Dialog {
     fn build(&self, builder: &mut TemplateContext) {
         let on_close = self.on_close;
         let on_exited = self.on_exited;
-        let open = self.open;
+        let open = self.open.clone();
         let width = self.width;
 
-        let transition_entity = builder.spawn((
-            // GhostNode::default(),
-            BistableTransition::new(false, TRANSITION_DURATION).set_exit_callback(on_exited),
-            computations![Calc::new(
-                move |world: DeferredWorld| open.get(&world),
-                |open, ent| {
-                    ent.get_mut::<BistableTransition>().unwrap().set_open(open);
-                },
-            )],
-        ));
-        let transition_id = transition_entity.id();
-
         let contents = self.contents.clone();
-        builder.spawn(Cond::new(
-            move |world: DeferredWorld| {
-                world
-                    .entity(transition_id)
-                    .get::<BistableTransition>()
-                    .unwrap()
-                    .state
-                    != BistableTransitionState::Exited
-            },
-            move || {
+        let mut cond = TransitionCond::new(
+            move |world: DeferredWorld| open.get(&world),
+            move |state: Mutable<BistableTransitionState>| {
                 Spawn((
                     Node::default(),
                     Name::new("Dialog::Overlay"),
                     Styles(style_dialog_barrier),
                     CoreBarrier { on_close },
                     computations![Calc::new(
-                        move |world: DeferredWorld| match world
-                            .entity(transition_id)
-                            .get::<BistableTransition>()
-                            .unwrap()
-                            .state
-                        {
+                        move |world: DeferredWorld| match state.get(&world) {
                             BistableTransitionState::Entering
                             | BistableTransitionState::Entered => colors::U2.with_alpha(0.7),
-                            BistableTransitionState::Exiting | BistableTransitionState::Exited => {
-                                colors::U2.with_alpha(0.0)
-                            }
+                            BistableTransitionState::Exiting | BistableTransitionState::Exited =>
+                                colors::U2.with_alpha(0.0),
                         },
                         move |color, ent| {
                             AnimatedTransition::<AnimatedBackgroundColor>::start(
@@ -208,12 +180,7 @@ impl Template for Dialog {
                             modal: true,
                         },
                         computations![Calc::new(
-                            move |world: DeferredWorld| match world
-                                .entity(transition_id)
-                                .get::<BistableTransition>()
-                                .unwrap()
-                                .state
-                            {
+                            move |world: DeferredWorld| match state.get(&world) {
                                 BistableTransitionState::Entering => (0.0, 1.0),
                                 BistableTransitionState::Exiting => (1.0, 0.0),
                                 BistableTransitionState::Entered => (1.0, 1.0),
@@ -232,8 +199,15 @@ impl Template for Dialog {
                     )),
                 ))
             },
-            || (),
-        ));
+            |_| (),
+        )
+        // The dialog's animations all take the same time, so rather than have them report when
+        // they are done, the exit transition is ended by the timeout.
+        .timeout(TRANSITION_DURATION);
+        if let Some(on_exited) = on_exited {
+            cond = cond.on_exited(on_exited);
+        }
+        builder.spawn(cond);
 
         // TODO: re-enable this code.
         //                     .observe(|mut trigger: Trigger<Pointer<Pressed>>| {
@@ -13,8 +13,8 @@ use bevy::{
     winit::cursor::CursorIcon,
 };
 use thorium_ui_core::{
-    computations, CreateMemo, DynChildren, IntoSignal, InvokeWith, Calc, Signal, StyleHandle,
-    StyleTuple, Styles, Template, TemplateContext,
+    computations, Calc, CreateMemo, DynChildren, InvokeWith, Signal, StyleHandle, Styles, Template,
+    TemplateContext, TemplateProps,
 };
 use thorium_ui_headless::{hover::IsHovering, CoreCheckbox};
 
@@ -31,7 +31,7 @@ fn style_toggle(ec: &mut EntityCommands) {
 
 /// A widget which displays small toggleable chevron that can be used to control whether
 /// a panel is visible or hidden.
-#[derive(Default)]
+#[derive(TemplateProps)]
 pub struct DisclosureToggle {
     /// Whether the toggle is in an expanded state.
     pub expanded: Signal<bool>,
@@ -55,59 +55,10 @@ pub struct DisclosureToggle {
     pub autofocus: bool,
 }
 
-impl DisclosureToggle {
-    /// Construct a new `DisclosureToggle`.
-    pub fn new() -> Self {
-        Self::default()
-    }
-
-    /// Set the expanded state of the button.
-    pub fn expanded(mut self, expanded: impl IntoSignal<bool>) -> Self {
-        self.expanded = expanded.into_signal();
-        self
-    }
-
-    /// Set the button size.
-    pub fn size(mut self, size: Size) -> Self {
-        self.size = size;
-        self
-    }
-
-    /// Set the button disabled state.
-    pub fn disabled(mut self, disabled: impl IntoSignal<bool>) -> Self {
-        self.disabled = disabled.into_signal();
-        self
-    }
-
-    /// Set the additional styles for the button.
-    pub fn style<S: StyleTuple + 'static>(mut self, style: S) -> Self {
-        self.style = style.into_handle();
-        self
-    }
-
-    /// Set callback when clicked
-    pub fn on_change(mut self, callback: SystemId<In<bool>>) -> Self {
-        self.on_change = Some(callback);
-        self
-    }
-
-    /// Set the tab index of the button.
-    pub fn tab_index(mut self, tab_index: i32) -> Self {
-        self.tab_index = tab_index;
-        self
-    }
-
-    /// Set whether to autofocus the button when it's added to the UI.
-    pub fn autofocus(mut self, autofocus: bool) -> Self {
-        self.autofocus = autofocus;
-        self
-    }
-}
-
 impl Template for DisclosureToggle {
     fn build(&self, builder: &mut TemplateContext) {
-        let disabled = self.disabled;
-        let checked = self.expanded;
+        let disabled = self.disabled.clone();
+        let checked = self.expanded.clone();
         let size = self.size;
         let mut toggle = builder.spawn((
             Node::default(),
@@ -153,6 +104,7 @@ impl Template for DisclosureToggle {
                 )
             ],
             DynChildren::spawn(InvokeWith(move |tc| {
+                let disabled = disabled.clone();
                 let icon_color = tc.create_memo(
                     move |world: DeferredWorld| {
                         let is_disabled = disabled.get(&world);
@@ -5,8 +5,8 @@ use bevy::{
     ui,
 };
 use thorium_ui_core::{
-    computations, CreateMemo, IntoSignal, Calc, Signal, StyleHandle, StyleTuple, Styles,
-    Template, TemplateContext,
+    computations, Calc, CreateMemo, IntoSignal, Signal, StyleHandle, Styles, Template,
+    TemplateContext, TemplateProps,
 };
 use thorium_ui_headless::{hover::Hovering, CoreSlider, ValueChange};
 
@@ -118,6 +118,7 @@ fn style_thumb(ec: &mut EntityCommands) {
 }
 
 /// Horizontal slider widget that displays a gradient bar and a draggable button.
+#[derive(TemplateProps)]
 pub struct GradientSlider {
     /// Gradient to display.
     pub gradient: Signal<ColorGradient>,
@@ -129,6 +130,7 @@ pub struct GradientSlider {
     pub min: Signal<f32>,
 
     /// Maximum slider value.
+    #[props(default = 1.)]
     pub max: Signal<f32>,
 
     /// Number of decimal places to round to (0 = integer).
@@ -144,81 +146,11 @@ pub struct GradientSlider {
     pub on_change: Option<SystemId<In<f32>>>,
 }
 
-impl GradientSlider {
-    /// Create a new gradient slider.
-    pub fn new() -> Self {
-        Self::default()
-    }
-
-    /// Set the gradient to display.
-    pub fn gradient(mut self, gradient: impl IntoSignal<ColorGradient>) -> Self {
-        self.gradient = gradient.into_signal();
-        self
-    }
-
-    /// Set the current slider value.
-    pub fn value(mut self, value: impl IntoSignal<f32>) -> Self {
-        self.value = value.into_signal();
-        self
-    }
-
-    /// Set the minimum slider value.
-    pub fn min(mut self, min: impl IntoSignal<f32>) -> Self {
-        self.min = min.into_signal();
-        self
-    }
-
-    /// Set the maximum slider value.
-    pub fn max(mut self, max: impl IntoSignal<f32>) -> Self {
-        self.max = max.into_signal();
-        self
-    }
-
-    /// Set the number of decimal places to round to (0 = integer).
-    pub fn precision(mut self, precision: usize) -> Self {
-        self.precision = precision;
-        self
-    }
-
-    /// Set whether the slider is disabled.
-    pub fn disabled(mut self, disabled: impl IntoSignal<bool>) -> Self {
-        self.disabled = disabled.into_signal();
-        self
-    }
-
-    /// Set the style handle for the slider root element.
-    pub fn style<S: StyleTuple + 'static>(mut self, style: S) -> Self {
-        self.style = style.into_handle();
-        self
-    }
-
-    /// Set the callback called when the value changes.
-    pub fn on_change(mut self, on_change: SystemId<In<f32>>) -> Self {
-        self.on_change = Some(on_change);
-        self
-    }
-}
-
-impl Default for GradientSlider {
-    fn default() -> Self {
-        Self {
-            gradient: Signal::Constant(ColorGradient::default()),
-            value: Signal::Constant(0.),
-            min: Signal::Constant(0.),
-            max: Signal::Constant(1.),
-            precision: 0,
-            disabled: Signal::Constant(false),
-            style: StyleHandle::default(),
-            on_change: None,
-        }
-    }
-}
-
 impl Template for GradientSlider {
     fn build(&self, builder: &mut TemplateContext) {
         // This should really be an effect.
         let color_stops: Signal<(usize, [Vec4; 8])> = {
-            let gradient = self.gradient;
+            let gradient = self.gradient.clone();
             builder
                 .create_memo(
                     move |world: DeferredWorld| {
@@ -247,20 +179,26 @@ impl Template for GradientSlider {
         ));
         let slider_id = slider.id();
 
-        let min = self.min;
-        let max = self.max;
-        let value = self.value;
+        let min = self.min.clone();
+        let max = self.max.clone();
+        let value = self.value.clone();
         let precision = self.precision;
         let on_change = self.on_change;
 
         slider
             .insert(computations![Calc::new(
-                move |world: DeferredWorld| (value.get(&world), min.get(&world), max.get(&world)),
+                {
+                    let (min, max, value) = (min.clone(), max.clone(), value.clone());
+                    move |world: DeferredWorld| {
+                        (value.get(&world), min.get(&world), max.get(&world))
+                    }
+                },
                 |(value, min, max), ent| {
                     ent.insert(CoreSlider::new(value, min, max));
                 }
             )])
-            .observe(
+            .observe({
+                let (min, max, value) = (min.clone(), max.clone(), value.clone());
                 move |mut trigger: Trigger<ValueChange<f32>>,
                       world: DeferredWorld,
                       mut commands: Commands| {
@@ -275,9 +213,10 @@ impl Template for GradientSlider {
                             commands.run_system_with(on_change, new_value);
                         }
                     }
-                },
-            )
-            .observe(
+                }
+            })
+            .observe({
+                let (min, max, value) = (min.clone(), max.clone(), value.clone());
                 move |mut trigger: Trigger<Pointer<Pressed>>,
                       world: DeferredWorld,
                       mut commands: Commands| {
@@ -304,8 +243,8 @@ impl Template for GradientSlider {
                             }
                         };
                     }
-                },
-            )
+                }
+            })
             .with_children(|builder| {
                 builder.spawn((
                     MaterialNode::<GradientRectMaterial>::default(),
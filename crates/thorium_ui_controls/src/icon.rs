@@ -66,7 +66,7 @@ impl Template for Icon {
     fn build(&self, builder: &mut TemplateContext) {
         let icon = self.icon.clone();
         let size = self.size;
-        let color = self.color;
+        let color = self.color.clone();
 
         builder.spawn((
             ImageNode { ..default() },
@@ -101,15 +101,18 @@ impl IconButton {
 
 impl Template for IconButton {
     fn build(&self, builder: &mut TemplateContext) {
-        let disabled = self.disabled;
+        let disabled = self.disabled.clone();
         let size = self.size;
         let icon = self.icon.clone();
         let icon_color = builder.create_memo(
-            move |world: DeferredWorld| {
-                if disabled.get(&world) {
-                    Color::from(colors::DIM).with_alpha(0.2)
-                } else {
-                    Color::from(colors::DIM)
+            {
+                let disabled = disabled.clone();
+                move |world: DeferredWorld| {
+                    if disabled.get(&world) {
+                        Color::from(colors::DIM).with_alpha(0.2)
+                    } else {
+                        Color::from(colors::DIM)
+                    }
                 }
             },
             Color::from(colors::DIM),
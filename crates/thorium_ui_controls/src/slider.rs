@@ -58,6 +58,7 @@ fn style_label(ec: &mut EntityCommands) {
 }
 
 /// Horizontal slider widget
+#[derive(TemplateProps)]
 pub struct Slider {
     /// Current slider value.
     pub value: Signal<f32>,
@@ -66,12 +67,14 @@ pub struct Slider {
     pub min: Signal<f32>,
 
     /// Maximum slider value.
+    #[props(default = 1.)]
     pub max: Signal<f32>,
 
     /// Number of decimal places to round to (0 = integer).
     pub precision: usize,
 
     /// Amount to increment when using arrow buttons.
+    #[props(default = 1.)]
     pub step: f32,
 
     /// Whether the slider is disabled.
@@ -91,141 +94,98 @@ pub struct Slider {
     pub on_change: Option<SystemId<In<f32>>>,
 }
 
-impl Slider {
-    /// Create a new slider.
-    pub fn new() -> Self {
-        Self::default()
-    }
-
-    /// Set the current slider value.
-    pub fn value(mut self, value: impl IntoSignal<f32>) -> Self {
-        self.value = value.into_signal();
-        self
-    }
-
-    /// Set the minimum slider value.
-    pub fn min(mut self, min: impl IntoSignal<f32>) -> Self {
-        self.min = min.into_signal();
-        self
-    }
-
-    /// Set the maximum slider value.
-    pub fn max(mut self, max: impl IntoSignal<f32>) -> Self {
-        self.max = max.into_signal();
-        self
-    }
-
-    /// Set the number of decimal places to round to (0 = integer).
-    pub fn precision(mut self, precision: usize) -> Self {
-        self.precision = precision;
-        self
-    }
-
-    /// Set the amount to increment when using arrow buttons.
-    pub fn step(mut self, step: f32) -> Self {
-        self.step = step;
-        self
-    }
-
-    /// Set whether the slider is disabled.
-    pub fn disabled(mut self, disabled: impl IntoSignal<bool>) -> Self {
-        self.disabled = disabled.into_signal();
-        self
-    }
-
-    /// Set the signal which returns the value formatted as a string. If `None`, then a default
-    /// formatter will be used.
-    pub fn formatted_value(mut self, formatted_value: impl IntoSignal<String>) -> Self {
-        self.formatted_value = Some(formatted_value.into_signal());
-        self
-    }
-
-    /// Set the optional label to be displayed inside the slider.
-    pub fn label(mut self, label: impl Into<String>) -> Self {
-        self.label = Some(label.into());
-        self
-    }
-
-    /// Set the style handle for the slider root element.
-    pub fn style<S: StyleTuple + 'static>(mut self, style: S) -> Self {
-        self.style = style.into_handle();
-        self
-    }
+/// The non-signal properties of a [`Slider`] which are read by its callbacks, and which can be
+/// replaced by [`Template::update`].
+#[derive(Clone, Copy, PartialEq)]
+struct SliderSettings {
+    precision: usize,
+    step: f32,
+    on_change: Option<SystemId<In<f32>>>,
+}
 
-    /// Set the callback called when value changes.
-    pub fn on_change(mut self, on_change: SystemId<In<f32>>) -> Self {
-        self.on_change = Some(on_change);
-        self
-    }
+/// The properties of a [`Slider`] which can be replaced by [`Template::update`].
+#[derive(Component, Clone, Copy)]
+struct SliderSlots {
+    value: SignalSlot<f32>,
+    min: SignalSlot<f32>,
+    max: SignalSlot<f32>,
+    settings: Mutable<SliderSettings>,
 }
 
-impl Default for Slider {
-    fn default() -> Self {
-        Self {
-            value: Signal::Constant(0.),
-            min: Signal::Constant(0.),
-            max: Signal::Constant(1.),
-            precision: 0,
-            step: 1.,
-            disabled: Signal::Constant(false),
-            formatted_value: None,
-            style: StyleHandle::default(),
-            label: None,
-            on_change: None,
+impl Slider {
+    fn settings(&self) -> SliderSettings {
+        SliderSettings {
+            precision: self.precision,
+            step: self.step,
+            on_change: self.on_change,
         }
     }
 }
 
 impl Template for Slider {
     fn build(&self, builder: &mut TemplateContext) {
+        let slots = SliderSlots {
+            value: SignalSlot::new(builder, self.value.clone()),
+            min: SignalSlot::new(builder, self.min.clone()),
+            max: SignalSlot::new(builder, self.max.clone()),
+            settings: builder.create_mutable(self.settings()),
+        };
         let mut slider = builder.spawn((
             MaterialNode::<SliderRectMaterial>::default(),
             Name::new("Slider"),
             Hovering::default(),
             Styles((typography::text_default, style_slider, self.style.clone())),
+            slots,
         ));
 
-        let min = self.min;
-        let max = self.max;
-        let value = self.value;
-        let precision = self.precision;
+        let min = slots.min.signal();
+        let max = slots.max.signal();
+        let value = slots.value.signal();
+        let settings = slots.settings;
         let label = self.label.clone();
-        let step = self.step;
-        let on_change = self.on_change;
 
-        let dec_click =
-            slider.create_callback(move |world: DeferredWorld, mut commands: Commands| {
+        let dec_click = slider.create_callback({
+            let (min, max, value) = (min.clone(), max.clone(), value.clone());
+            move |world: DeferredWorld, mut commands: Commands| {
                 let min = min.get(&world);
                 let max = max.get(&world);
+                let SliderSettings {
+                    step, on_change, ..
+                } = settings.get(&world);
                 let next_value = (value.get(&world) - step).clamp(min, max);
                 if let Some(on_change) = on_change {
                     commands.run_system_with(on_change, next_value);
                 }
-            });
+            }
+        });
 
-        let inc_click =
-            slider.create_callback(move |world: DeferredWorld, mut commands: Commands| {
+        let inc_click = slider.create_callback({
+            let (min, max, value) = (min.clone(), max.clone(), value.clone());
+            move |world: DeferredWorld, mut commands: Commands| {
                 let min = min.get(&world);
                 let max = max.get(&world);
+                let SliderSettings {
+                    step, on_change, ..
+                } = settings.get(&world);
                 let next_value = (value.get(&world) + step).clamp(min, max);
                 if let Some(on_change) = on_change {
                     commands.run_system_with(on_change, next_value);
                 }
-            });
+            }
+        });
 
-        let dec_disabled = slider.create_memo(
-            move |world: DeferredWorld| value.get(&world) <= min.get(&world),
-            false,
-        );
-        let inc_disabled = slider.create_memo(
-            move |world: DeferredWorld| value.get(&world) >= max.get(&world),
-            false,
-        );
+        let dec_disabled = value.zip(&min).map_signal(|(value, min)| value <= min);
+        let inc_disabled = value.zip(&max).map_signal(|(value, max)| value >= max);
 
         slider
             .insert(
                 computations![Calc::new(
-                    move |world: DeferredWorld| (value.get(&world), min.get(&world), max.get(&world)),
+                    {
+                        let (min, max, value) = (min.clone(), max.clone(), value.clone());
+                        move |world: DeferredWorld| {
+                            (value.get(&world), min.get(&world), max.get(&world))
+                        }
+                    },
                     |(value, min, max), ent| {
                         let core_slider = CoreSlider { value, min, max };
                         let material_handle = ent
@@ -253,12 +213,18 @@ impl Template for Slider {
                         }
                     },
             )])
-            .observe(
+            .observe({
+                let value = value.clone();
                 move |mut trigger: Trigger<ValueChange<f32>>,
                       world: DeferredWorld,
                       mut commands: Commands| {
                     trigger.propagate(false);
                     let event = trigger.event();
+                    let SliderSettings {
+                        precision,
+                        on_change,
+                        ..
+                    } = settings.get(&world);
                     let rounding = f32::powi(10., precision as i32);
                     let value = value.get(&world);
                     let new_value = ((event.0 * rounding).round() / rounding)
@@ -268,8 +234,8 @@ impl Template for Slider {
                             commands.run_system_with(on_change, new_value);
                         }
                     }
-                },
-            )
+                }
+            })
             .with_children(|builder| {
                 builder
                     .spawn((
@@ -308,8 +274,10 @@ impl Template for Slider {
                                 UseInheritedTextStyles,
                                 computations![
                                     Calc::new(
-                                        move |world: DeferredWorld| value.get(&world),
-                                        move |value, ent| {
+                                        move |world: DeferredWorld| {
+                                            (value.get(&world), settings.get(&world).precision)
+                                        },
+                                        move |(value, precision), ent| {
                                             ent.entry::<Text>().and_modify(|mut text| {
                                                 text.0 = format!("{:.*}", precision, value);
                                             });
@@ -340,4 +308,25 @@ impl Template for Slider {
                     });
             });
     }
+
+    fn update(&self, prev: &Self, builder: &mut TemplateContext) -> bool {
+        // The label is spawned once, when the slider is built, and styles can't be removed once
+        // applied.
+        if self.label != prev.label || self.style != prev.style {
+            return false;
+        }
+        let Some(&slider_id) = builder.spawned().first() else {
+            return false;
+        };
+        let mut slider = builder.entity_mut(slider_id);
+        let Some(slots) = slider.get::<SliderSlots>().copied() else {
+            return false;
+        };
+        let world = slider.into_world_mut();
+        slots.value.set(world, self.value.clone());
+        slots.min.set(world, self.min.clone());
+        slots.max.set(world, self.max.clone());
+        slots.settings.set(world, self.settings());
+        true
+    }
 }
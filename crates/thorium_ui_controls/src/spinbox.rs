@@ -6,8 +6,8 @@ use bevy::{
     winit::cursor::CursorIcon,
 };
 use thorium_ui_core::{
-    computations, Cond, CreateCallback, CreateMemo, CreateMutable, DynChildOf, IntoSignal, Invoke,
-    Calc, Signal, StyleHandle, StyleTuple, Styles, Template, TemplateContext,
+    computations, observers, Calc, Cond, CreateCallback, CreateMemo, CreateMutable, DynChildOf,
+    Invoke, Signal, StyleHandle, Styles, Template, TemplateContext, TemplateProps,
 };
 
 use crate::{
@@ -76,20 +76,24 @@ fn style_spinbox_button(ec: &mut EntityCommands) {
 /// * The range of values is large or unbounded, making it difficult to select a specific value
 ///   with a slider.
 /// * There is limited horizontal space available.
+#[derive(TemplateProps)]
 pub struct SpinBox {
     /// Current slider value.
     pub value: Signal<f32>,
 
     /// Minimum slider value.
+    #[props(default = f32::MIN)]
     pub min: Signal<f32>,
 
     /// Maximum slider value.
+    #[props(default = f32::MAX)]
     pub max: Signal<f32>,
 
     /// Number of decimal places to round to (0 = integer).
     pub precision: usize,
 
     /// Amount to increment when using arrow buttons.
+    #[props(default = 1.)]
     pub step: f32,
 
     /// Whether the slider is disabled.
@@ -106,84 +110,6 @@ pub struct SpinBox {
     pub on_change: Option<SystemId<In<f32>>>,
 }
 
-impl SpinBox {
-    /// Create a new spinbox.
-    pub fn new() -> Self {
-        Self::default()
-    }
-
-    /// Set the current spinbox value.
-    pub fn value(mut self, value: impl IntoSignal<f32>) -> Self {
-        self.value = value.into_signal();
-        self
-    }
-
-    /// Set the minimum spinbox value.
-    pub fn min(mut self, min: impl IntoSignal<f32>) -> Self {
-        self.min = min.into_signal();
-        self
-    }
-
-    /// Set the maximum spinbox value.
-    pub fn max(mut self, max: impl IntoSignal<f32>) -> Self {
-        self.max = max.into_signal();
-        self
-    }
-
-    /// Set the number of decimal places to round to (0 = integer).
-    pub fn precision(mut self, precision: usize) -> Self {
-        self.precision = precision;
-        self
-    }
-
-    /// Set the amount to increment when using arrow buttons.
-    pub fn step(mut self, step: f32) -> Self {
-        self.step = step;
-        self
-    }
-
-    /// Set whether the spinbox is disabled.
-    pub fn disabled(mut self, disabled: impl IntoSignal<bool>) -> Self {
-        self.disabled = disabled.into_signal();
-        self
-    }
-
-    /// Set the signal which returns the value formatted as a string. If `None`, then a default
-    /// formatter will be used.
-    pub fn formatted_value(mut self, formatted_value: impl IntoSignal<String>) -> Self {
-        self.formatted_value = Some(formatted_value.into_signal());
-        self
-    }
-
-    /// Set the style handle for the spinbox root element.
-    pub fn style<S: StyleTuple + 'static>(mut self, style: S) -> Self {
-        self.style = style.into_handle();
-        self
-    }
-
-    /// Set the callback called when value changes.
-    pub fn on_change(mut self, on_change: SystemId<In<f32>>) -> Self {
-        self.on_change = Some(on_change);
-        self
-    }
-}
-
-impl Default for SpinBox {
-    fn default() -> Self {
-        Self {
-            value: Signal::Constant(0.),
-            min: Signal::Constant(f32::MIN),
-            max: Signal::Constant(f32::MAX),
-            precision: 0,
-            step: 1.,
-            disabled: Signal::Constant(false),
-            formatted_value: None,
-            style: StyleHandle::default(),
-            on_change: None,
-        }
-    }
-}
-
 impl Template for SpinBox {
     fn build(&self, builder: &mut TemplateContext) {
         let drag_state = builder.create_mutable::<DragState>(DragState::default());
@@ -198,39 +124,49 @@ impl Template for SpinBox {
         //     node.size().x >= 48.
         // });
 
-        let min = self.min;
-        let max = self.max;
-        let value = self.value;
+        let min = self.min.clone();
+        let max = self.max.clone();
+        let value = self.value.clone();
         let precision = self.precision;
         let step = self.step;
         let on_change = self.on_change;
 
-        let dec_click =
-            spinbox.create_callback(move |world: DeferredWorld, mut commands: Commands| {
+        let dec_click = spinbox.create_callback({
+            let (min, max, value) = (min.clone(), max.clone(), value.clone());
+            move |world: DeferredWorld, mut commands: Commands| {
                 let min = min.get(&world);
                 let max = max.get(&world);
                 let next_value = (value.get(&world) - step).clamp(min, max);
                 if let Some(on_change) = on_change {
                     commands.run_system_with(on_change, next_value);
                 }
-            });
+            }
+        });
 
-        let inc_click =
-            spinbox.create_callback(move |world: DeferredWorld, mut commands: Commands| {
+        let inc_click = spinbox.create_callback({
+            let (min, max, value) = (min.clone(), max.clone(), value.clone());
+            move |world: DeferredWorld, mut commands: Commands| {
                 let min = min.get(&world);
                 let max = max.get(&world);
                 let next_value = (value.get(&world) + step).clamp(min, max);
                 if let Some(on_change) = on_change {
                     commands.run_system_with(on_change, next_value);
                 }
-            });
+            }
+        });
 
         let dec_disabled = spinbox.create_memo(
-            move |world: DeferredWorld| value.get(&world) <= min.get(&world),
+            {
+                let (min, value) = (min.clone(), value.clone());
+                move |world: DeferredWorld| value.get(&world) <= min.get(&world)
+            },
             false,
         );
         let inc_disabled = spinbox.create_memo(
-            move |world: DeferredWorld| value.get(&world) >= max.get(&world),
+            {
+                let (max, value) = (max.clone(), value.clone());
+                move |world: DeferredWorld| value.get(&world) >= max.get(&world)
+            },
             false,
         );
 
@@ -257,70 +193,80 @@ impl Template for SpinBox {
                     Node::default(),
                     Name::new("SpinBox::Label"),
                     Styles((typography::text_default, style_spinbox_label)),
-                ))
-                .observe(
-                    move |mut trigger: Trigger<Pointer<DragStart>>, mut world: DeferredWorld| {
-                        trigger.propagate(false);
-                        let offset = value.get(&world);
-                        drag_state.set(
-                            &mut world,
-                            DragState {
-                                dragging: DragType::Dragging,
-                                offset,
-                                was_dragged: false,
-                            },
-                        );
-                    },
-                )
-                .observe(
-                    move |mut trigger: Trigger<Pointer<DragEnd>>, mut world: DeferredWorld| {
-                        trigger.propagate(false);
-                        let offset = value.get(&world);
-                        let ds = drag_state.get(&world);
-                        if ds.dragging == DragType::Dragging {
-                            drag_state.set(
-                                &mut world,
-                                DragState {
-                                    dragging: DragType::None,
-                                    offset,
-                                    was_dragged: false,
-                                },
-                            );
-                        }
-                    },
-                )
-                .observe(
-                    move |mut trigger: Trigger<Pointer<Drag>>,
-                          mut world: DeferredWorld,
-                          mut commands: Commands| {
-                        trigger.propagate(false);
-                        let ds = drag_state.get(&world);
-                        if ds.dragging == DragType::Dragging {
-                            let min = min.get(&world);
-                            let max = max.get(&world);
-                            let event = trigger.event();
-                            let new_value =
-                                ds.offset + ((event.distance.x - event.distance.y) * 0.1 * step);
-                            let rounding = f32::powi(10., precision as i32);
-                            let value = value.get(&world);
-                            let new_value = (new_value * rounding).round() / rounding;
-                            if value != new_value {
-                                if !ds.was_dragged {
+                    observers![
+                        {
+                            let value = value.clone();
+                            move |mut trigger: Trigger<Pointer<DragStart>>,
+                                  mut world: DeferredWorld| {
+                                trigger.propagate(false);
+                                let offset = value.get(&world);
+                                drag_state.set(
+                                    &mut world,
+                                    DragState {
+                                        dragging: DragType::Dragging,
+                                        offset,
+                                        was_dragged: false,
+                                    },
+                                );
+                            }
+                        },
+                        {
+                            let value = value.clone();
+                            move |mut trigger: Trigger<Pointer<DragEnd>>,
+                                  mut world: DeferredWorld| {
+                                trigger.propagate(false);
+                                let offset = value.get(&world);
+                                let ds = drag_state.get(&world);
+                                if ds.dragging == DragType::Dragging {
                                     drag_state.set(
                                         &mut world,
                                         DragState {
-                                            was_dragged: true,
-                                            ..ds
+                                            dragging: DragType::None,
+                                            offset,
+                                            was_dragged: false,
                                         },
                                     );
                                 }
-                                if let Some(on_change) = on_change {
-                                    commands.run_system_with(on_change, new_value.clamp(min, max));
+                            }
+                        },
+                        {
+                            let value = value.clone();
+                            move |mut trigger: Trigger<Pointer<Drag>>,
+                                  mut world: DeferredWorld,
+                                  mut commands: Commands| {
+                                trigger.propagate(false);
+                                let ds = drag_state.get(&world);
+                                if ds.dragging == DragType::Dragging {
+                                    let min = min.get(&world);
+                                    let max = max.get(&world);
+                                    let event = trigger.event();
+                                    let new_value = ds.offset
+                                        + ((event.distance.x - event.distance.y) * 0.1 * step);
+                                    let rounding = f32::powi(10., precision as i32);
+                                    let value = value.get(&world);
+                                    let new_value = (new_value * rounding).round() / rounding;
+                                    if value != new_value {
+                                        if !ds.was_dragged {
+                                            drag_state.set(
+                                                &mut world,
+                                                DragState {
+                                                    was_dragged: true,
+                                                    ..ds
+                                                },
+                                            );
+                                        }
+                                        if let Some(on_change) = on_change {
+                                            commands.run_system_with(
+                                                on_change,
+                                                new_value.clamp(min, max),
+                                            );
+                                        }
+                                    }
                                 }
                             }
-                        }
-                    },
-                )
+                        },
+                    ],
+                ))
                 .with_children(|builder| {
                     builder.spawn((
                         Text::new(""),
@@ -154,11 +154,12 @@ impl Template for Splitter {
         ));
         let splitter_id = splitter.id();
         let on_change = self.on_change;
-        let current_offset = self.value;
+        let current_offset = self.value.clone();
         let direction = self.direction.clone();
 
         splitter
-            .observe(
+            .observe({
+                let current_offset = current_offset.clone();
                 move |mut trigger: Trigger<Pointer<DragStart>>, mut world: DeferredWorld| {
                     // Save initial value to use as drag offset.
                     trigger.propagate(false);
@@ -170,9 +171,10 @@ impl Template for Splitter {
                             offset,
                         },
                     );
-                },
-            )
-            .observe(
+                }
+            })
+            .observe({
+                let current_offset = current_offset.clone();
                 move |mut trigger: Trigger<Pointer<DragEnd>>, mut world: DeferredWorld| {
                     trigger.propagate(false);
                     let offset = current_offset.get(&world);
@@ -183,8 +185,8 @@ impl Template for Splitter {
                             offset,
                         },
                     );
-                },
-            )
+                }
+            })
             .observe(
                 move |mut trigger: Trigger<Pointer<Cancel>>, mut world: DeferredWorld| {
                     trigger.propagate(false);
@@ -85,8 +85,8 @@ impl Swatch {
 
 impl Template for Swatch {
     fn build(&self, builder: &mut TemplateContext) {
-        let color = self.color;
-        let selected = self.selected;
+        let color = self.color.clone();
+        let selected = self.selected.clone();
         let on_click = self.on_click;
 
         builder
@@ -102,7 +102,10 @@ impl Template for Swatch {
                 )],
                 // Update the color
                 computations![Calc::new(
-                    move |world: DeferredWorld| LinearRgba::from(color.get(&world)),
+                    {
+                        let color = color.clone();
+                        move |world: DeferredWorld| LinearRgba::from(color.get(&world))
+                    },
                     |color, ent| {
                         // In order to handle the transparency grid, we need a custom shader.
                         let material_handle = ent
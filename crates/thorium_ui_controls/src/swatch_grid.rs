@@ -114,7 +114,7 @@ impl Template for SwatchGrid {
         let colors = self.colors.clone();
         let num_cells = (self.grid_size.x * self.grid_size.y) as usize;
         let grid_size = self.grid_size;
-        let selected = self.selected;
+        let selected = self.selected.clone();
         let on_change = self.on_change;
 
         let on_click = tc.create_callback_arg(move |color: In<Srgba>, mut commands: Commands| {
@@ -194,8 +194,8 @@ impl Template for ToolButton {
     fn build(&self, builder: &mut TemplateContext) {
         let mut btn = Button::new()
             .size(self.size)
-            .variant(self.variant)
-            .disabled(self.disabled)
+            .variant(self.variant.clone())
+            .disabled(self.disabled.clone())
             .tab_index(self.tab_index)
             .autofocus(self.autofocus)
             .corners(self.corners);
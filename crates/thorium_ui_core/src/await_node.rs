@@ -0,0 +1,238 @@
+use std::{fmt::Display, future::Future};
+
+use bevy::{
+    asset::LoadState,
+    ecs::{
+        bundle::{BundleEffect, DynamicBundle},
+        spawn::SpawnableList,
+        world::DeferredWorld,
+    },
+    prelude::*,
+    tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task},
+};
+
+use crate::{
+    dyn_children::Fragment,
+    effect_cell::{AnyEffect, EffectCell},
+    error_boundary::report_error,
+    owner::Owned,
+    tracking::run_tracked,
+    Computations, DynChildOf, DynChildren, OwnedBy, SpawnableListGen,
+};
+
+/// Holds the task being awaited. The task entity is owned by the [`Await`] node, so the task is
+/// dropped (and thereby cancelled) if the node is despawned before the task completes.
+#[derive(Component)]
+struct AwaitTask<T: Send + Sync + 'static>(Task<Result<T, String>>);
+
+type PollFn<T> = Box<dyn FnMut(&mut World, Entity) -> Option<Result<T, String>> + Send + Sync>;
+type SpawnWithFn<T> = Box<dyn Fn(&T, &mut World, Entity) + Send + Sync>;
+
+/// What the [`Await`] node is waiting on.
+enum AwaitSource<T: Send + 'static> {
+    Task(Task<Result<T, String>>),
+    Poll(PollFn<T>),
+}
+
+/// Control-flow node which waits for an asynchronous result: a future, a [`Task`], or an asset
+/// being loaded. While waiting, the `pending` contents are shown; once the result is available,
+/// they are replaced by the `resolved` contents, which are passed the value, or the `failed`
+/// contents, which are passed the error message.
+pub struct Await<T: Send + Sync + 'static> {
+    source: AwaitSource<T>,
+    pending: Option<Box<dyn SpawnableListGen + Send + Sync>>,
+    resolved: Option<SpawnWithFn<T>>,
+    failed: Option<SpawnWithFn<String>>,
+}
+
+impl<T: Send + Sync + 'static> Await<T> {
+    fn from_source(source: AwaitSource<T>) -> Self {
+        Self {
+            source,
+            pending: None,
+            resolved: None,
+            failed: None,
+        }
+    }
+
+    /// Wait for a future, which is spawned on the [`AsyncComputeTaskPool`].
+    pub fn future<E: Display, F: Future<Output = Result<T, E>> + Send + 'static>(
+        future: F,
+    ) -> Self {
+        let task = AsyncComputeTaskPool::get()
+            .spawn(async move { future.await.map_err(|err| err.to_string()) });
+        Self::from_source(AwaitSource::Task(task))
+    }
+
+    /// Wait for a task which has already been spawned.
+    pub fn task<E: Display + Send + 'static>(task: Task<Result<T, E>>) -> Self {
+        // Wrap the task so that the error can be converted to a message. Dropping the wrapper
+        // drops (and cancels) the inner task.
+        let task = AsyncComputeTaskPool::get()
+            .spawn(async move { task.await.map_err(|err| err.to_string()) });
+        Self::from_source(AwaitSource::Task(task))
+    }
+
+    /// Sets the contents to show while waiting.
+    pub fn pending<F: SpawnableListGen + Send + Sync + 'static>(mut self, pending: F) -> Self {
+        self.pending = Some(Box::new(pending));
+        self
+    }
+
+    /// Sets the contents to show once the value is available.
+    pub fn resolved<S: SpawnableList<DynChildOf>, F: Fn(&T) -> S + Send + Sync + 'static>(
+        mut self,
+        resolved: F,
+    ) -> Self {
+        self.resolved = Some(Box::new(move |value, world, entity| {
+            resolved(value).spawn(world, entity);
+        }));
+        self
+    }
+
+    /// Sets the contents to show if there was an error.
+    pub fn failed<S: SpawnableList<DynChildOf>, F: Fn(&str) -> S + Send + Sync + 'static>(
+        mut self,
+        failed: F,
+    ) -> Self {
+        self.failed = Some(Box::new(move |message: &String, world, entity| {
+            failed(message.as_str()).spawn(world, entity);
+        }));
+        self
+    }
+}
+
+impl<A: Asset> Await<Handle<A>> {
+    /// Wait for an asset to be loaded by the [`AssetServer`]. The resolved contents are passed
+    /// the asset handle.
+    pub fn asset(handle: Handle<A>) -> Self {
+        Self::from_source(AwaitSource::Poll(Box::new(move |world, _| {
+            match world.resource::<AssetServer>().get_load_state(&handle)? {
+                LoadState::Loaded => Some(Ok(handle.clone())),
+                LoadState::Failed(err) => Some(Err(err.to_string())),
+                _ => None,
+            }
+        })))
+    }
+}
+
+unsafe impl<T: Send + Sync + 'static> Bundle for Await<T> {
+    fn component_ids(
+        _components: &mut bevy::ecs::component::Components,
+        _ids: &mut impl FnMut(bevy::ecs::component::ComponentId),
+    ) {
+    }
+
+    fn get_component_ids(
+        _components: &bevy::ecs::component::Components,
+        _ids: &mut impl FnMut(Option<bevy::ecs::component::ComponentId>),
+    ) {
+    }
+
+    fn register_required_components(
+        _components: &mut bevy::ecs::component::Components,
+        _required_components: &mut bevy::ecs::component::RequiredComponents,
+    ) {
+    }
+}
+
+impl<T: Send + Sync + 'static> DynamicBundle for Await<T> {
+    type Effect = Self;
+
+    fn get_components(
+        self,
+        _func: &mut impl FnMut(bevy::ecs::component::StorageType, bevy::ptr::OwningPtr<'_>),
+    ) -> Self::Effect {
+        self
+    }
+}
+
+impl<T: Send + Sync + 'static> BundleEffect for Await<T> {
+    fn apply(self, entity: &mut EntityWorldMut) {
+        let poll: PollFn<T> = match self.source {
+            AwaitSource::Task(task) => {
+                let owner = entity.id();
+                let task_entity =
+                    entity.world_scope(|world| world.spawn((AwaitTask(task), OwnedBy(owner))).id());
+                Box::new(move |world, _| {
+                    let mut task = world.get_mut::<AwaitTask<T>>(task_entity)?;
+                    block_on(future::poll_once(&mut task.0))
+                })
+            }
+            AwaitSource::Poll(poll) => poll,
+        };
+        entity.insert((
+            EffectCell::new(AwaitEffect {
+                poll,
+                pending: self.pending,
+                resolved: self.resolved,
+                failed: self.failed,
+                first: true,
+                done: false,
+            }),
+            Fragment,
+        ));
+    }
+}
+
+struct AwaitEffect<T: Send + Sync + 'static> {
+    poll: PollFn<T>,
+    pending: Option<Box<dyn SpawnableListGen + Send + Sync>>,
+    resolved: Option<SpawnWithFn<T>>,
+    failed: Option<SpawnWithFn<String>>,
+    first: bool,
+    done: bool,
+}
+
+impl<T: Send + Sync + 'static> AnyEffect for AwaitEffect<T> {
+    fn update(&mut self, world: &mut World, entity: Entity) {
+        if self.done {
+            return;
+        }
+
+        // Nothing here is tracked, so until the result is available this will be polled
+        // every frame.
+        let result = run_tracked(world, entity, |world| (self.poll)(world, entity));
+        if result.is_none() && !self.first {
+            return;
+        }
+        self.first = false;
+
+        // Replace the previous contents. Once the result is available, the task is finished, so
+        // it's safe to despawn it along with anything else owned by the previous contents.
+        let mut entt = world.entity_mut(entity);
+        entt.remove::<Children>();
+        entt.despawn_related::<DynChildren>();
+        entt.despawn_related::<Computations>();
+        if result.is_some() {
+            entt.despawn_related::<Owned>();
+        }
+
+        match result {
+            None => {
+                if let Some(pending) = self.pending.as_ref() {
+                    pending.spawn(world, entity);
+                }
+            }
+            Some(Ok(value)) => {
+                self.done = true;
+                if let Some(resolved) = self.resolved.as_ref() {
+                    resolved(&value, world, entity);
+                }
+            }
+            Some(Err(message)) => {
+                self.done = true;
+                match self.failed.as_ref() {
+                    Some(failed) => {
+                        warn!("Error in await {entity}: {message}");
+                        failed(&message, world, entity);
+                    }
+                    // With no failure contents, let the nearest error boundary handle it.
+                    None => report_error(world, entity, message),
+                }
+            }
+        }
+    }
+
+    fn cleanup(&self, _world: &mut DeferredWorld, _entity: Entity) {}
+}
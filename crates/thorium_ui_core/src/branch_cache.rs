@@ -0,0 +1,130 @@
+use std::sync::PoisonError;
+
+use bevy::{prelude::*, ui::experimental::GhostNode};
+
+use crate::{dyn_children::Fragment, effect_cell::EffectCell, DynChildOf, OwnedBy};
+
+/// Cache of inactive branches for a `Cond` or `Switch` in keep-alive mode.
+///
+/// In keep-alive mode, each branch is built inside its own fragment entity. When a branch becomes
+/// inactive, rather than being despawned, its fragment is moved to a hidden "stash" entity, which
+/// preserves all of its state (local mutables, text input, scroll position and so on). If the
+/// branch becomes active again, the fragment is moved back.
+pub(crate) struct BranchCache<K: PartialEq> {
+    /// Hidden entity which holds the inactive branches.
+    stash: Option<Entity>,
+    /// The currently active branch.
+    active: Option<(K, Entity)>,
+    /// Inactive branches, least recently used first.
+    inactive: Vec<(K, Entity)>,
+    /// Maximum number of inactive branches to keep.
+    limit: Option<usize>,
+}
+
+impl<K: PartialEq> BranchCache<K> {
+    pub(crate) fn new(limit: Option<usize>) -> Self {
+        Self {
+            stash: None,
+            active: None,
+            inactive: Vec::new(),
+            limit,
+        }
+    }
+
+    /// Make the branch for `key` the active branch of `parent`, restoring it from the cache if
+    /// possible, otherwise calling `build` to build it.
+    pub(crate) fn activate(
+        &mut self,
+        world: &mut World,
+        parent: Entity,
+        key: K,
+        build: impl FnOnce(&mut World, Entity),
+    ) {
+        if self
+            .active
+            .as_ref()
+            .is_some_and(|(active, _)| *active == key)
+        {
+            return;
+        }
+
+        // Stash the outgoing branch.
+        if let Some((active_key, holder)) = self.active.take() {
+            let stash = self.stash(world, parent);
+            if let Ok(mut entt) = world.get_entity_mut(holder) {
+                entt.insert(DynChildOf(stash));
+                self.inactive.push((active_key, holder));
+            }
+        }
+
+        // Restore or build the incoming branch.
+        let holder = match self.inactive.iter().position(|(k, _)| *k == key) {
+            Some(index) => {
+                let (_, holder) = self.inactive.remove(index);
+                world.entity_mut(holder).insert(DynChildOf(parent));
+                holder
+            }
+            None => {
+                let holder = world.spawn((GhostNode, Fragment, DynChildOf(parent))).id();
+                build(world, holder);
+                holder
+            }
+        };
+        self.active = Some((key, holder));
+
+        if let Some(limit) = self.limit {
+            while self.inactive.len() > limit {
+                let (_, holder) = self.inactive.remove(0);
+                despawn_if_exists(world, holder);
+            }
+        }
+    }
+
+    /// Despawn all inactive branches.
+    pub(crate) fn evict(&mut self, world: &mut World) {
+        for (_, holder) in self.inactive.drain(..) {
+            despawn_if_exists(world, holder);
+        }
+    }
+
+    /// Returns the stash entity, creating it if needed. The stash is a hidden UI node, owned by
+    /// the parent so that it is despawned (along with all of the cached branches) with it.
+    fn stash(&mut self, world: &mut World, parent: Entity) -> Entity {
+        *self.stash.get_or_insert_with(|| {
+            world
+                .spawn((
+                    Node {
+                        display: Display::None,
+                        ..default()
+                    },
+                    Visibility::Hidden,
+                    OwnedBy(parent),
+                    Name::new("BranchCache"),
+                ))
+                .id()
+        })
+    }
+}
+
+fn despawn_if_exists(world: &mut World, entity: Entity) {
+    if let Ok(entt) = world.get_entity_mut(entity) {
+        entt.despawn();
+    }
+}
+
+/// Command which despawns the inactive branches cached by a `Cond` or `Switch` which is in
+/// keep-alive mode. Does nothing for other entities.
+pub struct EvictCachedBranches(pub Entity);
+
+impl Command for EvictCachedBranches {
+    fn apply(self, world: &mut World) {
+        let Some(cell) = world.get::<EffectCell>(self.0) else {
+            return;
+        };
+        let effect = cell.effect.clone();
+        effect
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .evict(world, self.0);
+    }
+}
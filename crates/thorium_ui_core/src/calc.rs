@@ -10,6 +10,8 @@ use bevy::{
 use crate::{
     computations::ComputationOf,
     effect_cell::{AnyEffect, EffectCell},
+    error_boundary::report_error,
+    tracking::run_tracked,
 };
 
 pub struct CalcEffect<P, M, EffectFn: Fn(P, &mut EntityWorldMut)> {
@@ -28,11 +30,17 @@ impl<P: 'static + PartialEq + Send + Sync + Clone, M, EffectFn: Fn(P, &mut Entit
         };
         let owner = owner.get();
         // Run the dependencies and see if the result changed.
-        let deps = world.run_system(self.deps_sys).ok();
-        if deps.is_some() && deps != self.deps {
-            self.deps = deps.clone();
+        let deps = match run_tracked(world, entity, |world| world.run_system(self.deps_sys)) {
+            Ok(deps) => deps,
+            Err(err) => {
+                report_error(world, entity, err.to_string());
+                return;
+            }
+        };
+        if self.deps.as_ref() != Some(&deps) {
+            self.deps = Some(deps.clone());
             // Run the effect
-            (self.effect_fn)(deps.unwrap(), &mut world.entity_mut(owner));
+            (self.effect_fn)(deps, &mut world.entity_mut(owner));
         }
     }
 
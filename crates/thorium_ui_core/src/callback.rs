@@ -10,7 +10,7 @@ use crate::{owner::OwnedBy, TemplateContext};
 
 #[derive(Component)]
 #[component(on_remove = on_remove_callback_cell::<I>, storage = "SparseSet")]
-pub struct CallbackCell<I: SystemInput + Send + Sync>(SystemId<I, ()>);
+pub struct CallbackCell<I: SystemInput + Send + Sync>(pub(crate) SystemId<I, ()>);
 
 fn on_remove_callback_cell<I: SystemInput + Send + Sync + 'static>(
     mut world: DeferredWorld,
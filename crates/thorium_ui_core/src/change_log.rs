@@ -0,0 +1,117 @@
+use std::collections::VecDeque;
+
+/// Maximum number of changes retained by a [`ChangeLog`]. Consumers which fall further behind
+/// than this have to resynchronize from scratch.
+const LOG_LIMIT: usize = 256;
+
+/// A bounded log of fine-grained changes made to a reactive collection. Each change advances the
+/// log's version by one, so consumers can remember the version they last saw and ask for the
+/// changes made since then.
+pub(crate) struct ChangeLog<C> {
+    changes: VecDeque<C>,
+    version: usize,
+}
+
+impl<C: Clone> ChangeLog<C> {
+    pub(crate) fn new() -> Self {
+        Self {
+            changes: VecDeque::new(),
+            version: 0,
+        }
+    }
+
+    /// The current version of the collection.
+    pub(crate) fn version(&self) -> usize {
+        self.version
+    }
+
+    /// Record a change.
+    pub(crate) fn record(&mut self, change: C) {
+        if self.changes.len() >= LOG_LIMIT {
+            self.changes.pop_front();
+        }
+        self.changes.push_back(change);
+        self.version += 1;
+    }
+
+    /// Record a change which can't be described incrementally, such as replacing the whole
+    /// collection. This discards the log, so all consumers will need to resynchronize.
+    pub(crate) fn reset(&mut self) {
+        self.changes.clear();
+        self.version += 1;
+    }
+
+    /// Returns the changes made since `version`, or `None` if they are no longer available.
+    pub(crate) fn since(&self, version: usize) -> Option<Vec<C>> {
+        let count = self.version.checked_sub(version)?;
+        if count > self.changes.len() {
+            return None;
+        }
+        Some(
+            self.changes
+                .range(self.changes.len() - count..)
+                .cloned()
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChangeLog, LOG_LIMIT};
+
+    #[test]
+    fn test_empty() {
+        let log: ChangeLog<i32> = ChangeLog::new();
+
+        assert_eq!(log.version(), 0);
+        assert_eq!(log.since(0), Some(vec![]));
+    }
+
+    #[test]
+    fn test_since() {
+        let mut log = ChangeLog::new();
+        log.record(1);
+        log.record(2);
+        log.record(3);
+
+        assert_eq!(log.version(), 3);
+        assert_eq!(log.since(0), Some(vec![1, 2, 3]));
+        assert_eq!(log.since(1), Some(vec![2, 3]));
+        assert_eq!(log.since(3), Some(vec![]));
+    }
+
+    #[test]
+    fn test_since_future() {
+        let mut log = ChangeLog::new();
+        log.record(1);
+
+        assert_eq!(log.since(2), None);
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut log = ChangeLog::new();
+        log.record(1);
+        log.reset();
+
+        assert_eq!(log.version(), 2);
+        assert_eq!(log.since(0), None);
+        assert_eq!(log.since(1), None);
+        assert_eq!(log.since(2), Some(vec![]));
+
+        log.record(3);
+        assert_eq!(log.since(2), Some(vec![3]));
+    }
+
+    #[test]
+    fn test_limit() {
+        let mut log = ChangeLog::new();
+        for change in 0..=LOG_LIMIT {
+            log.record(change);
+        }
+
+        assert_eq!(log.since(0), None);
+        assert_eq!(log.since(1), Some((1..=LOG_LIMIT).collect()));
+    }
+}
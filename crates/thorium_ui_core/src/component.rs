@@ -0,0 +1,579 @@
+use std::{
+    any::Any,
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
+
+use bevy::{
+    ecs::{
+        bundle::{BundleEffect, DynamicBundle},
+        system::SystemId,
+        world::DeferredWorld,
+    },
+    prelude::*,
+};
+
+use crate::{
+    callback::CallbackCell,
+    derived::DerivedSignal,
+    dyn_children::Fragment,
+    effect_cell::{AnyEffect, EffectCell},
+    error_boundary::report_error,
+    lens::LensSignal,
+    reconcile,
+    tracking::run_tracked,
+    CreateMemo, CreateMutable, Memo, Mutable, OwnedBy, ReadMemo, ReadMutable, ReadTracked,
+    Template, TemplateContext,
+};
+
+/// The properties of a [`FunctionComponent`] instance. Inserting a new `ComponentProps` on the
+/// instance entity re-runs the component function with the new properties; the component's hook
+/// state is preserved.
+#[derive(Component)]
+pub struct ComponentProps<P: Send + Sync + 'static>(Arc<P>);
+
+impl<P: Send + Sync + 'static> ComponentProps<P> {
+    pub fn new(props: P) -> Self {
+        Self(Arc::new(props))
+    }
+
+    /// The current property values.
+    pub fn get(&self) -> &P {
+        &self.0
+    }
+}
+
+/// Context passed to a component function. This derefs to the [`TemplateContext`] for the
+/// component's output, and also provides the component's hooks.
+///
+/// Hooks are identified by the order in which they are called, so a component function must
+/// call the same hooks in the same order every time it runs: don't call hooks conditionally or
+/// in loops whose length can change.
+pub struct ComponentContext<'w, 'h> {
+    tc: TemplateContext<'w>,
+    hooks: &'h mut Vec<HookSlot>,
+    next_hook: usize,
+}
+
+/// The value of a hook, and the entity which holds its resources, such as the cell of a
+/// mutable or the one-shot system of a callback.
+struct HookSlot {
+    value: Box<dyn Any + Send + Sync>,
+    entity: Entity,
+}
+
+impl<'w> Deref for ComponentContext<'w, '_> {
+    type Target = TemplateContext<'w>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.tc
+    }
+}
+
+impl DerefMut for ComponentContext<'_, '_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.tc
+    }
+}
+
+impl ComponentContext<'_, '_> {
+    /// The entity of the component instance, which owns the entities created by hooks.
+    pub fn entity(&self) -> Entity {
+        self.tc.target_entity()
+    }
+
+    /// Returns the value of the next hook slot, creating it on the first run. `create` returns
+    /// the hook's value, and the entity which holds its resources.
+    fn use_hook<H: Clone + Send + Sync + 'static>(
+        &mut self,
+        create: impl FnOnce(&mut World, Entity) -> (H, Entity),
+    ) -> H {
+        let index = self.next_hook;
+        self.next_hook += 1;
+        let owner = self.entity();
+        if let Some(slot) = self.hooks.get(index) {
+            if let Some(hook) = slot.value.downcast_ref::<H>() {
+                return hook.clone();
+            }
+            // The hooks were called in a different order. Report it, and replace the slot so
+            // that the component can still render.
+            let replaced = slot.entity;
+            let world = self.tc.world_mut();
+            report_error(
+                world,
+                owner,
+                format!(
+                    "Hook {index} has a different type than on the previous run; \
+                    hooks must be called in the same order every time the component runs"
+                ),
+            );
+            if let Ok(entt) = world.get_entity_mut(replaced) {
+                entt.despawn();
+            }
+            let (hook, entity) = create(world, owner);
+            self.hooks[index] = HookSlot {
+                value: Box::new(hook.clone()),
+                entity,
+            };
+            return hook;
+        }
+        let (hook, entity) = create(self.tc.world_mut(), owner);
+        self.hooks.push(HookSlot {
+            value: Box::new(hook.clone()),
+            entity,
+        });
+        hook
+    }
+
+    /// Local state for the component. On the first run, creates a [`Mutable`] holding `init`;
+    /// on later runs, returns the same mutable and ignores `init`. Writing to the mutable
+    /// re-runs the component if the component function read it.
+    pub fn use_state<T: Send + Sync + 'static>(&mut self, init: T) -> Mutable<T> {
+        self.use_hook(move |world, owner| {
+            let mutable = world.create_mutable(init);
+            world.entity_mut(mutable.id()).insert(OwnedBy(owner));
+            (mutable, mutable.id())
+        })
+    }
+
+    /// A memoized computation. On the first run, creates a [`Memo`] from `factory`; on later
+    /// runs, returns the same memo.
+    pub fn use_memo<
+        M: Send + Sync + 'static,
+        P: PartialEq + Clone + Send + Sync + 'static,
+        I: IntoSystem<(), P, M> + Send + Sync + 'static,
+    >(
+        &mut self,
+        factory: I,
+        default_value: P,
+    ) -> Memo<P> {
+        self.use_hook(move |world, owner| {
+            let memo = world.entity_mut(owner).create_memo(factory, default_value);
+            let entity = memo.entity();
+            (memo, entity)
+        })
+    }
+
+    /// A side effect. On the first run, registers `effect` as a system which runs once, and
+    /// then again whenever any of the signals, components or resources it read change. The
+    /// effect is removed when the component is despawned.
+    pub fn use_effect<M, I: IntoSystem<(), (), M> + Send + Sync + 'static>(&mut self, effect: I) {
+        self.use_hook(move |world, owner| {
+            let system = world.register_system(effect);
+            let effect = world
+                .spawn((EffectCell::new(HookEffect { system }), OwnedBy(owner)))
+                .id();
+            ((), effect)
+        })
+    }
+
+    /// A callback. On the first run, registers `callback` as a one-shot system; on later runs,
+    /// returns the same system id.
+    pub fn use_callback<M, I: IntoSystem<(), (), M> + 'static>(
+        &mut self,
+        callback: I,
+    ) -> SystemId<(), ()> {
+        self.use_hook(move |world, owner| {
+            let system = world.register_system(callback);
+            let cell = world.spawn((CallbackCell(system), OwnedBy(owner))).id();
+            (system, cell)
+        })
+    }
+
+    /// A callback which takes an argument. On the first run, registers `callback` as a one-shot
+    /// system; on later runs, returns the same system id.
+    pub fn use_callback_arg<M, A: Send + Sync + 'static, I: IntoSystem<In<A>, (), M> + 'static>(
+        &mut self,
+        callback: I,
+    ) -> SystemId<In<A>, ()> {
+        self.use_hook(move |world, owner| {
+            let system = world.register_system(callback);
+            let cell = world.spawn((CallbackCell(system), OwnedBy(owner))).id();
+            (system, cell)
+        })
+    }
+}
+
+impl ReadMutable for ComponentContext<'_, '_> {
+    fn read_mutable<T>(&self, mutable: &Mutable<T>) -> T
+    where
+        T: Send + Sync + Copy + 'static,
+    {
+        self.tc.world().read_mutable(mutable)
+    }
+
+    fn read_mutable_clone<T>(&self, mutable: &Mutable<T>) -> T
+    where
+        T: Send + Sync + Clone + 'static,
+    {
+        self.tc.world().read_mutable_clone(mutable)
+    }
+
+    fn read_mutable_as_ref<T>(&self, mutable: &Mutable<T>) -> &T
+    where
+        T: Send + Sync + 'static,
+    {
+        self.tc.world().read_mutable_as_ref(mutable)
+    }
+
+    fn read_mutable_map<T, U, F: Fn(&T) -> U>(&self, mutable: &Mutable<T>, f: F) -> U
+    where
+        T: Send + Sync + 'static,
+    {
+        self.tc.world().read_mutable_map(mutable, f)
+    }
+
+    fn read_lens_map<U, V, F: FnOnce(&U) -> V>(&self, lens: &LensSignal<U>, f: F) -> V {
+        self.tc.world().read_lens_map(lens, f)
+    }
+}
+
+impl ReadMemo for ComponentContext<'_, '_> {
+    fn read_memo<P: Clone + Send + Sync + 'static>(&self, memo: Memo<P>) -> P {
+        self.tc.world().read_memo(memo)
+    }
+
+    fn read_memo_map<P, U, F: Fn(&P) -> U>(&self, memo: &Memo<P>, f: F) -> U
+    where
+        P: Send + Sync + 'static,
+    {
+        self.tc.world().read_memo_map(memo, f)
+    }
+
+    fn read_derived<P>(&self, derived: &DerivedSignal<P>) -> P {
+        self.tc.world().read_derived(derived)
+    }
+}
+
+/// Effect created by [`ComponentContext::use_effect`].
+struct HookEffect {
+    system: SystemId<(), ()>,
+}
+
+impl AnyEffect for HookEffect {
+    fn update(&mut self, world: &mut World, entity: Entity) {
+        if let Err(err) = run_tracked(world, entity, |world| world.run_system(self.system)) {
+            report_error(world, entity, err.to_string());
+        }
+    }
+
+    fn cleanup(&self, world: &mut DeferredWorld, _entity: Entity) {
+        world.commands().unregister_system(self.system);
+    }
+}
+
+/// A component defined by a function, rather than by a struct implementing
+/// [`Template`](crate::Template):
+///
+/// ```rust,ignore
+/// fn counter(cx: &mut ComponentContext, props: &CounterProps) {
+///     let count = cx.use_state(props.initial);
+///     let increment = cx.use_callback(move |mut world: DeferredWorld| {
+///         count.update(&mut world, |mut count| *count += 1);
+///     });
+///     let label = format!("{}: {}", props.label, count.get(cx));
+///     cx.spawn(Text::new(label));
+///     cx.invoke(Button::new().label("+").on_click(increment));
+/// }
+///
+/// builder.spawn(FunctionComponent::new(counter, CounterProps { label: "Apples", initial: 3 }));
+/// ```
+///
+/// The function re-runs whenever any of the signals it read change, or whenever new
+/// [`ComponentProps`] are inserted on the instance. Each time it runs, the children from the
+/// previous run are despawned and rebuilt, except for keyed template invocations (see
+/// [`Invoke::keyed`](crate::Invoke::keyed)), which are updated in place. State created by hooks
+/// persists for as long as the instance exists.
+///
+/// A function component is also a [`Template`]. When it is invoked with a key, for example with
+/// `cx.invoke_keyed("counter", FunctionComponent::new(counter, props))` from a parent component,
+/// a rebuild of the parent passes the new properties to the existing instance, rather than
+/// spawning a new instance and losing its hook state. The render function of the existing
+/// instance is kept.
+pub struct FunctionComponent<P: Send + Sync + 'static, F> {
+    render: Arc<F>,
+    props: Arc<P>,
+}
+
+impl<P: Send + Sync + 'static, F: Fn(&mut ComponentContext, &P) + Send + Sync + 'static>
+    FunctionComponent<P, F>
+{
+    /// Construct an instance of a function component.
+    pub fn new(render: F, props: P) -> Self {
+        Self {
+            render: Arc::new(render),
+            props: Arc::new(props),
+        }
+    }
+}
+
+impl<P: Send + Sync + 'static, F: Fn(&mut ComponentContext, &P) + Send + Sync + 'static> Template
+    for FunctionComponent<P, F>
+{
+    fn build(&self, tc: &mut TemplateContext) {
+        tc.spawn(FunctionComponent {
+            render: self.render.clone(),
+            props: self.props.clone(),
+        });
+    }
+
+    fn update(&self, _prev: &Self, tc: &mut TemplateContext) -> bool {
+        let Some(&instance) = tc.spawned().first() else {
+            return false;
+        };
+        tc.entity_mut(instance)
+            .insert(ComponentProps(self.props.clone()));
+        true
+    }
+
+    fn size_hint(&self) -> usize {
+        1
+    }
+}
+
+impl<P: Send + Sync + 'static, F: Fn(&mut ComponentContext, &P) + Send + Sync + 'static>
+    BundleEffect for FunctionComponent<P, F>
+{
+    fn apply(self, entity: &mut EntityWorldMut) {
+        entity.insert((
+            EffectCell::new(FunctionComponentEffect::<P, F> {
+                render: self.render,
+                hooks: Vec::new(),
+                first: true,
+                marker: std::marker::PhantomData,
+            }),
+            ComponentProps(self.props),
+            Fragment,
+        ));
+    }
+}
+
+impl<P: Send + Sync + 'static, F: Fn(&mut ComponentContext, &P) + Send + Sync + 'static>
+    DynamicBundle for FunctionComponent<P, F>
+{
+    type Effect = Self;
+
+    fn get_components(
+        self,
+        _func: &mut impl FnMut(bevy::ecs::component::StorageType, bevy::ptr::OwningPtr<'_>),
+    ) -> Self::Effect {
+        self
+    }
+}
+
+unsafe impl<P: Send + Sync + 'static, F: Fn(&mut ComponentContext, &P) + Send + Sync + 'static>
+    Bundle for FunctionComponent<P, F>
+{
+    fn component_ids(
+        _components: &mut bevy::ecs::component::Components,
+        _ids: &mut impl FnMut(bevy::ecs::component::ComponentId),
+    ) {
+    }
+
+    fn get_component_ids(
+        _components: &bevy::ecs::component::Components,
+        _ids: &mut impl FnMut(Option<bevy::ecs::component::ComponentId>),
+    ) {
+    }
+
+    fn register_required_components(
+        _components: &mut bevy::ecs::component::Components,
+        _required_components: &mut bevy::ecs::component::RequiredComponents,
+    ) {
+    }
+}
+
+struct FunctionComponentEffect<P, F> {
+    render: Arc<F>,
+    hooks: Vec<HookSlot>,
+    first: bool,
+    marker: std::marker::PhantomData<fn(&P)>,
+}
+
+impl<P: Send + Sync + 'static, F: Fn(&mut ComponentContext, &P) + Send + Sync + 'static> AnyEffect
+    for FunctionComponentEffect<P, F>
+{
+    fn update(&mut self, world: &mut World, entity: Entity) {
+        let hooks = &mut self.hooks;
+        let render = &*self.render;
+        let mut hook_count = None;
+        // Replace the output of the previous run, reusing its keyed template instances.
+        reconcile::rebuild_children(world, entity, |world| {
+            hook_count = run_tracked(world, entity, |world| {
+                let props = world
+                    .read_component::<ComponentProps<P>>(entity)
+                    .map(|props| props.0.clone())?;
+                let mut cx = ComponentContext {
+                    tc: TemplateContext::new(entity, world),
+                    hooks,
+                    next_hook: 0,
+                };
+                render(&mut cx, &props);
+                Some(cx.next_hook)
+            });
+        });
+
+        let first = std::mem::replace(&mut self.first, false);
+        if !first && hook_count.is_some_and(|count| count != self.hooks.len()) {
+            report_error(
+                world,
+                entity,
+                "Component called a different number of hooks than on the previous run".to_string(),
+            );
+        }
+    }
+
+    fn cleanup(&self, _world: &mut DeferredWorld, _entity: Entity) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{ecs::system::SystemId, prelude::*};
+
+    use super::{ComponentContext, ComponentProps, FunctionComponent};
+    use crate::{
+        effect_cell::update_effects, CreateMutable, DynChildren, Mutable, Template,
+        TemplateContext, WriteMutable,
+    };
+
+    /// The hooks and children seen by each run of a test component.
+    #[derive(Resource, Default)]
+    struct Runs(Vec<Run>);
+
+    #[derive(Clone, Copy, PartialEq, Debug)]
+    struct Run {
+        state: Entity,
+        callback: SystemId,
+    }
+
+    struct Props {
+        trigger: Mutable<i32>,
+    }
+
+    struct Row;
+
+    impl Template for Row {
+        fn build(&self, tc: &mut TemplateContext) {
+            tc.spawn(());
+        }
+    }
+
+    fn counter(cx: &mut ComponentContext, props: &Props) {
+        props.trigger.get(cx);
+        let state = cx.use_state(0);
+        let callback = cx.use_callback(|| {});
+        cx.invoke_keyed("row", Row);
+        cx.world_mut().resource_mut::<Runs>().0.push(Run {
+            state: state.id(),
+            callback,
+        });
+    }
+
+    fn reordered(cx: &mut ComponentContext, props: &Props) {
+        let state = if props.trigger.get(cx) == 0 {
+            cx.use_state(0).id()
+        } else {
+            cx.use_state(String::new()).id()
+        };
+        let callback = cx.use_callback(|| {});
+        cx.world_mut()
+            .resource_mut::<Runs>()
+            .0
+            .push(Run { state, callback });
+    }
+
+    /// The state and props seen by each run of a child component.
+    #[derive(Resource, Default)]
+    struct ChildRuns(Vec<(Entity, i32)>);
+
+    struct ChildProps {
+        value: i32,
+    }
+
+    fn child(cx: &mut ComponentContext, props: &ChildProps) {
+        let state = cx.use_state(0);
+        cx.world_mut()
+            .resource_mut::<ChildRuns>()
+            .0
+            .push((state.id(), props.value));
+    }
+
+    fn parent(cx: &mut ComponentContext, props: &Props) {
+        let value = props.trigger.get(cx);
+        cx.invoke_keyed("child", FunctionComponent::new(child, ChildProps { value }));
+    }
+
+    fn frame(world: &mut World) {
+        world.increment_change_tick();
+        update_effects(world);
+    }
+
+    fn setup(render: fn(&mut ComponentContext, &Props)) -> (World, Entity, Mutable<i32>) {
+        let mut world = World::new();
+        world.init_resource::<Runs>();
+        world.init_resource::<ChildRuns>();
+        let trigger = world.create_mutable(0);
+        let component = world
+            .spawn(FunctionComponent::new(render, Props { trigger }))
+            .id();
+        frame(&mut world);
+        (world, component, trigger)
+    }
+
+    #[test]
+    fn test_hooks_stable() {
+        let (mut world, component, trigger) = setup(counter);
+        let row = world.get::<DynChildren>(component).unwrap()[0];
+
+        // Re-run because a signal changed, and because the props were replaced.
+        world.write_mutable(trigger.id(), 1);
+        frame(&mut world);
+        world
+            .entity_mut(component)
+            .insert(ComponentProps::new(Props { trigger }));
+        frame(&mut world);
+
+        let runs = &world.resource::<Runs>().0;
+        assert_eq!(runs.len(), 3);
+        assert!(runs.iter().all(|run| *run == runs[0]));
+        assert!(world.get_entity(runs[0].state).is_ok());
+
+        // The keyed row was reused rather than rebuilt.
+        assert_eq!(world.get::<DynChildren>(component).unwrap()[..], [row]);
+    }
+
+    #[test]
+    fn test_child_keeps_state() {
+        let (mut world, _, trigger) = setup(parent);
+        // The child is spawned during the first frame, and runs no later than the next one.
+        frame(&mut world);
+
+        world.write_mutable(trigger.id(), 1);
+        frame(&mut world);
+
+        // The parent's rebuild passed the new props to the same child instance.
+        let runs = &world.resource::<ChildRuns>().0;
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].1, 0);
+        assert_eq!(runs[1], (runs[0].0, 1));
+        assert!(world.get_entity(runs[0].0).is_ok());
+    }
+
+    #[test]
+    fn test_hook_type_changed() {
+        let (mut world, _, trigger) = setup(reordered);
+
+        world.write_mutable(trigger.id(), 1);
+        frame(&mut world);
+
+        // The mismatched slot is replaced, and the rest of the function still runs.
+        let runs = &world.resource::<Runs>().0;
+        assert_eq!(runs.len(), 2);
+        assert_ne!(runs[1].state, runs[0].state);
+        assert_eq!(runs[1].callback, runs[0].callback);
+
+        // The replaced hook's mutable is despawned.
+        assert!(world.get_entity(runs[0].state).is_err());
+        assert!(world.get_entity(runs[1].state).is_ok());
+    }
+}
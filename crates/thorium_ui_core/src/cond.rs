@@ -8,10 +8,13 @@ use bevy::{
 };
 
 use crate::{
+    branch_cache::BranchCache,
     dyn_children::Fragment,
     effect_cell::{AnyEffect, EffectCell},
-    owner::Owned,
-    Computations, DynChildren, SpawnableListGen,
+    error_boundary::report_error,
+    reconcile::rebuild,
+    tracking::run_tracked,
+    SpawnableListGen,
 };
 
 pub struct Cond<
@@ -23,6 +26,7 @@ pub struct Cond<
     test_fn: TestFn,
     pos: Pos,
     neg: Neg,
+    cache: Option<BranchCache<bool>>,
     marker: std::marker::PhantomData<M>,
 }
 
@@ -38,9 +42,19 @@ impl<
             test_fn,
             pos,
             neg,
+            cache: None,
             marker: std::marker::PhantomData,
         }
     }
+
+    /// Keep the inactive branch alive rather than despawning it. When the condition flips, the
+    /// outgoing branch is detached and hidden, preserving its state, and is restored if the
+    /// condition flips back. Use [`EvictCachedBranches`](crate::EvictCachedBranches) to despawn
+    /// the inactive branch explicitly.
+    pub fn keep_alive(mut self) -> Self {
+        self.cache = Some(BranchCache::new(None));
+        self
+    }
 }
 
 unsafe impl<
@@ -102,6 +116,7 @@ impl<
                 test_sys,
                 pos: self.pos,
                 neg: self.neg,
+                cache: self.cache,
                 marker: std::marker::PhantomData::<M>,
             }),
             // GhostNode::default(),
@@ -117,32 +132,50 @@ struct CondEffect<M, Pos: SpawnableListGen, Neg: SpawnableListGen> {
     test_sys: SystemId<(), bool>,
     pos: Pos,
     neg: Neg,
+    cache: Option<BranchCache<bool>>,
     marker: std::marker::PhantomData<M>,
 }
 
 impl<M, Pos: SpawnableListGen, Neg: SpawnableListGen> AnyEffect for CondEffect<M, Pos, Neg> {
     fn update(&mut self, world: &mut World, entity: Entity) {
         // Run the condition and see if the result changed.
-        let test = world.run_system(self.test_sys);
-        if let Ok(test) = test {
-            if self.state != test || self.first {
-                self.first = false;
-                self.state = test;
-                let mut entt = world.entity_mut(entity);
-                entt.remove::<Children>();
-                entt.despawn_related::<DynChildren>();
-                entt.despawn_related::<Computations>();
-                entt.despawn_related::<Owned>();
+        let test = match run_tracked(world, entity, |world| world.run_system(self.test_sys)) {
+            Ok(test) => test,
+            Err(err) => {
+                report_error(world, entity, err.to_string());
+                return;
+            }
+        };
+        if self.state != test || self.first {
+            self.first = false;
+            self.state = test;
+            if let Some(cache) = self.cache.as_mut() {
+                cache.activate(world, entity, test, |world, holder| {
+                    if test {
+                        self.pos.spawn(world, holder);
+                    } else {
+                        self.neg.spawn(world, holder);
+                    }
+                });
+                return;
+            }
+            rebuild(world, entity, |world| {
                 if test {
                     self.pos.spawn(world, entity);
                 } else {
                     self.neg.spawn(world, entity);
                 }
-            }
+            });
         }
     }
 
     fn cleanup(&self, world: &mut DeferredWorld, _entity: Entity) {
         world.commands().unregister_system(self.test_sys);
     }
+
+    fn evict(&mut self, world: &mut World, _entity: Entity) {
+        if let Some(cache) = self.cache.as_mut() {
+            cache.evict(world);
+        }
+    }
 }
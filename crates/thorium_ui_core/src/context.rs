@@ -0,0 +1,80 @@
+use bevy::{ecs::world::DeferredWorld, prelude::*};
+
+use crate::{portal::PortalContents, tracking::track_component, DynChildOf, TemplateContext};
+
+/// Holds a context value provided by an entity to its descendants.
+#[derive(Component)]
+pub(crate) struct ContextCell<T>(pub(crate) T);
+
+/// Search for the nearest ancestor of `entity` (including `entity` itself) which provides a
+/// context of type `T`. The dynamic hierarchy (`DynChildOf`) is searched first, since it includes
+/// fragments and it is available before the children have been flattened. The contents of a
+/// [`Portal`](crate::Portal) continue the search from the portal. Otherwise, we follow `ChildOf`.
+///
+/// If `track` is true, then every entity visited is added to the current tracking scope, so that
+/// the caller will re-run if the context is changed, or if a nearer provider is added.
+fn find_context<T: Send + Sync + 'static>(
+    world: &World,
+    mut entity: Entity,
+    track: bool,
+) -> Option<&T> {
+    loop {
+        let entt = world.get_entity(entity).ok()?;
+        let cell = entt.get::<ContextCell<T>>();
+        if track {
+            track_component::<ContextCell<T>>(world, entity, cell.is_some());
+        }
+        if let Some(cell) = cell {
+            return Some(&cell.0);
+        }
+        if let Some(dyn_parent) = entt.get::<DynChildOf>() {
+            entity = dyn_parent.get();
+        } else if let Some(portal) = entt.get::<PortalContents>() {
+            entity = portal.0;
+        } else if let Some(parent) = entt.get::<ChildOf>() {
+            entity = parent.get();
+        } else {
+            return None;
+        }
+    }
+}
+
+impl TemplateContext<'_> {
+    /// Provide a context value to all descendants of the target entity. Descendants can look up
+    /// the value using `use_context`. Providing a value of the same type again replaces the
+    /// previous value, and any reactions which read it will re-run.
+    ///
+    /// To make the value itself reactive, provide a [`Signal`](crate::Signal).
+    pub fn provide_context<T: Send + Sync + 'static>(&mut self, value: T) -> &mut Self {
+        let target = self.target_entity();
+        self.world_mut()
+            .entity_mut(target)
+            .insert(ContextCell(value));
+        self
+    }
+
+    /// Look up a context value provided by the target entity or one of its ancestors. This
+    /// is a one-time read; to react to changes, use [`UseContext`] within a reaction.
+    pub fn use_context<T: Clone + Send + Sync + 'static>(&self) -> Option<T> {
+        find_context::<T>(self.world(), self.target_entity(), false).cloned()
+    }
+}
+
+/// Trait for looking up context values within a reaction.
+pub trait UseContext {
+    /// Look up a context value provided by `entity` or one of its ancestors. The lookup is added
+    /// to the current tracking scope.
+    fn use_context<T: Clone + Send + Sync + 'static>(&self, entity: Entity) -> Option<T>;
+}
+
+impl UseContext for World {
+    fn use_context<T: Clone + Send + Sync + 'static>(&self, entity: Entity) -> Option<T> {
+        find_context::<T>(self, entity, true).cloned()
+    }
+}
+
+impl UseContext for DeferredWorld<'_> {
+    fn use_context<T: Clone + Send + Sync + 'static>(&self, entity: Entity) -> Option<T> {
+        find_context::<T>(self, entity, true).cloned()
+    }
+}
@@ -0,0 +1,35 @@
+use std::{fmt::Debug, sync::Arc};
+
+use bevy::prelude::*;
+
+/// A signal whose value is computed by a closure each time it is read. Derived signals are cheap:
+/// they don't spawn an entity or register a system, and any signals read by the closure are
+/// added to the tracking scope of whoever reads the derived signal.
+///
+/// Because the closure is re-run on every read, expensive computations should be memoized with
+/// [`Signal::memoize`](crate::Signal::memoize).
+pub struct DerivedSignal<T>(Arc<dyn Fn(&World) -> T + Send + Sync>);
+
+impl<T> DerivedSignal<T> {
+    /// Construct a new derived signal from a closure.
+    pub fn new<F: Fn(&World) -> T + Send + Sync + 'static>(compute: F) -> Self {
+        Self(Arc::new(compute))
+    }
+
+    /// Compute the value of the signal.
+    pub(crate) fn get(&self, world: &World) -> T {
+        (self.0)(world)
+    }
+}
+
+impl<T> Clone for DerivedSignal<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> Debug for DerivedSignal<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("DerivedSignal")
+    }
+}
@@ -1,10 +1,13 @@
 use core::slice;
+use std::collections::{HashMap, HashSet};
 
 use bevy::{
     ecs::relationship::{RelatedSpawner, RelatedSpawnerCommands},
     prelude::*,
 };
 
+use crate::portal::PortalContents;
+
 /// Represents a component that is member of a dynamic child list.
 // #[derive(Component, Clone, Reflect, PartialEq, Eq, Debug)]
 // #[reflect(Component, PartialEq, Debug, FromWorld)]
@@ -76,42 +79,63 @@ pub type DynChildSpawnerCommands<'w> = RelatedSpawnerCommands<'w, DynChildOf>;
 #[derive(Component, Default)]
 pub struct Fragment;
 
+/// Cached flattened list of children for a fragment. This lets us skip re-walking fragments whose
+/// dynamic children (and those of any nested fragments) have not changed.
+#[derive(Component, Default)]
+pub(crate) struct FlattenedChildren(Vec<Entity>);
+
 /// If a Fragment entity has changed children, then also mark the non-fragment ancestor as
-/// changed. This will ensure that the ancestor's children are recomputed.
-pub fn mark_children_changed(
-    q_fragments: Query<(Ref<DynChildren>, &DynChildOf), With<Fragment>>,
+/// changed. This will ensure that the ancestor's children are recomputed. Intermediate fragments
+/// are marked as changed as well, so that their cached flattened children are recomputed.
+pub(crate) fn mark_children_changed(
+    mut q_fragments: Query<(Mut<DynChildren>, &DynChildOf), With<Fragment>>,
+    q_empty_fragments: Query<&DynChildOf, (With<Fragment>, Without<DynChildren>)>,
     mut q_non_fragments: Query<Mut<DynChildren>, Without<Fragment>>,
+    mut removed: RemovedComponents<DynChildren>,
 ) {
-    for (dyn_children, dyn_parent) in q_fragments.iter() {
-        // If the children of the fragment have changed, then...
-        if dyn_children.is_changed() {
-            let mut parent = dyn_parent.0;
-            loop {
-                if let Ok(mut parent_dyn_children) = q_non_fragments.get_mut(parent) {
-                    // If it's a non-fragment, mark as changed and then stop.
-                    parent_dyn_children.set_changed();
-                    break;
-                } else if let Ok((_, fragment_parent)) = q_fragments.get(parent) {
-                    // If it's a fragment, continue up the hierarchy.
-                    parent = fragment_parent.0;
-                } else {
-                    // We've reached the top of the hierarchy, stop.
-                    break;
-                }
+    // Fragments whose children have changed, including fragments whose children were removed
+    // entirely.
+    let mut changed: Vec<Entity> = q_fragments
+        .iter()
+        .filter(|(dyn_children, _)| dyn_children.is_changed())
+        .map(|(_, dyn_parent)| dyn_parent.0)
+        .collect();
+    changed.extend(
+        removed
+            .read()
+            .filter_map(|entity| q_empty_fragments.get(entity).ok())
+            .map(|dyn_parent| dyn_parent.0),
+    );
+
+    for mut parent in changed {
+        loop {
+            if let Ok(mut parent_dyn_children) = q_non_fragments.get_mut(parent) {
+                // If it's a non-fragment, mark as changed and then stop.
+                parent_dyn_children.set_changed();
+                break;
+            } else if let Ok((mut fragment_children, fragment_parent)) = q_fragments.get_mut(parent)
+            {
+                // If it's a fragment, mark it as well and continue up the hierarchy.
+                fragment_children.set_changed();
+                parent = fragment_parent.0;
+            } else {
+                // We've reached the top of the hierarchy, stop.
+                break;
             }
         }
     }
 }
 
 #[allow(clippy::type_complexity)]
-pub fn flatten_dyn_children(
+pub(crate) fn flatten_dyn_children(
     q_parents: Query<
-        (Entity, &DynChildren, Option<Mut<Children>>),
+        (Entity, &DynChildren, Option<&Children>),
         (Without<Fragment>, Changed<DynChildren>),
     >,
-    q_fragments: Query<(Entity, &DynChildren), With<Fragment>>,
+    q_fragments: Query<(Option<Ref<DynChildren>>, Option<&FlattenedChildren>), With<Fragment>>,
     mut commands: Commands,
 ) {
+    let mut cache_updates: Vec<(Entity, Vec<Entity>)> = Vec::new();
     for (entity, dyn_children, children) in q_parents.iter() {
         // Use the previous children count to pre-allocate the new children list.
         // This is only a heuristic, to get the real count we'd need to walk the hierarchy.
@@ -123,30 +147,124 @@ pub fn flatten_dyn_children(
         // Recursively flatten the hierarchy of dynamic children. If the child is a fragment, we
         // replace it with its children, otherwise push it to the new children list.
         let mut new_children = Vec::<Entity>::with_capacity(old_count);
-        flatten(&mut new_children, dyn_children, &q_fragments);
-
-        // Replace the children list of the entity with the new children list.
-        // Note that this is not particularly efficient, as children that are carried over from
-        // the previous list will be removed and re-added.
-        // TODO: Come up with a more efficient alfgorithm.
-        let mut entt = commands.entity(entity);
-        entt.remove::<Children>().add_children(&new_children);
+        flatten(
+            &mut new_children,
+            dyn_children,
+            &q_fragments,
+            &mut cache_updates,
+        );
+
+        // Only touch `Children` if the flattened list actually differs.
+        let old_children: &[Entity] = match children {
+            Some(children) => children,
+            None => &[],
+        };
+        if old_children != new_children.as_slice() {
+            commands.queue(PatchChildren {
+                parent: entity,
+                children: new_children,
+            });
+        }
+    }
+
+    for (fragment, flattened) in cache_updates {
+        commands
+            .entity(fragment)
+            .insert(FlattenedChildren(flattened));
     }
 }
 
-/// Recursively flatten the hierarchy of dynamic children.
+/// Recursively flatten the hierarchy of dynamic children. Fragments whose dynamic children are
+/// unchanged since the last flatten reuse their cached list.
 fn flatten(
     new_children: &mut Vec<Entity>,
     dyn_children: &DynChildren,
-    q_fragments: &Query<(Entity, &DynChildren), With<Fragment>>,
+    q_fragments: &Query<(Option<Ref<DynChildren>>, Option<&FlattenedChildren>), With<Fragment>>,
+    cache_updates: &mut Vec<(Entity, Vec<Entity>)>,
 ) {
     for child in dyn_children {
-        if let Ok((_, dyn_children)) = q_fragments.get(*child) {
-            // If the child is a fragment, replace with it's children
-            flatten(new_children, dyn_children, q_fragments);
-        } else {
+        match q_fragments.get(*child) {
+            // Fragment with no children, contributes nothing.
+            Ok((None, _)) => {}
+            // Fragment which hasn't changed, use the cached list.
+            Ok((Some(fragment_children), Some(cached))) if !fragment_children.is_changed() => {
+                new_children.extend_from_slice(&cached.0);
+            }
+            // Fragment which has changed, replace with its children and update the cache.
+            Ok((Some(fragment_children), _)) => {
+                let start = new_children.len();
+                flatten(new_children, &fragment_children, q_fragments, cache_updates);
+                cache_updates.push((*child, new_children[start..].to_vec()));
+            }
             // Otherwise, push the child to the new children list.
-            new_children.push(*child);
+            Err(_) => new_children.push(*child),
+        }
+    }
+}
+
+/// Command which updates the `Children` of an entity to match the given list, with the minimum
+/// amount of change: children which are carried over from the previous list are left in place,
+/// and are only re-sorted if the order has changed. The containers of any [`Portal`]s which
+/// target the entity are not part of its dynamic children, so they are left alone, after the
+/// other children.
+///
+/// [`Portal`]: crate::Portal
+struct PatchChildren {
+    parent: Entity,
+    children: Vec<Entity>,
+}
+
+impl Command for PatchChildren {
+    fn apply(self, world: &mut World) {
+        let Ok(parent) = world.get_entity(self.parent) else {
+            return;
+        };
+        let old_children: Vec<Entity> = parent
+            .get::<Children>()
+            .map(|children| children.to_vec())
+            .unwrap_or_default();
+        let old_set: HashSet<Entity> = old_children.iter().copied().collect();
+        let new_set: HashSet<Entity> = self.children.iter().copied().collect();
+
+        // Remove children which are no longer present.
+        for child in old_children.iter() {
+            if !new_set.contains(child) {
+                if let Ok(mut entt) = world.get_entity_mut(*child) {
+                    if entt.contains::<PortalContents>() {
+                        continue;
+                    }
+                    entt.remove::<ChildOf>();
+                }
+            }
+        }
+
+        // Add children which are new. These are appended to the end of the list.
+        for child in self.children.iter() {
+            if !old_set.contains(child) {
+                if let Ok(mut entt) = world.get_entity_mut(*child) {
+                    entt.insert(ChildOf(self.parent));
+                }
+            }
+        }
+
+        // Fix up the order if needed. Portal containers are not among the patched children, so
+        // only the order of the other children is compared.
+        let Some(children) = world.get::<Children>(self.parent) else {
+            return;
+        };
+        let in_order = children
+            .iter()
+            .filter(|child| world.get::<PortalContents>(*child).is_none())
+            .eq(self.children.iter().copied());
+        if !in_order {
+            let position: HashMap<Entity, usize> = self
+                .children
+                .iter()
+                .enumerate()
+                .map(|(index, child)| (*child, index))
+                .collect();
+            let mut children = world.get_mut::<Children>(self.parent).unwrap();
+            children.sort_by_key(|child| position.get(child).copied().unwrap_or(usize::MAX));
         }
     }
 }
@@ -1,6 +1,11 @@
-use std::sync::{
-    atomic::{AtomicUsize, Ordering},
-    Arc, Mutex,
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, PoisonError,
+    },
 };
 
 use bevy::{
@@ -8,6 +13,11 @@ use bevy::{
     prelude::*,
 };
 
+use crate::{
+    error_boundary::{panic_message, report_error},
+    tracking::{restore_panicked_scopes, TrackingScope},
+};
+
 /// Component which holds a type-erased entity effect. An effect represents some dynamic mutation
 /// of the entity's state.
 /// Note: If Bevy had trait queries, we wouldn't the Arc/Mutex.
@@ -32,37 +42,200 @@ impl EffectCell {
 pub(crate) trait AnyEffect {
     fn update(&mut self, world: &mut World, entity: Entity);
     fn cleanup(&self, world: &mut DeferredWorld, entity: Entity);
+
+    /// Despawn any cached content. Only meaningful for control-flow nodes in keep-alive mode.
+    fn evict(&mut self, _world: &mut World, _entity: Entity) {}
 }
 
 fn on_add_effect(mut world: DeferredWorld, context: HookContext) {
+    invalidate_effect_order(&mut world);
     world.commands().queue(RunEffectNow(context.entity));
 }
 
 fn on_remove_effect(mut world: DeferredWorld, context: HookContext) {
+    invalidate_effect_order(&mut world);
     let cell = world.get_mut::<EffectCell>(context.entity).unwrap();
     let comp = cell.effect.clone();
-    comp.lock().unwrap().cleanup(&mut world, context.entity);
+    comp.lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .cleanup(&mut world, context.entity);
 }
 
+/// The order in which effects are run, which is cached between frames. It is only recomputed
+/// when an effect is added or removed, or when the dependencies of an effect change.
+#[derive(Resource, Default)]
+struct EffectOrder {
+    /// Effect entities, sorted in dependency order.
+    effects: Vec<Entity>,
+
+    /// False if `effects` needs to be recomputed.
+    valid: bool,
+
+    /// The effects in the dependency cycle which was last reported, so that each cycle is only
+    /// reported once.
+    cycle: Vec<Entity>,
+
+    /// True if the effects did not settle in the previous frame, so that this is only reported
+    /// once until they do settle.
+    unsettled: bool,
+}
+
+/// Mark the cached effect order as needing to be recomputed.
+pub(crate) fn invalidate_effect_order(world: &mut DeferredWorld) {
+    if let Some(mut order) = world.get_resource_mut::<EffectOrder>() {
+        if order.valid {
+            order.valid = false;
+        }
+    }
+}
+
+/// Run a single effect. If the effect panics, the panic is reported to the nearest error boundary
+/// rather than taking down the app.
+fn run_effect(world: &mut World, entity: Entity, cell: &EffectCell) {
+    // If an earlier update panicked while holding the lock, the effect is still usable.
+    let mut effect = cell.effect.lock().unwrap_or_else(PoisonError::into_inner);
+    let result = catch_unwind(AssertUnwindSafe(|| effect.update(world, entity)));
+    drop(effect);
+    if let Err(payload) = result {
+        restore_panicked_scopes(world);
+        report_error(world, entity, panic_message(payload.as_ref()));
+    }
+}
+
+/// Maximum number of passes that [`update_effects`] will make in a single frame before giving up.
+/// Effects that are still dirty after this many passes will be run again next frame.
+const MAX_EFFECT_PASSES: usize = 32;
+
 pub(crate) fn update_effects(world: &mut World) {
-    let mut query = world.query::<(Entity, &EffectCell)>();
-    let mut effects = query
-        .iter(world)
-        .map(|(entity, eff)| (entity, eff.clone()))
+    for pass in 0..MAX_EFFECT_PASSES {
+        let effects = sorted_effects(world);
+        let mut ran = false;
+        for entity in effects {
+            // An earlier effect may have despawned this one.
+            let Ok(entt) = world.get_entity(entity) else {
+                continue;
+            };
+            let Some(eff) = entt.get::<EffectCell>() else {
+                continue;
+            };
+            // Because effects are run in dependency order, an effect's inputs may have been
+            // changed by an earlier effect in this pass, so check each one just before running.
+            // Polled effects, and effects which have not recorded a scope, only run in the first
+            // pass, otherwise we would never settle.
+            let dirty = match entt.get::<TrackingScope>() {
+                Some(scope) if pass == 0 => scope.should_run(world),
+                Some(scope) => scope.dependencies_changed(world),
+                None => pass == 0,
+            };
+            if !dirty {
+                continue;
+            }
+            let eff = eff.clone();
+            run_effect(world, entity, &eff);
+            // Advance the change tick so that writes made by later effects are seen as newer
+            // than this effect's last run.
+            world.increment_change_tick();
+            ran = true;
+        }
+        world.flush();
+        if !ran {
+            world.resource_mut::<EffectOrder>().unsettled = false;
+            return;
+        }
+    }
+    let mut order = world.resource_mut::<EffectOrder>();
+    if !order.unsettled {
+        order.unsettled = true;
+        warn!(
+            "Effects did not settle after {} passes, this may indicate a dependency cycle",
+            MAX_EFFECT_PASSES
+        );
+    }
+}
+
+/// Returns all effects sorted so that each effect comes after any effects that it depends on
+/// (for example, a memo is run before the calcs which read it). Effects with no dependency
+/// relation are run in creation order. The order is cached in the [`EffectOrder`] resource.
+fn sorted_effects(world: &mut World) -> Vec<Entity> {
+    let order = world.get_resource_or_insert_with(EffectOrder::default);
+    if order.valid {
+        return order.effects.clone();
+    }
+
+    let mut query = world.query::<(Entity, &EffectCell, Option<&TrackingScope>)>();
+    let mut nodes: HashMap<Entity, (usize, Vec<Entity>)> = HashMap::new();
+    for (entity, eff, scope) in query.iter(world) {
+        let deps = scope
+            .map(|scope| scope.dependencies().collect())
+            .unwrap_or_default();
+        nodes.insert(entity, (eff.order, deps));
+    }
+
+    // Kahn's algorithm, using creation order to break ties.
+    let mut in_degree: HashMap<Entity, usize> = HashMap::with_capacity(nodes.len());
+    let mut readers: HashMap<Entity, Vec<Entity>> = HashMap::new();
+    for (&entity, (_, deps)) in nodes.iter() {
+        let mut count = 0;
+        for &dep in deps {
+            if dep != entity && nodes.contains_key(&dep) {
+                readers.entry(dep).or_default().push(entity);
+                count += 1;
+            }
+        }
+        in_degree.insert(entity, count);
+    }
+
+    let mut ready: BinaryHeap<Reverse<(usize, Entity)>> = in_degree
+        .iter()
+        .filter(|(_, &count)| count == 0)
+        .map(|(&entity, _)| Reverse((nodes[&entity].0, entity)))
+        .collect();
+    let mut result = Vec::with_capacity(nodes.len());
+    while let Some(Reverse((_, entity))) = ready.pop() {
+        if let Some(dependents) = readers.get(&entity) {
+            for reader in dependents {
+                let count = in_degree.get_mut(reader).unwrap();
+                *count -= 1;
+                if *count == 0 {
+                    ready.push(Reverse((nodes[reader].0, *reader)));
+                }
+            }
+        }
+        in_degree.remove(&entity);
+        nodes.remove(&entity);
+        result.push(entity);
+    }
+
+    // Anything left over is part of (or depends on) a cycle. Run these last, in creation order.
+    let mut remaining = nodes
+        .into_iter()
+        .map(|(entity, (order, _))| (order, entity))
         .collect::<Vec<_>>();
-    // Sort effects by creation order
-    effects.sort_by(|a, b| a.1.order.cmp(&b.1.order));
-    for (entity, eff) in effects {
-        eff.effect.lock().unwrap().update(world, entity);
+    remaining.sort();
+    let cycle = remaining
+        .into_iter()
+        .map(|(_, entity)| entity)
+        .collect::<Vec<_>>();
+    result.extend(cycle.iter().copied());
+
+    let mut order = world.resource_mut::<EffectOrder>();
+    if !cycle.is_empty() && cycle != order.cycle {
+        warn!("Dependency cycle detected between effects: {:?}", cycle);
     }
+    order.cycle = cycle;
+    order.effects = result.clone();
+    order.valid = true;
+    result
 }
 
 struct RunEffectNow(pub Entity);
 
 impl Command for RunEffectNow {
     fn apply(self, world: &mut World) {
-        let cell = world.get_mut::<EffectCell>(self.0).unwrap();
-        let effect = cell.effect.clone();
-        effect.lock().unwrap().update(world, self.0);
+        let Some(cell) = world.get::<EffectCell>(self.0) else {
+            return;
+        };
+        let cell = cell.clone();
+        run_effect(world, self.0, &cell);
     }
 }
@@ -0,0 +1,280 @@
+use std::{
+    any::Any,
+    panic::{catch_unwind, AssertUnwindSafe},
+};
+
+use bevy::{
+    ecs::{
+        bundle::{BundleEffect, DynamicBundle},
+        spawn::SpawnableList,
+        system::SystemId,
+        world::DeferredWorld,
+    },
+    prelude::*,
+};
+
+use crate::{
+    dyn_children::Fragment,
+    effect_cell::{AnyEffect, EffectCell},
+    owner::Owned,
+    portal::PortalContents,
+    tracking::{restore_panicked_scopes, run_tracked, TrackingScope},
+    ComputationOf, Computations, DynChildOf, DynChildren, OwnedBy, ReadTracked, SpawnableListGen,
+};
+
+/// Information about an error caught by an [`ErrorBoundary`], passed to the fallback.
+#[derive(Clone, Debug)]
+pub struct BoundaryError {
+    /// The error message, or the panic message if the error was a panic.
+    pub message: String,
+
+    /// One-shot system which clears the error and rebuilds the contents of the boundary. This can
+    /// be used as the `on_click` handler of a "retry" button.
+    pub retry: SystemId,
+}
+
+/// State of an error boundary, which errors are reported to.
+#[derive(Component, Default)]
+pub(crate) struct ErrorBoundaryState {
+    error: Option<String>,
+    showing_fallback: bool,
+}
+
+/// Extract a readable message from a panic payload.
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+/// Report an error which occurred while updating `entity`. The error is passed to the nearest
+/// enclosing [`ErrorBoundary`] which is not already showing its fallback, searching up through
+/// the owner, dynamic parent and parent of each entity. The error is also logged.
+///
+/// If `entity` is an effect, it is marked as having failed, so that it is not run again (and
+/// the error is not reported again) until one of its dependencies changes.
+pub(crate) fn report_error(world: &mut World, entity: Entity, message: String) {
+    if let Some(mut scope) = world.get_mut::<TrackingScope>(entity) {
+        scope.set_errored();
+    }
+
+    let mut current = entity;
+    let boundary = loop {
+        let Ok(entt) = world.get_entity(current) else {
+            break None;
+        };
+        if let Some(state) = entt.get::<ErrorBoundaryState>() {
+            if !state.showing_fallback {
+                break Some(current);
+            }
+        }
+        current = if let Some(owner) = entt.get::<ComputationOf>() {
+            owner.get()
+        } else if let Some(dyn_parent) = entt.get::<DynChildOf>() {
+            dyn_parent.get()
+        } else if let Some(portal) = entt.get::<PortalContents>() {
+            portal.0
+        } else if let Some(parent) = entt.get::<ChildOf>() {
+            parent.get()
+        } else if let Some(owner) = entt.get::<OwnedBy>() {
+            owner.get()
+        } else {
+            break None;
+        };
+    };
+
+    match boundary {
+        Some(boundary) => {
+            error!("Error in {entity}, caught by error boundary {boundary}: {message}");
+            let mut state = world.get_mut::<ErrorBoundaryState>(boundary).unwrap();
+            if state.error.is_none() {
+                state.error = Some(message);
+            }
+        }
+        None => {
+            error!("Error in {entity}: {message}");
+        }
+    }
+}
+
+/// Control-flow node which catches errors from the reactions within its contents: errors
+/// returned by their one-shot systems, as well as panics in reactions and template `build`
+/// functions. When an error is caught, the contents are despawned and replaced by the
+/// fallback, which is passed the error message and a callback to retry.
+pub struct ErrorBoundary<
+    Contents: SpawnableListGen + Send + Sync + 'static,
+    S: SpawnableList<DynChildOf>,
+    FallbackFn: Fn(&BoundaryError) -> S + Send + Sync + 'static,
+> {
+    contents: Contents,
+    fallback: FallbackFn,
+}
+
+impl<
+        Contents: SpawnableListGen + Send + Sync + 'static,
+        S: SpawnableList<DynChildOf>,
+        FallbackFn: Fn(&BoundaryError) -> S + Send + Sync + 'static,
+    > ErrorBoundary<Contents, S, FallbackFn>
+{
+    pub fn new(contents: Contents, fallback: FallbackFn) -> Self {
+        Self { contents, fallback }
+    }
+}
+
+unsafe impl<
+        Contents: SpawnableListGen + Send + Sync + 'static,
+        S: SpawnableList<DynChildOf> + 'static,
+        FallbackFn: Fn(&BoundaryError) -> S + Send + Sync + 'static,
+    > Bundle for ErrorBoundary<Contents, S, FallbackFn>
+{
+    fn component_ids(
+        _components: &mut bevy::ecs::component::Components,
+        _ids: &mut impl FnMut(bevy::ecs::component::ComponentId),
+    ) {
+    }
+
+    fn get_component_ids(
+        _components: &bevy::ecs::component::Components,
+        _ids: &mut impl FnMut(Option<bevy::ecs::component::ComponentId>),
+    ) {
+    }
+
+    fn register_required_components(
+        _components: &mut bevy::ecs::component::Components,
+        _required_components: &mut bevy::ecs::component::RequiredComponents,
+    ) {
+    }
+}
+
+impl<
+        Contents: SpawnableListGen + Send + Sync + 'static,
+        S: SpawnableList<DynChildOf> + 'static,
+        FallbackFn: Fn(&BoundaryError) -> S + Send + Sync + 'static,
+    > DynamicBundle for ErrorBoundary<Contents, S, FallbackFn>
+{
+    type Effect = Self;
+
+    fn get_components(
+        self,
+        _func: &mut impl FnMut(bevy::ecs::component::StorageType, bevy::ptr::OwningPtr<'_>),
+    ) -> Self::Effect {
+        self
+    }
+}
+
+impl<
+        Contents: SpawnableListGen + Send + Sync + 'static,
+        S: SpawnableList<DynChildOf> + 'static,
+        FallbackFn: Fn(&BoundaryError) -> S + Send + Sync + 'static,
+    > BundleEffect for ErrorBoundary<Contents, S, FallbackFn>
+{
+    fn apply(self, entity: &mut EntityWorldMut) {
+        let boundary = entity.id();
+        let retry = entity.world_scope(|world| {
+            world.register_system(move |mut q_state: Query<&mut ErrorBoundaryState>| {
+                if let Ok(mut state) = q_state.get_mut(boundary) {
+                    state.error = None;
+                }
+            })
+        });
+        entity.insert((
+            ErrorBoundaryState::default(),
+            EffectCell::new(ErrorBoundaryEffect {
+                contents: self.contents,
+                fallback: self.fallback,
+                retry,
+                first: true,
+                marker: std::marker::PhantomData,
+            }),
+            Fragment,
+        ));
+    }
+}
+
+struct ErrorBoundaryEffect<
+    Contents: SpawnableListGen,
+    S: SpawnableList<DynChildOf>,
+    FallbackFn: Fn(&BoundaryError) -> S,
+> {
+    contents: Contents,
+    fallback: FallbackFn,
+    retry: SystemId,
+    first: bool,
+    marker: std::marker::PhantomData<fn() -> S>,
+}
+
+impl<
+        Contents: SpawnableListGen,
+        S: SpawnableList<DynChildOf>,
+        FallbackFn: Fn(&BoundaryError) -> S,
+    > ErrorBoundaryEffect<Contents, S, FallbackFn>
+{
+    fn clear(&self, world: &mut World, entity: Entity) {
+        let mut entt = world.entity_mut(entity);
+        entt.remove::<Children>();
+        entt.despawn_related::<DynChildren>();
+        entt.despawn_related::<Computations>();
+        entt.despawn_related::<Owned>();
+    }
+}
+
+impl<
+        Contents: SpawnableListGen,
+        S: SpawnableList<DynChildOf>,
+        FallbackFn: Fn(&BoundaryError) -> S,
+    > AnyEffect for ErrorBoundaryEffect<Contents, S, FallbackFn>
+{
+    fn update(&mut self, world: &mut World, entity: Entity) {
+        let (mut error, showing_fallback) = run_tracked(world, entity, |world| {
+            world
+                .read_component::<ErrorBoundaryState>(entity)
+                .map(|state| (state.error.clone(), state.showing_fallback))
+                .unwrap_or_default()
+        });
+
+        if error.is_none() && (showing_fallback || self.first) {
+            // Build (or rebuild) the contents.
+            self.first = false;
+            self.clear(world, entity);
+            let result = catch_unwind(AssertUnwindSafe(|| self.contents.spawn(world, entity)));
+            if let Err(payload) = result {
+                restore_panicked_scopes(world);
+                let message = panic_message(payload.as_ref());
+                error!("Panic while building contents of error boundary {entity}: {message}");
+                error = Some(message);
+            }
+        }
+
+        if let Some(message) = error {
+            if !showing_fallback {
+                self.clear(world, entity);
+                (self.fallback)(&BoundaryError {
+                    message: message.clone(),
+                    retry: self.retry,
+                })
+                .spawn(world, entity);
+            }
+            // Only write the state if it differs, so as not to trigger another update.
+            let mut state = world.get_mut::<ErrorBoundaryState>(entity).unwrap();
+            if state.error.is_none() {
+                state.error = Some(message);
+            }
+            if !state.showing_fallback {
+                state.showing_fallback = true;
+            }
+        } else if showing_fallback {
+            world
+                .get_mut::<ErrorBoundaryState>(entity)
+                .unwrap()
+                .showing_fallback = false;
+        }
+    }
+
+    fn cleanup(&self, world: &mut DeferredWorld, _entity: Entity) {
+        world.commands().unregister_system(self.retry);
+    }
+}
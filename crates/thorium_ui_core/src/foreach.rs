@@ -12,13 +12,16 @@ use bevy::{
 use crate::{
     dyn_children::Fragment,
     effect_cell::{AnyEffect, EffectCell},
+    error_boundary::report_error,
     lcs::lcs,
+    reconcile::{begin_reconcile, end_reconcile, retire_instances},
+    tracking::run_tracked,
     DynChildOf, DynChildren, SpawnableListGen, TemplateContext,
 };
 
 pub struct ListItems<Item: Clone> {
-    items: Vec<Item>,
-    changed: bool,
+    pub(crate) items: Vec<Item>,
+    pub(crate) changed: bool,
 }
 
 impl<Item: Clone> ListItems<Item> {
@@ -88,6 +91,7 @@ impl<
     fn build_recursive(
         &self,
         world: &mut World,
+        parent: Entity,
         prev_state: &[ListItem<Item>],
         prev_range: Range<usize>,
         next_items: &[Item],
@@ -107,11 +111,12 @@ impl<
             // Raze old elements
             for i in prev_range {
                 let prev = &prev_state[i];
+                retire_instances(world, prev.child);
                 world.entity_mut(prev.child).despawn();
             }
             // Build new elements
             for i in next_range {
-                let child_id = world.spawn((GhostNode, Fragment)).id();
+                let child_id = world.spawn((GhostNode, Fragment, DynChildOf(parent))).id();
                 let mut tc = TemplateContext::new(child_id, world);
                 (self.each)(&next_items[i], &mut tc);
                 out.push(ListItem {
@@ -132,7 +137,7 @@ impl<
                 // Both prev and next have entries before lcs, so recurse
                 self.build_recursive(
                     world,
-                    // owner,
+                    parent,
                     prev_state,
                     prev_range.start..prev_start,
                     next_items,
@@ -143,13 +148,14 @@ impl<
                 // Deletions
                 for i in prev_range.start..prev_start {
                     let prev = &prev_state[i];
+                    retire_instances(world, prev.child);
                     world.entity_mut(prev.child).despawn();
                 }
             }
         } else if next_start > next_range.start {
             // Insertions
             for i in next_range.start..next_start {
-                let child_id = world.spawn((GhostNode, Fragment)).id();
+                let child_id = world.spawn((GhostNode, Fragment, DynChildOf(parent))).id();
                 let mut tc = TemplateContext::new(child_id, world);
                 (self.each)(&next_items[i], &mut tc);
                 out.push(ListItem {
@@ -173,7 +179,7 @@ impl<
                 // Both prev and next have entries after lcs, so recurse
                 self.build_recursive(
                     world,
-                    // owner,
+                    parent,
                     prev_state,
                     prev_end..prev_range.end,
                     next_items,
@@ -184,13 +190,14 @@ impl<
                 // Deletions
                 for i in prev_end..prev_range.end {
                     let prev = &prev_state[i];
+                    retire_instances(world, prev.child);
                     world.entity_mut(prev.child).despawn();
                 }
             }
         } else if next_end < next_range.end {
             // Insertions
             for i in next_end..next_range.end {
-                let child_id = world.spawn((GhostNode, Fragment)).id();
+                let child_id = world.spawn((GhostNode, Fragment, DynChildOf(parent))).id();
                 let mut tc = TemplateContext::new(child_id, world);
                 (self.each)(&next_items[i], &mut tc);
                 out.push(ListItem {
@@ -215,20 +222,36 @@ impl<
             items: Vec::new(),
             changed: false,
         };
-        world.run_system_with(self.item_sys, &mut items).unwrap();
+        if let Err(err) = run_tracked(world, parent, |world| {
+            world.run_system_with(self.item_sys, &mut items)
+        }) {
+            report_error(world, parent, err.to_string());
+            return;
+        }
         if items.changed || self.first {
             let mut next_state: Vec<ListItem<Item>> = Vec::with_capacity(items.items.len());
             let next_len = items.items.len();
             let prev_len = self.state.len();
 
+            if prev_len == 0 && next_len > 0 {
+                // Transitioning from empty to non-empty, delete fallback. This needs to happen
+                // before the new rows are spawned, since they are dynamic children of the parent.
+                world.entity_mut(parent).remove::<Children>();
+                world.entity_mut(parent).despawn_related::<DynChildren>();
+            }
+
+            // Keyed template instances in removed rows can be reused by inserted rows.
+            begin_reconcile(world);
             self.build_recursive(
                 world,
+                parent,
                 &self.state,
                 0..prev_len,
                 &items.items,
                 0..next_len,
                 &mut next_state,
             );
+            end_reconcile(world);
             let children: Vec<Entity> = next_state.iter().map(|i| i.child).collect();
             self.state = std::mem::take(&mut next_state);
 
@@ -241,13 +264,8 @@ impl<
                     self.fallback.spawn(world, parent);
                 }
             } else {
-                if prev_len == 0 {
-                    // Transitioning from non-empty to empty, delete fallback.
-                    world.entity_mut(parent).remove::<Children>();
-                    world.entity_mut(parent).despawn_related::<DynChildren>();
-                } else {
-                    world.entity_mut(parent).remove::<DynChildren>();
-                }
+                // Re-insert the rows in the new order.
+                world.entity_mut(parent).remove::<DynChildren>();
                 world
                     .entity_mut(parent)
                     .add_related::<DynChildOf>(&children);
@@ -0,0 +1,320 @@
+use std::hash::Hash;
+
+use bevy::{
+    ecs::{
+        bundle::{BundleEffect, DynamicBundle},
+        system::SystemId,
+    },
+    prelude::*,
+    ui::experimental::GhostNode,
+};
+
+use crate::{
+    dyn_children::Fragment,
+    effect_cell::{AnyEffect, EffectCell},
+    error_boundary::report_error,
+    foreach::ListItems,
+    keyed::keyed_match,
+    tracking::run_tracked,
+    CreateMutable, DynChildOf, DynChildren, For, Mutable, OwnedBy, Signal, SpawnableListGen,
+    TemplateContext, WriteMutable,
+};
+
+struct KeyedListItem<Item, K> {
+    child: Entity,
+    key: K,
+    item: Mutable<Item>,
+    index: Mutable<usize>,
+}
+
+/// A reaction that handles keyed list rendering.
+struct ForKeyedEffect<
+    'a,
+    Item: Clone + 'static,
+    K: Hash + Eq,
+    EachFn: Fn(Signal<Item>, Signal<usize>, &mut TemplateContext) + Send + Sync + 'static,
+    FallbackFn: SpawnableListGen + Send + Sync + 'static,
+> where
+    Self: Send + Sync,
+{
+    item_sys: SystemId<InMut<'a, ListItems<Item>>, ()>,
+    key: fn(&Item) -> K,
+    each: EachFn,
+    fallback: FallbackFn,
+    state: Vec<KeyedListItem<Item, K>>,
+    first: bool,
+}
+
+impl<
+        Item: Clone + PartialEq + Send + Sync + 'static,
+        K: Hash + Eq + Send + Sync + 'static,
+        EachFn: Fn(Signal<Item>, Signal<usize>, &mut TemplateContext) + Send + Sync + 'static,
+        FallbackFn: SpawnableListGen + Send + Sync + 'static,
+    > AnyEffect for ForKeyedEffect<'_, Item, K, EachFn, FallbackFn>
+{
+    fn update(&mut self, world: &mut World, parent: Entity) {
+        let mut items = ListItems::<Item> {
+            items: Vec::new(),
+            changed: false,
+        };
+        if let Err(err) = run_tracked(world, parent, |world| {
+            world.run_system_with(self.item_sys, &mut items)
+        }) {
+            report_error(world, parent, err.to_string());
+            return;
+        }
+        if !items.changed && !self.first {
+            return;
+        }
+
+        let prev_len = self.state.len();
+        let next_len = items.items.len();
+        let next_keys: Vec<K> = items.items.iter().map(self.key).collect();
+        let prev_keys: Vec<&K> = self.state.iter().map(|prev| &prev.key).collect();
+        let matches = keyed_match(&prev_keys, &next_keys.iter().collect::<Vec<_>>());
+
+        // Despawn any previous rows whose key is no longer present.
+        let mut reused = vec![false; prev_len];
+        for index in matches.iter().flatten() {
+            reused[*index] = true;
+        }
+        for (prev, reused) in self.state.iter().zip(reused) {
+            if !reused {
+                world.entity_mut(prev.child).despawn();
+            }
+        }
+
+        if prev_len == 0 && next_len > 0 {
+            // Transitioning from empty to non-empty, delete fallback. This needs to happen
+            // before the new rows are spawned, since they are dynamic children of the parent.
+            world.entity_mut(parent).remove::<Children>();
+            world.entity_mut(parent).despawn_related::<DynChildren>();
+        }
+
+        // Rows with a matching key keep their entities (and their subtree), even if they have
+        // moved; the row's item and index signals are updated in place. New keys get a freshly
+        // built row.
+        let mut next_state: Vec<KeyedListItem<Item, K>> = Vec::with_capacity(next_len);
+        for (index, ((item, key), matched)) in items
+            .items
+            .iter()
+            .zip(next_keys)
+            .zip(matches.iter())
+            .enumerate()
+        {
+            let row = match matched {
+                Some(prev_index) => {
+                    let prev = &self.state[*prev_index];
+                    world.write_mutable(prev.item.id(), item.clone());
+                    world.write_mutable(prev.index.id(), index);
+                    KeyedListItem {
+                        child: prev.child,
+                        key,
+                        item: prev.item,
+                        index: prev.index,
+                    }
+                }
+                None => {
+                    let child_id = world.spawn((GhostNode, Fragment, DynChildOf(parent))).id();
+                    // Row state is owned by the row, so it is despawned along with it.
+                    let item_mutable = world.create_mutable(item.clone());
+                    let index_mutable = world.create_mutable(index);
+                    world
+                        .entity_mut(child_id)
+                        .add_related::<OwnedBy>(&[item_mutable.id(), index_mutable.id()]);
+                    let mut tc = TemplateContext::new(child_id, world);
+                    (self.each)(item_mutable.signal(), index_mutable.signal(), &mut tc);
+                    KeyedListItem {
+                        child: child_id,
+                        key,
+                        item: item_mutable,
+                        index: index_mutable,
+                    }
+                }
+            };
+            next_state.push(row);
+        }
+
+        let children: Vec<Entity> = next_state.iter().map(|row| row.child).collect();
+        let unchanged = !self.first
+            && prev_len == next_len
+            && self
+                .state
+                .iter()
+                .zip(&children)
+                .all(|(prev, child)| prev.child == *child);
+        self.state = next_state;
+
+        if unchanged {
+            // Same rows in the same order, nothing to re-parent.
+            return;
+        }
+
+        if next_len == 0 {
+            if prev_len > 0 || self.first {
+                self.first = false;
+                // Transitioning from non-empty to empty, generate fallback.
+                world.entity_mut(parent).remove::<Children>();
+                world.entity_mut(parent).despawn_related::<DynChildren>();
+                self.fallback.spawn(world, parent);
+            }
+        } else {
+            self.first = false;
+            // Re-insert the rows in the new order.
+            world.entity_mut(parent).remove::<DynChildren>();
+            world
+                .entity_mut(parent)
+                .add_related::<DynChildOf>(&children);
+        }
+    }
+
+    fn cleanup(&self, world: &mut bevy::ecs::world::DeferredWorld, _entity: Entity) {
+        world.commands().unregister_system(self.item_sys);
+    }
+}
+
+/// A keyed version of [`For`], created by [`For::each_keyed`]. Rows are identified by a key
+/// rather than by comparing items, so reordering the list moves the existing rows rather than
+/// rebuilding them.
+pub struct ForKeyed<
+    'a: 'static,
+    M: Send + Sync + 'static,
+    Item: Send + Sync + 'static + Clone,
+    K: Hash + Eq + Send + Sync + 'static,
+    ItemFn: IntoSystem<InMut<'a, ListItems<Item>>, (), M> + Send + Sync + 'static,
+    EachFn: Fn(Signal<Item>, Signal<usize>, &mut TemplateContext) + Send + Sync + 'static,
+    FallbackFn: SpawnableListGen + Send + Sync + 'static,
+> {
+    items_fn: ItemFn,
+    key: fn(&Item) -> K,
+    each: EachFn,
+    fallback: FallbackFn,
+    marker: std::marker::PhantomData<(&'a M, Item)>,
+}
+
+impl<
+        'a: 'static,
+        M: Send + Sync + 'static,
+        Item: Send + Sync + 'static + Clone + PartialEq,
+        K: Hash + Eq + Send + Sync + 'static,
+        ItemFn: IntoSystem<InMut<'a, ListItems<Item>>, (), M> + Send + Sync + 'static,
+        EachFn: Fn(Signal<Item>, Signal<usize>, &mut TemplateContext) + Send + Sync + 'static,
+        FallbackFn: SpawnableListGen + Send + Sync + 'static,
+    > ForKeyed<'a, M, Item, K, ItemFn, EachFn, FallbackFn>
+{
+    /// Construct a keyed list. This is the same as [`For::each_keyed`].
+    pub fn new(items_fn: ItemFn, key: fn(&Item) -> K, each: EachFn, fallback: FallbackFn) -> Self {
+        Self {
+            items_fn,
+            key,
+            each,
+            fallback,
+            marker: std::marker::PhantomData,
+        }
+    }
+}
+
+// `For`'s own row function takes the item itself, so the keyed constructor lives in an impl
+// where that parameter is fixed; it is never used, since the constructor returns a `ForKeyed`.
+impl<
+        'a: 'static,
+        M: Send + Sync + 'static,
+        Item: Send + Sync + 'static + Clone + PartialEq,
+        ItemFn: IntoSystem<InMut<'a, ListItems<Item>>, (), M> + Send + Sync + 'static,
+        FallbackFn: SpawnableListGen + Send + Sync + 'static,
+    > For<'a, M, Item, ItemFn, fn(&Item, &mut TemplateContext), FallbackFn>
+{
+    /// Construct a keyed list. Each item's identity is determined by `key`: when the list
+    /// changes, rows whose key is still present are kept (and moved if needed), rows whose key
+    /// is gone are despawned, and rows for new keys are built using `each`.
+    ///
+    /// Rather than the item itself, `each` is passed a signal for the item and a signal for its
+    /// position in the list. When an existing row's item or position changes, these signals
+    /// are updated in place, so the row can react to the change without being rebuilt.
+    pub fn each_keyed<
+        K: Hash + Eq + Send + Sync + 'static,
+        EachFn: Fn(Signal<Item>, Signal<usize>, &mut TemplateContext) + Send + Sync + 'static,
+    >(
+        items_fn: ItemFn,
+        key: fn(&Item) -> K,
+        each: EachFn,
+        fallback: FallbackFn,
+    ) -> ForKeyed<'a, M, Item, K, ItemFn, EachFn, FallbackFn> {
+        ForKeyed::new(items_fn, key, each, fallback)
+    }
+}
+
+impl<
+        'a: 'static,
+        M: Send + Sync + 'static,
+        Item: Send + Sync + 'static + Clone + PartialEq,
+        K: Hash + Eq + Send + Sync + 'static,
+        ItemFn: IntoSystem<InMut<'a, ListItems<Item>>, (), M> + Send + Sync + 'static,
+        EachFn: Fn(Signal<Item>, Signal<usize>, &mut TemplateContext) + Send + Sync + 'static,
+        FallbackFn: SpawnableListGen + Send + Sync + 'static,
+    > BundleEffect for ForKeyed<'a, M, Item, K, ItemFn, EachFn, FallbackFn>
+{
+    fn apply(self, entity: &mut EntityWorldMut) {
+        let item_sys = entity.world_scope(|world| world.register_system(self.items_fn));
+        entity.insert((
+            EffectCell::new(ForKeyedEffect {
+                item_sys,
+                key: self.key,
+                each: self.each,
+                fallback: self.fallback,
+                state: Vec::new(),
+                first: true,
+            }),
+            Fragment,
+        ));
+    }
+}
+
+impl<
+        'a: 'static,
+        M: Send + Sync + 'static,
+        Item: Send + Sync + 'static + Clone + PartialEq,
+        K: Hash + Eq + Send + Sync + 'static,
+        ItemFn: IntoSystem<InMut<'a, ListItems<Item>>, (), M> + Send + Sync + 'static,
+        EachFn: Fn(Signal<Item>, Signal<usize>, &mut TemplateContext) + Send + Sync + 'static,
+        FallbackFn: SpawnableListGen + Send + Sync + 'static,
+    > DynamicBundle for ForKeyed<'a, M, Item, K, ItemFn, EachFn, FallbackFn>
+{
+    type Effect = Self;
+
+    fn get_components(
+        self,
+        _func: &mut impl FnMut(bevy::ecs::component::StorageType, bevy::ptr::OwningPtr<'_>),
+    ) -> Self::Effect {
+        self
+    }
+}
+
+unsafe impl<
+        'a: 'static,
+        M: Send + Sync + 'static,
+        Item: Send + Sync + 'static + Clone + PartialEq,
+        K: Hash + Eq + Send + Sync + 'static,
+        ItemFn: IntoSystem<InMut<'a, ListItems<Item>>, (), M> + Send + Sync + 'static,
+        EachFn: Fn(Signal<Item>, Signal<usize>, &mut TemplateContext) + Send + Sync + 'static,
+        FallbackFn: SpawnableListGen + Send + Sync + 'static,
+    > Bundle for ForKeyed<'a, M, Item, K, ItemFn, EachFn, FallbackFn>
+{
+    fn component_ids(
+        _components: &mut bevy::ecs::component::Components,
+        _ids: &mut impl FnMut(bevy::ecs::component::ComponentId),
+    ) {
+    }
+
+    fn get_component_ids(
+        _components: &bevy::ecs::component::Components,
+        _ids: &mut impl FnMut(Option<bevy::ecs::component::ComponentId>),
+    ) {
+    }
+
+    fn register_required_components(
+        _components: &mut bevy::ecs::component::Components,
+        _required_components: &mut bevy::ecs::component::RequiredComponents,
+    ) {
+    }
+}
@@ -0,0 +1,429 @@
+use bevy::{
+    ecs::{
+        bundle::{BundleEffect, DynamicBundle},
+        world::DeferredWorld,
+    },
+    prelude::*,
+    ui::experimental::GhostNode,
+};
+
+use crate::{
+    dyn_children::Fragment,
+    effect_cell::{AnyEffect, EffectCell},
+    foreach::ListItems,
+    mutable_vec::{MutableVec, VecChange},
+    tracking::run_tracked,
+    DynChildOf, DynChildren, For, SpawnableListGen, TemplateContext,
+};
+
+/// What the list view needs to do to catch up with the vector.
+enum VecSync {
+    /// Nothing has changed.
+    None,
+    /// Apply these changes, in order.
+    Changes(Vec<VecChange>),
+    /// Rebuild all rows.
+    Rebuild,
+}
+
+/// The rows of a list view, kept in step with a [`MutableVec`] by replaying its changes. Rows
+/// which still need to be built are `None`, and their indices are kept in `pending`, so that
+/// replaying a change only touches the rows which it affects.
+struct RowList<R> {
+    rows: Vec<Option<R>>,
+    pending: Vec<usize>,
+    /// True if rows were moved, in which case the order of all rows needs to be re-applied.
+    moved: bool,
+}
+
+impl<R> RowList<R> {
+    fn new() -> Self {
+        Self {
+            rows: Vec::new(),
+            pending: Vec::new(),
+            moved: false,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Replace all rows with `len` rows which need to be built. Returns the old rows.
+    fn reset(&mut self, len: usize) -> Vec<R> {
+        let old = std::mem::take(&mut self.rows);
+        self.rows.resize_with(len, || None);
+        self.pending = (0..len).collect();
+        old.into_iter().flatten().collect()
+    }
+
+    /// Replay a single change. Returns the row which was removed or replaced, if it had been
+    /// built.
+    fn apply(&mut self, change: VecChange) -> Option<R> {
+        match change {
+            VecChange::Insert(index) => {
+                for pending in self.pending.iter_mut().filter(|pending| **pending >= index) {
+                    *pending += 1;
+                }
+                self.rows.insert(index, None);
+                self.pending.push(index);
+                None
+            }
+            VecChange::Remove(index) => {
+                self.pending.retain(|pending| *pending != index);
+                for pending in self.pending.iter_mut().filter(|pending| **pending > index) {
+                    *pending -= 1;
+                }
+                self.rows.remove(index)
+            }
+            VecChange::Set(index) => {
+                let row = self.rows[index].take();
+                if row.is_some() {
+                    self.pending.push(index);
+                }
+                row
+            }
+            VecChange::Move { from, to } => {
+                let row = self.rows.remove(from);
+                self.rows.insert(to, row);
+                for pending in self.pending.iter_mut() {
+                    if *pending == from {
+                        *pending = to;
+                    } else {
+                        if *pending > from {
+                            *pending -= 1;
+                        }
+                        if *pending >= to {
+                            *pending += 1;
+                        }
+                    }
+                }
+                self.moved = true;
+                None
+            }
+        }
+    }
+
+    /// Takes the indices of the rows which need to be built, in ascending order.
+    fn take_pending(&mut self) -> Vec<usize> {
+        let mut pending = std::mem::take(&mut self.pending);
+        pending.sort_unstable();
+        pending
+    }
+}
+
+/// A reaction that renders the rows of a [`MutableVec`], driven by its change log.
+struct ForVecEffect<
+    Item: Clone + Send + Sync + 'static,
+    EachFn: Fn(&Item, &mut TemplateContext) + Send + Sync + 'static,
+    FallbackFn: SpawnableListGen + Send + Sync + 'static,
+> {
+    items: MutableVec<Item>,
+    each: EachFn,
+    fallback: FallbackFn,
+    /// Row entities, in order.
+    rows: RowList<Entity>,
+    /// The version of the vector that the rows reflect, or `None` if nothing has been built.
+    version: Option<usize>,
+}
+
+impl<
+        Item: Clone + Send + Sync + 'static,
+        EachFn: Fn(&Item, &mut TemplateContext) + Send + Sync + 'static,
+        FallbackFn: SpawnableListGen + Send + Sync + 'static,
+    > AnyEffect for ForVecEffect<Item, EachFn, FallbackFn>
+{
+    fn update(&mut self, world: &mut World, parent: Entity) {
+        let version = self.version;
+        let items = self.items;
+        let (next_version, sync) = run_tracked(world, parent, |world| {
+            let next_version = items.version(world);
+            let sync = match version {
+                Some(version) if version == next_version => VecSync::None,
+                Some(version) => match items.changes_since(world, version) {
+                    Some(changes) => VecSync::Changes(changes),
+                    None => VecSync::Rebuild,
+                },
+                None => VecSync::Rebuild,
+            };
+            (next_version, sync)
+        });
+        let first = self.version.is_none();
+        self.version = Some(next_version);
+
+        let prev_len = self.rows.len();
+        match sync {
+            VecSync::None => return,
+            VecSync::Rebuild => {
+                for row in self.rows.reset(items.len(world)) {
+                    world.entity_mut(row).despawn();
+                }
+            }
+            VecSync::Changes(changes) => {
+                // Replay the changes against the rows, without building anything yet: an
+                // inserted item may have been modified or removed again by a later change.
+                for change in changes {
+                    if let Some(row) = self.rows.apply(change) {
+                        world.entity_mut(row).despawn();
+                    }
+                }
+            }
+        }
+
+        let next_len = self.rows.len();
+        if prev_len == 0 && next_len > 0 {
+            // Transitioning from empty to non-empty, delete fallback. This needs to happen
+            // before the new rows are spawned, since they are dynamic children of the parent.
+            world.entity_mut(parent).remove::<Children>();
+            world.entity_mut(parent).despawn_related::<DynChildren>();
+        }
+
+        // Build the rows which are new or were replaced. These are built in ascending order, so
+        // that all of the rows before each one are already in place among the parent's dynamic
+        // children, and it can be inserted at its own index.
+        let moved = std::mem::take(&mut self.rows.moved);
+        for index in self.rows.take_pending() {
+            let item = items.as_slice(world)[index].clone();
+            let child_id = world.spawn((GhostNode, Fragment, DynChildOf(parent))).id();
+            let mut tc = TemplateContext::new(child_id, world);
+            (self.each)(&item, &mut tc);
+            if !moved {
+                world
+                    .entity_mut(parent)
+                    .insert_related::<DynChildOf>(index, &[child_id]);
+            }
+            self.rows.rows[index] = Some(child_id);
+        }
+
+        if next_len == 0 {
+            if prev_len > 0 || first {
+                // Transitioning from non-empty to empty, generate fallback.
+                world.entity_mut(parent).remove::<Children>();
+                world.entity_mut(parent).despawn_related::<DynChildren>();
+                self.fallback.spawn(world, parent);
+            }
+        } else if moved {
+            // Re-insert the rows in the new order.
+            let children: Vec<Entity> = self.rows.rows.iter().flatten().copied().collect();
+            world.entity_mut(parent).remove::<DynChildren>();
+            world
+                .entity_mut(parent)
+                .add_related::<DynChildOf>(&children);
+        }
+    }
+
+    fn cleanup(&self, _world: &mut DeferredWorld, _entity: Entity) {}
+}
+
+/// A list view over a [`MutableVec`], created by [`For::each_vec`].
+pub struct ForVec<
+    Item: Clone + Send + Sync + 'static,
+    EachFn: Fn(&Item, &mut TemplateContext) + Send + Sync + 'static,
+    FallbackFn: SpawnableListGen + Send + Sync + 'static,
+> {
+    items: MutableVec<Item>,
+    each: EachFn,
+    fallback: FallbackFn,
+}
+
+impl<
+        Item: Clone + Send + Sync + 'static,
+        EachFn: Fn(&Item, &mut TemplateContext) + Send + Sync + 'static,
+        FallbackFn: SpawnableListGen + Send + Sync + 'static,
+    > ForVec<Item, EachFn, FallbackFn>
+{
+    /// Construct a list view over a [`MutableVec`]. This is the same as [`For::each_vec`].
+    pub fn new(items: MutableVec<Item>, each: EachFn, fallback: FallbackFn) -> Self {
+        Self {
+            items,
+            each,
+            fallback,
+        }
+    }
+}
+
+// A `MutableVec` list has no items function, so the constructor lives in an impl where `For`'s
+// items function is fixed to a function pointer; it is never used, since the constructor returns
+// a `ForVec`.
+impl<
+        'a: 'static,
+        M: Send + Sync + 'static,
+        Item: Clone + Send + Sync + 'static,
+        EachFn: Fn(&Item, &mut TemplateContext) + Send + Sync + 'static,
+        FallbackFn: SpawnableListGen + Send + Sync + 'static,
+    > For<'a, M, Item, for<'b> fn(InMut<'b, ListItems<Item>>), EachFn, FallbackFn>
+where
+    for<'b> fn(InMut<'b, ListItems<Item>>): IntoSystem<InMut<'a, ListItems<Item>>, (), M>,
+{
+    /// Construct a list view over a [`MutableVec`]. Rather than re-reading and diffing the
+    /// entire list when it changes, the view replays the vector's change log: pushing an item
+    /// builds one row, removing an item despawns one row, and setting an item rebuilds just
+    /// that row. If the view falls too far behind the log, or the vector's contents are
+    /// replaced wholesale, all rows are rebuilt.
+    pub fn each_vec(
+        items: MutableVec<Item>,
+        each: EachFn,
+        fallback: FallbackFn,
+    ) -> ForVec<Item, EachFn, FallbackFn> {
+        ForVec::new(items, each, fallback)
+    }
+}
+
+impl<
+        Item: Clone + Send + Sync + 'static,
+        EachFn: Fn(&Item, &mut TemplateContext) + Send + Sync + 'static,
+        FallbackFn: SpawnableListGen + Send + Sync + 'static,
+    > BundleEffect for ForVec<Item, EachFn, FallbackFn>
+{
+    fn apply(self, entity: &mut EntityWorldMut) {
+        entity.insert((
+            EffectCell::new(ForVecEffect {
+                items: self.items,
+                each: self.each,
+                fallback: self.fallback,
+                rows: RowList::new(),
+                version: None,
+            }),
+            Fragment,
+        ));
+    }
+}
+
+impl<
+        Item: Clone + Send + Sync + 'static,
+        EachFn: Fn(&Item, &mut TemplateContext) + Send + Sync + 'static,
+        FallbackFn: SpawnableListGen + Send + Sync + 'static,
+    > DynamicBundle for ForVec<Item, EachFn, FallbackFn>
+{
+    type Effect = Self;
+
+    fn get_components(
+        self,
+        _func: &mut impl FnMut(bevy::ecs::component::StorageType, bevy::ptr::OwningPtr<'_>),
+    ) -> Self::Effect {
+        self
+    }
+}
+
+unsafe impl<
+        Item: Clone + Send + Sync + 'static,
+        EachFn: Fn(&Item, &mut TemplateContext) + Send + Sync + 'static,
+        FallbackFn: SpawnableListGen + Send + Sync + 'static,
+    > Bundle for ForVec<Item, EachFn, FallbackFn>
+{
+    fn component_ids(
+        _components: &mut bevy::ecs::component::Components,
+        _ids: &mut impl FnMut(bevy::ecs::component::ComponentId),
+    ) {
+    }
+
+    fn get_component_ids(
+        _components: &bevy::ecs::component::Components,
+        _ids: &mut impl FnMut(Option<bevy::ecs::component::ComponentId>),
+    ) {
+    }
+
+    fn register_required_components(
+        _components: &mut bevy::ecs::component::Components,
+        _required_components: &mut bevy::ecs::component::RequiredComponents,
+    ) {
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RowList;
+    use crate::mutable_vec::VecChange;
+
+    /// A row list whose rows have all been built, with the given values.
+    fn built(values: &[i32]) -> RowList<i32> {
+        let mut rows = RowList::new();
+        rows.reset(values.len());
+        for index in rows.take_pending() {
+            rows.rows[index] = Some(values[index]);
+        }
+        rows
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut rows: RowList<i32> = RowList::new();
+
+        assert_eq!(rows.reset(3), vec![]);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows.take_pending(), vec![0, 1, 2]);
+        assert_eq!(rows.take_pending(), vec![]);
+
+        let mut rows = built(&[10, 20, 30]);
+        assert_eq!(rows.reset(2), vec![10, 20, 30]);
+        assert_eq!(rows.rows, vec![None, None]);
+        assert_eq!(rows.take_pending(), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut rows = built(&[10, 20, 30]);
+
+        assert_eq!(rows.apply(VecChange::Insert(1)), None);
+        assert_eq!(rows.apply(VecChange::Insert(0)), None);
+        assert_eq!(rows.rows, vec![None, Some(10), None, Some(20), Some(30)]);
+        assert_eq!(rows.take_pending(), vec![0, 2]);
+        assert!(!rows.moved);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut rows = built(&[10, 20, 30]);
+
+        assert_eq!(rows.apply(VecChange::Remove(1)), Some(20));
+        assert_eq!(rows.rows, vec![Some(10), Some(30)]);
+        assert_eq!(rows.take_pending(), vec![]);
+    }
+
+    #[test]
+    fn test_remove_pending() {
+        let mut rows = built(&[10, 20, 30]);
+
+        // Removing a row which hasn't been built yet cancels it.
+        assert_eq!(rows.apply(VecChange::Insert(1)), None);
+        assert_eq!(rows.apply(VecChange::Remove(1)), None);
+        assert_eq!(rows.take_pending(), vec![]);
+
+        // Removing an earlier row shifts the pending rows after it.
+        assert_eq!(rows.apply(VecChange::Insert(2)), None);
+        assert_eq!(rows.apply(VecChange::Remove(0)), Some(10));
+        assert_eq!(rows.rows, vec![Some(20), None, Some(30)]);
+        assert_eq!(rows.take_pending(), vec![1]);
+    }
+
+    #[test]
+    fn test_set() {
+        let mut rows = built(&[10, 20, 30]);
+
+        assert_eq!(rows.apply(VecChange::Set(1)), Some(20));
+        assert_eq!(rows.apply(VecChange::Set(1)), None);
+        assert_eq!(rows.rows, vec![Some(10), None, Some(30)]);
+        assert_eq!(rows.take_pending(), vec![1]);
+    }
+
+    #[test]
+    fn test_move() {
+        let mut rows = built(&[10, 20, 30]);
+
+        assert_eq!(rows.apply(VecChange::Insert(0)), None);
+        assert_eq!(rows.apply(VecChange::Move { from: 0, to: 3 }), None);
+        assert_eq!(rows.rows, vec![Some(10), Some(20), Some(30), None]);
+        assert_eq!(rows.take_pending(), vec![3]);
+        assert!(rows.moved);
+    }
+
+    #[test]
+    fn test_move_past_pending() {
+        let mut rows = built(&[10, 20, 30]);
+
+        assert_eq!(rows.apply(VecChange::Set(0)), Some(10));
+        assert_eq!(rows.apply(VecChange::Move { from: 2, to: 0 }), None);
+        assert_eq!(rows.rows, vec![Some(30), None, Some(20)]);
+        assert_eq!(rows.take_pending(), vec![1]);
+    }
+}
@@ -9,6 +9,8 @@ use bevy::{
 
 use crate::{
     effect_cell::{AnyEffect, EffectCell},
+    error_boundary::report_error,
+    tracking::run_tracked,
     ComputationOf,
 };
 
@@ -25,16 +27,20 @@ impl<B: Bundle, FactoryFn: Fn() -> B> AnyEffect for InsertWhenEffect<B, FactoryF
         };
         let owner = owner.get();
         // Run the condition and see if the result changed.
-        let test = world.run_system(self.test_sys);
-        if let Ok(test) = test {
-            if self.state != test {
-                if test {
-                    world.commands().entity(owner).insert((self.factory)());
-                } else {
-                    world.commands().entity(owner).remove::<B>();
-                }
-                self.state = test;
+        let test = match run_tracked(world, entity, |world| world.run_system(self.test_sys)) {
+            Ok(test) => test,
+            Err(err) => {
+                report_error(world, entity, err.to_string());
+                return;
             }
+        };
+        if self.state != test {
+            if test {
+                world.commands().entity(owner).insert((self.factory)());
+            } else {
+                world.commands().entity(owner).remove::<B>();
+            }
+            self.state = test;
         }
     }
 
@@ -0,0 +1,85 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+};
+
+/// Keyed matching of two sequences. For each element in `next`, returns the index of the element
+/// in `prev` which has the same key, or `None` if there is no such element. Each element of `prev`
+/// is matched at most once; if a key appears more than once, occurrences are matched in order.
+///
+/// Runs in O(n) time, regardless of how the elements have been reordered.
+pub fn keyed_match<K: Hash + Eq>(prev: &[K], next: &[K]) -> Vec<Option<usize>> {
+    let mut prev_index: HashMap<&K, VecDeque<usize>> = HashMap::with_capacity(prev.len());
+    for (i, key) in prev.iter().enumerate() {
+        prev_index.entry(key).or_default().push_back(i);
+    }
+
+    next.iter()
+        .map(|key| {
+            prev_index
+                .get_mut(key)
+                .and_then(|indices| indices.pop_front())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::keyed_match;
+
+    #[test]
+    fn test_empty() {
+        let a: Vec<i32> = vec![];
+        let b: Vec<i32> = vec![];
+
+        assert_eq!(keyed_match(&a, &b), vec![]);
+    }
+
+    #[test]
+    fn test_empty_left() {
+        let a: Vec<i32> = vec![];
+        let b: Vec<i32> = vec![0, 1];
+
+        assert_eq!(keyed_match(&a, &b), vec![None, None]);
+    }
+
+    #[test]
+    fn test_empty_right() {
+        let a: Vec<i32> = vec![0, 1];
+        let b: Vec<i32> = vec![];
+
+        assert_eq!(keyed_match(&a, &b), vec![]);
+    }
+
+    #[test]
+    fn test_same() {
+        let a: Vec<i32> = vec![1, 2, 3];
+        let b: Vec<i32> = vec![1, 2, 3];
+
+        assert_eq!(keyed_match(&a, &b), vec![Some(0), Some(1), Some(2)]);
+    }
+
+    #[test]
+    fn test_reversed() {
+        let a: Vec<i32> = vec![1, 2, 3];
+        let b: Vec<i32> = vec![3, 2, 1];
+
+        assert_eq!(keyed_match(&a, &b), vec![Some(2), Some(1), Some(0)]);
+    }
+
+    #[test]
+    fn test_insert_remove() {
+        let a: Vec<i32> = vec![1, 2, 3, 4];
+        let b: Vec<i32> = vec![0, 4, 2, 5];
+
+        assert_eq!(keyed_match(&a, &b), vec![None, Some(3), Some(1), None]);
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let a: Vec<i32> = vec![1, 2, 1];
+        let b: Vec<i32> = vec![1, 1, 1, 2];
+
+        assert_eq!(keyed_match(&a, &b), vec![Some(0), Some(2), None, Some(1)]);
+    }
+}
@@ -0,0 +1,142 @@
+use std::{fmt::Debug, sync::Arc};
+
+use bevy::prelude::*;
+
+use crate::{mutable::ReadMutable, IntoSignal, Mutable, Signal, WriteMutable};
+
+/// A projection of a [`Mutable`] onto one of its fields. This allows widgets to be bound to
+/// individual fields of a larger struct: the lens can be read and written like a mutable, and
+/// converted into a [`Signal`] for the field.
+///
+/// Lenses are created with [`Mutable::lens`].
+pub struct MutableLens<T, U> {
+    mutable: Mutable<T>,
+    get: fn(&T) -> &U,
+    get_mut: fn(&mut T) -> &mut U,
+}
+
+impl<T, U> Copy for MutableLens<T, U> {}
+impl<T, U> Clone for MutableLens<T, U> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Mutable<T> {
+    /// Create a lens which projects this mutable onto a field, given functions which return a
+    /// reference and a mutable reference to the field:
+    ///
+    /// ```rust,ignore
+    /// let x = position.lens(|pos| &pos.x, |pos| &mut pos.x);
+    /// ```
+    pub fn lens<U>(&self, get: fn(&T) -> &U, get_mut: fn(&mut T) -> &mut U) -> MutableLens<T, U> {
+        MutableLens {
+            mutable: *self,
+            get,
+            get_mut,
+        }
+    }
+}
+
+impl<T, U> MutableLens<T, U>
+where
+    T: Send + Sync + 'static,
+    U: Send + Sync + 'static,
+{
+    /// The mutable that this lens projects.
+    pub fn mutable(&self) -> Mutable<T> {
+        self.mutable
+    }
+
+    /// Get the value of the field with Copy semantics. This adds the underlying mutable to the
+    /// current tracking scope.
+    pub fn get<R: ReadMutable>(&self, cx: &R) -> U
+    where
+        U: Copy,
+    {
+        cx.read_mutable_map(&self.mutable, |value| *(self.get)(value))
+    }
+
+    /// Get the value of the field with Clone semantics. This adds the underlying mutable to the
+    /// current tracking scope.
+    pub fn get_clone<R: ReadMutable>(&self, cx: &R) -> U
+    where
+        U: Clone,
+    {
+        cx.read_mutable_map(&self.mutable, |value| (self.get)(value).clone())
+    }
+
+    /// Set the value of the field. The underlying mutable is only marked as changed if the new
+    /// value differs from the existing value of the field.
+    pub fn set<W: WriteMutable>(&self, cx: &mut W, value: U)
+    where
+        U: PartialEq,
+    {
+        let (get, get_mut) = (self.get, self.get_mut);
+        cx.update_mutable(self.mutable.id(), move |mut cell: Mut<T>| {
+            if *get(&cell) != value {
+                *get_mut(&mut cell) = value;
+            }
+        });
+    }
+
+    /// Returns a signal for the field.
+    pub fn signal(&self) -> Signal<U> {
+        Signal::Lens(LensSignal(Arc::new(*self)))
+    }
+}
+
+impl<T, U> IntoSignal<U> for MutableLens<T, U>
+where
+    T: Send + Sync + 'static,
+    U: Send + Sync + 'static,
+{
+    fn into_signal(self) -> Signal<U> {
+        self.signal()
+    }
+}
+
+/// Type-erased read access to a lens, which hides the type of the underlying mutable.
+trait ReadLens<U>: Send + Sync {
+    fn read_map(&self, world: &World, f: &mut dyn FnMut(&U));
+}
+
+impl<T, U> ReadLens<U> for MutableLens<T, U>
+where
+    T: Send + Sync + 'static,
+    U: Send + Sync + 'static,
+{
+    fn read_map(&self, world: &World, f: &mut dyn FnMut(&U)) {
+        f((self.get)(world.read_mutable_as_ref(&self.mutable)));
+    }
+}
+
+/// A signal which reads a field of a mutable through a [`MutableLens`].
+pub struct LensSignal<U>(Arc<dyn ReadLens<U>>);
+
+impl<U> Clone for LensSignal<U> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<U> LensSignal<U> {
+    /// Read the field using a mapping function. This adds the underlying mutable to the current
+    /// tracking scope.
+    pub(crate) fn map<V>(&self, world: &World, f: impl FnOnce(&U) -> V) -> V {
+        let mut f = Some(f);
+        let mut result = None;
+        self.0.read_map(world, &mut |value| {
+            if let Some(f) = f.take() {
+                result = Some(f(value));
+            }
+        });
+        result.unwrap()
+    }
+}
+
+impl<U> Debug for LensSignal<U> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("LensSignal")
+    }
+}
@@ -1,47 +1,102 @@
+mod await_node;
+mod branch_cache;
 mod calc;
 mod callback;
+mod change_log;
+mod component;
 mod computations;
 mod cond;
+mod context;
+mod derived;
 mod dyn_children;
 mod effect_cell;
+mod error_boundary;
 mod foreach;
+mod foreach_keyed;
+mod foreach_vec;
 mod insert_when;
+mod keyed;
 mod lcs;
+mod lens;
 mod memo;
 mod mutable;
+mod mutable_map;
+mod mutable_vec;
+mod node_ref;
+mod observer;
 mod owner;
+mod portal;
+mod reconcile;
 mod signal;
 mod style;
 mod switch;
 mod template;
+mod template_asset;
+mod timer;
+mod tracking;
+mod transition;
+mod ui_state;
+mod undo;
 
+pub use await_node::Await;
 use bevy::{
     app::{App, Plugin, PostUpdate, Update},
     prelude::IntoSystemConfigs,
 };
+pub use branch_cache::EvictCachedBranches;
 pub use calc::Calc;
 pub use callback::CreateCallback;
+pub use component::{ComponentContext, ComponentProps, FunctionComponent};
 pub use computations::{ComputationOf, Computations};
 pub use cond::Cond;
+pub use context::UseContext;
+pub use derived::DerivedSignal;
 pub use dyn_children::{
     DynChildOf, DynChildSpawner, DynChildSpawnerCommands, DynChildren, Fragment,
 };
 use effect_cell::update_effects;
+pub use error_boundary::{BoundaryError, ErrorBoundary};
 pub use foreach::{For, ListItems};
+pub use foreach_keyed::ForKeyed;
+pub use foreach_vec::ForVec;
 pub use insert_when::InsertWhen;
+pub use lens::{LensSignal, MutableLens};
 pub use memo::{CreateMemo, Memo, ReadMemo};
-pub use mutable::{CreateMutable, Mutable, ReadMutable, WriteMutable};
+pub use mutable::{CreateKeyedMutable, CreateMutable, Mutable, ReadMutable, WriteMutable};
+pub use mutable_map::{MapChange, MutableMap};
+pub use mutable_vec::{MutableVec, VecChange};
+pub use node_ref::NodeRef;
+pub use observer::{CreateObserver, EntityObservers};
 pub use owner::{Owned, OwnedBy};
+pub use portal::Portal;
+pub use reconcile::{InvokeKeyed, SignalSlot};
 pub use signal::{IntoSignal, Signal};
 pub use style::{StyleHandle, StyleTuple, Styles};
 pub use switch::Switch;
 pub use template::{Invoke, InvokeWith, SpawnArc, SpawnableListGen, Template, TemplateContext};
+pub use template_asset::{
+    AssetTemplate, TemplateAsset, TemplateAssetError, TemplateAssetLoader, TemplateAssetPlugin,
+    TemplateNode, TemplateRegistry,
+};
+pub use timer::{CreateTimer, RateLimit, TimerAction, TimerClock};
+pub use tracking::{ReadTracked, TrackingScope};
+pub use transition::{BistableTransitionState, TransitionCond, TransitionSwitch};
+pub use ui_state::UiStateSnapshot;
+pub use undo::{Redo, Transaction, Undo, UndoHistory};
+
+pub use thorium_ui_macros::{template, ui, TemplateProps};
+
+/// Re-exported for the expansions of the `template!` and `ui!` macros, so that crates which use
+/// them don't need their own dependency on `bevy`.
+#[doc(hidden)]
+pub use bevy as __bevy;
 
 pub struct ThoriumUiCorePlugin;
 
 impl Plugin for ThoriumUiCorePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, update_effects);
+        app.init_resource::<UndoHistory>();
+        app.add_systems(Update, (timer::tick_timers, update_effects).chain());
         app.add_systems(
             PostUpdate,
             (
@@ -6,8 +6,11 @@ use bevy::{
 };
 
 use crate::{
+    derived::DerivedSignal,
     effect_cell::{AnyEffect, EffectCell},
+    error_boundary::report_error,
     template::TemplateContext,
+    tracking::{run_tracked, track_component},
     OwnedBy,
 };
 
@@ -34,7 +37,14 @@ pub(crate) struct MemoValue<P>(P);
 
 impl<P: PartialEq + Clone + Send + Sync + 'static> AnyEffect for MemoEffect<P> {
     fn update(&mut self, world: &mut World, entity: Entity) {
-        let value = world.run_system(self.system).unwrap();
+        let value = match run_tracked(world, entity, |world| world.run_system(self.system)) {
+            Ok(value) => value,
+            Err(err) => {
+                // Keep the previous value.
+                report_error(world, entity, err.to_string());
+                return;
+            }
+        };
         let Ok(mut entt) = world.get_entity_mut(entity) else {
             return;
         };
@@ -174,10 +184,15 @@ pub trait ReadMemo {
     fn read_memo_map<P, U, F: Fn(&P) -> U>(&self, derived: &Memo<P>, f: F) -> U
     where
         P: Send + Sync + 'static;
+
+    /// Computes the value of a derived signal. This adds any dependencies of the derived signal
+    /// to the current tracking scope.
+    fn read_derived<P>(&self, derived: &DerivedSignal<P>) -> P;
 }
 
 impl ReadMemo for World {
     fn read_memo<P: Clone + Send + Sync + 'static>(&self, memo: Memo<P>) -> P {
+        track_component::<MemoValue<P>>(self, memo.entity, true);
         self.entity(memo.entity)
             .get::<MemoValue<P>>()
             .unwrap()
@@ -189,15 +204,21 @@ impl ReadMemo for World {
     where
         P: Send + Sync + 'static,
     {
+        track_component::<MemoValue<P>>(self, memo.entity, true);
         self.entity(memo.entity)
             .get::<MemoValue<P>>()
             .map(|value| f(&value.0))
             .unwrap()
     }
+
+    fn read_derived<P>(&self, derived: &DerivedSignal<P>) -> P {
+        derived.get(self)
+    }
 }
 
 impl ReadMemo for DeferredWorld<'_> {
     fn read_memo<P: Clone + Send + Sync + 'static>(&self, memo: Memo<P>) -> P {
+        track_component::<MemoValue<P>>(self, memo.entity, true);
         self.entity(memo.entity)
             .get::<MemoValue<P>>()
             .unwrap()
@@ -209,9 +230,14 @@ impl ReadMemo for DeferredWorld<'_> {
     where
         P: Send + Sync + 'static,
     {
+        track_component::<MemoValue<P>>(self, memo.entity, true);
         self.entity(memo.entity)
             .get::<MemoValue<P>>()
             .map(|value| f(&value.0))
             .unwrap()
     }
+
+    fn read_derived<P>(&self, derived: &DerivedSignal<P>) -> P {
+        derived.get(self)
+    }
 }
@@ -1,8 +1,17 @@
-use std::marker::PhantomData;
+use std::{collections::HashMap, hash::Hash, marker::PhantomData};
 
-use bevy::{ecs::world::DeferredWorld, prelude::*};
+use bevy::{ecs::world::DeferredWorld, prelude::*, reflect::GetTypeRegistration};
 
-use crate::{owner::OwnedBy, Signal, TemplateContext};
+use crate::{
+    change_log::ChangeLog,
+    lens::LensSignal,
+    mutable_map::{MapState, MutableMap},
+    mutable_vec::{MutableVec, VecState},
+    owner::OwnedBy,
+    tracking::track_component,
+    ui_state::StateKey,
+    Signal, TemplateContext,
+};
 
 /// Contains a mutable reactive value.
 #[derive(Component)]
@@ -127,6 +136,10 @@ pub trait ReadMutable {
     fn read_mutable_map<T, U, F: Fn(&T) -> U>(&self, mutable: &Mutable<T>, f: F) -> U
     where
         T: Send + Sync + 'static;
+
+    /// Read a field of a mutable variable through a lens, using a mapping function. Calling this
+    /// function adds the underlying mutable to the current tracking scope.
+    fn read_lens_map<U, V, F: FnOnce(&U) -> V>(&self, lens: &LensSignal<U>, f: F) -> V;
 }
 
 /// Trait for low-level write-access to mutables given an entity id.
@@ -150,6 +163,40 @@ pub trait CreateMutable {
     fn create_mutable<T>(&mut self, init: T) -> Mutable<T>
     where
         T: Send + Sync + 'static;
+
+    /// Create a new [`MutableVec`].
+    fn create_mutable_vec<T>(&mut self, init: Vec<T>) -> MutableVec<T>
+    where
+        T: Send + Sync + 'static,
+    {
+        MutableVec::new(self.create_mutable(VecState {
+            items: init,
+            log: ChangeLog::new(),
+        }))
+    }
+
+    /// Create a new [`MutableMap`].
+    fn create_mutable_map<K, V>(&mut self, init: HashMap<K, V>) -> MutableMap<K, V>
+    where
+        K: Hash + Eq + Clone + Send + Sync + 'static,
+        V: Send + Sync + 'static,
+    {
+        MutableMap::new(self.create_mutable(MapState {
+            entries: init,
+            log: ChangeLog::new(),
+        }))
+    }
+}
+
+/// Trait for creating mutable variables whose values are persisted in the
+/// [`UiStateSnapshot`](crate::UiStateSnapshot).
+pub trait CreateKeyedMutable {
+    /// Create a new [`Mutable`] whose value is persisted in the
+    /// [`UiStateSnapshot`](crate::UiStateSnapshot) under a stable `key`. If the snapshot already
+    /// has a value for the key, that value is used instead of `init`.
+    fn create_keyed_mutable<T>(&mut self, key: impl Into<String>, init: T) -> Mutable<T>
+    where
+        T: Reflect + FromReflect + GetTypeRegistration + Clone;
 }
 
 // /// Custom command which updates the state of a mutable cell.
@@ -173,6 +220,7 @@ impl ReadMutable for World {
     where
         T: Send + Sync + Copy + 'static,
     {
+        track_component::<MutableCell<T>>(self, mutable.cell, true);
         let mutable_entity = self.entity(mutable.cell);
         mutable_entity.get::<MutableCell<T>>().unwrap().0
     }
@@ -181,6 +229,7 @@ impl ReadMutable for World {
     where
         T: Send + Sync + Clone + 'static,
     {
+        track_component::<MutableCell<T>>(self, mutable.cell, true);
         let mutable_entity = self.entity(mutable.cell);
         mutable_entity.get::<MutableCell<T>>().unwrap().0.clone()
     }
@@ -189,6 +238,7 @@ impl ReadMutable for World {
     where
         T: Send + Sync + 'static,
     {
+        track_component::<MutableCell<T>>(self, mutable.cell, true);
         let mutable_entity = self.entity(mutable.cell);
         &mutable_entity.get::<MutableCell<T>>().unwrap().0
     }
@@ -197,9 +247,14 @@ impl ReadMutable for World {
     where
         T: Send + Sync + 'static,
     {
+        track_component::<MutableCell<T>>(self, mutable.cell, true);
         let mutable_entity = self.entity(mutable.cell);
         f(&mutable_entity.get::<MutableCell<T>>().unwrap().0)
     }
+
+    fn read_lens_map<U, V, F: FnOnce(&U) -> V>(&self, lens: &LensSignal<U>, f: F) -> V {
+        lens.map(self, f)
+    }
 }
 
 impl WriteMutable for World {
@@ -239,6 +294,21 @@ impl CreateMutable for World {
     }
 }
 
+impl CreateKeyedMutable for World {
+    fn create_keyed_mutable<T>(&mut self, key: impl Into<String>, init: T) -> Mutable<T>
+    where
+        T: Reflect + FromReflect + GetTypeRegistration + Clone,
+    {
+        let cell = self
+            .spawn((MutableCell::<T>(init), StateKey::new::<T>(key)))
+            .id();
+        Mutable {
+            cell,
+            marker: PhantomData,
+        }
+    }
+}
+
 impl CreateMutable for Commands<'_, '_> {
     fn create_mutable<T>(&mut self, init: T) -> Mutable<T>
     where
@@ -252,6 +322,21 @@ impl CreateMutable for Commands<'_, '_> {
     }
 }
 
+impl CreateKeyedMutable for Commands<'_, '_> {
+    fn create_keyed_mutable<T>(&mut self, key: impl Into<String>, init: T) -> Mutable<T>
+    where
+        T: Reflect + FromReflect + GetTypeRegistration + Clone,
+    {
+        let cell = self
+            .spawn((MutableCell::<T>(init), StateKey::new::<T>(key)))
+            .id();
+        Mutable {
+            cell,
+            marker: PhantomData,
+        }
+    }
+}
+
 impl CreateMutable for EntityCommands<'_> {
     fn create_mutable<T>(&mut self, init: T) -> Mutable<T>
     where
@@ -269,6 +354,27 @@ impl CreateMutable for EntityCommands<'_> {
     }
 }
 
+impl CreateKeyedMutable for EntityCommands<'_> {
+    fn create_keyed_mutable<T>(&mut self, key: impl Into<String>, init: T) -> Mutable<T>
+    where
+        T: Reflect + FromReflect + GetTypeRegistration + Clone,
+    {
+        let parent = self.id();
+        let cell = self
+            .commands()
+            .spawn((
+                MutableCell::<T>(init),
+                StateKey::new::<T>(key),
+                OwnedBy(parent),
+            ))
+            .id();
+        Mutable {
+            cell,
+            marker: PhantomData,
+        }
+    }
+}
+
 impl CreateMutable for ChildSpawnerCommands<'_> {
     fn create_mutable<T>(&mut self, init: T) -> Mutable<T>
     where
@@ -286,6 +392,27 @@ impl CreateMutable for ChildSpawnerCommands<'_> {
     }
 }
 
+impl CreateKeyedMutable for ChildSpawnerCommands<'_> {
+    fn create_keyed_mutable<T>(&mut self, key: impl Into<String>, init: T) -> Mutable<T>
+    where
+        T: Reflect + FromReflect + GetTypeRegistration + Clone,
+    {
+        let owner = self.target_entity();
+        let cell = self
+            .commands_mut()
+            .spawn((
+                MutableCell::<T>(init),
+                StateKey::new::<T>(key),
+                OwnedBy(owner),
+            ))
+            .id();
+        Mutable {
+            cell,
+            marker: PhantomData,
+        }
+    }
+}
+
 impl CreateMutable for TemplateContext<'_> {
     fn create_mutable<T>(&mut self, init: T) -> Mutable<T>
     where
@@ -303,11 +430,33 @@ impl CreateMutable for TemplateContext<'_> {
     }
 }
 
+impl CreateKeyedMutable for TemplateContext<'_> {
+    fn create_keyed_mutable<T>(&mut self, key: impl Into<String>, init: T) -> Mutable<T>
+    where
+        T: Reflect + FromReflect + GetTypeRegistration + Clone,
+    {
+        let owner = self.target_entity();
+        let cell = self
+            .commands()
+            .spawn((
+                MutableCell::<T>(init),
+                StateKey::new::<T>(key),
+                OwnedBy(owner),
+            ))
+            .id();
+        Mutable {
+            cell,
+            marker: PhantomData,
+        }
+    }
+}
+
 impl ReadMutable for DeferredWorld<'_> {
     fn read_mutable<T>(&self, mutable: &Mutable<T>) -> T
     where
         T: Send + Sync + Copy + 'static,
     {
+        track_component::<MutableCell<T>>(self, mutable.cell, true);
         let mutable_entity = self.entity(mutable.cell);
         mutable_entity.get::<MutableCell<T>>().unwrap().0
     }
@@ -316,6 +465,7 @@ impl ReadMutable for DeferredWorld<'_> {
     where
         T: Send + Sync + Clone + 'static,
     {
+        track_component::<MutableCell<T>>(self, mutable.cell, true);
         let mutable_entity = self.entity(mutable.cell);
         mutable_entity.get::<MutableCell<T>>().unwrap().0.clone()
     }
@@ -324,6 +474,7 @@ impl ReadMutable for DeferredWorld<'_> {
     where
         T: Send + Sync + 'static,
     {
+        track_component::<MutableCell<T>>(self, mutable.cell, true);
         let mutable_entity = self.entity(mutable.cell);
         &mutable_entity.get::<MutableCell<T>>().unwrap().0
     }
@@ -332,9 +483,14 @@ impl ReadMutable for DeferredWorld<'_> {
     where
         T: Send + Sync + 'static,
     {
+        track_component::<MutableCell<T>>(self, mutable.cell, true);
         let mutable_entity = self.entity(mutable.cell);
         f(&mutable_entity.get::<MutableCell<T>>().unwrap().0)
     }
+
+    fn read_lens_map<U, V, F: FnOnce(&U) -> V>(&self, lens: &LensSignal<U>, f: F) -> V {
+        lens.map(self, f)
+    }
 }
 
 impl WriteMutable for DeferredWorld<'_> {
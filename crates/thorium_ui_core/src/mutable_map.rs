@@ -0,0 +1,158 @@
+use std::{collections::HashMap, hash::Hash};
+
+use bevy::prelude::*;
+
+use crate::{change_log::ChangeLog, Mutable, ReadMutable, WriteMutable};
+
+/// A fine-grained change to a [`MutableMap`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MapChange<K> {
+    /// An entry with a new key was inserted.
+    Insert(K),
+    /// The value of an existing entry was replaced or modified.
+    Set(K),
+    /// The entry with the given key was removed.
+    Remove(K),
+}
+
+/// The contents of a [`MutableMap`], along with its change log.
+pub(crate) struct MapState<K, V> {
+    pub(crate) entries: HashMap<K, V>,
+    pub(crate) log: ChangeLog<MapChange<K>>,
+}
+
+/// A reactive hash map. Like [`MutableVec`](crate::MutableVec), each modification is recorded in
+/// a change log, so that consumers can find out which entries changed without comparing the
+/// entire map.
+///
+/// Created with [`CreateMutable::create_mutable_map`](crate::CreateMutable::create_mutable_map).
+pub struct MutableMap<K, V> {
+    pub(crate) cell: Mutable<MapState<K, V>>,
+}
+
+impl<K, V> Copy for MutableMap<K, V> {}
+impl<K, V> Clone for MutableMap<K, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K, V> MutableMap<K, V>
+where
+    K: Hash + Eq + Clone + Send + Sync + 'static,
+    V: Send + Sync + 'static,
+{
+    pub(crate) fn new(cell: Mutable<MapState<K, V>>) -> Self {
+        Self { cell }
+    }
+
+    /// The entity that holds the map.
+    pub fn id(&self) -> Entity {
+        self.cell.id()
+    }
+
+    /// Get a reference to the entries of the map. This adds the map to the current tracking
+    /// scope.
+    pub fn as_map<'a, R: ReadMutable>(&self, cx: &'a R) -> &'a HashMap<K, V> {
+        &cx.read_mutable_as_ref(&self.cell).entries
+    }
+
+    /// The number of entries in the map.
+    pub fn len<R: ReadMutable>(&self, cx: &R) -> usize {
+        self.as_map(cx).len()
+    }
+
+    /// True if the map is empty.
+    pub fn is_empty<R: ReadMutable>(&self, cx: &R) -> bool {
+        self.as_map(cx).is_empty()
+    }
+
+    /// True if the map contains an entry for `key`.
+    pub fn contains_key<R: ReadMutable>(&self, cx: &R, key: &K) -> bool {
+        self.as_map(cx).contains_key(key)
+    }
+
+    /// Get a clone of the value for `key`, if there is one.
+    pub fn get_clone<R: ReadMutable>(&self, cx: &R, key: &K) -> Option<V>
+    where
+        V: Clone,
+    {
+        self.as_map(cx).get(key).cloned()
+    }
+
+    /// The current version of the map, which is advanced by each change.
+    pub fn version<R: ReadMutable>(&self, cx: &R) -> usize {
+        cx.read_mutable_as_ref(&self.cell).log.version()
+    }
+
+    /// Returns the changes made since `version`, in order, or `None` if the log no longer
+    /// extends back that far (in which case the consumer should re-read the entire map).
+    pub fn changes_since<R: ReadMutable>(
+        &self,
+        cx: &R,
+        version: usize,
+    ) -> Option<Vec<MapChange<K>>> {
+        cx.read_mutable_as_ref(&self.cell).log.since(version)
+    }
+
+    /// Insert an entry, returning the previous value for the key if there was one. Does nothing
+    /// if the new value is equal to the existing one.
+    pub fn insert<W: WriteMutable>(&self, cx: &mut W, key: K, value: V) -> Option<V>
+    where
+        V: PartialEq,
+    {
+        let mut result = None;
+        cx.update_mutable(self.cell.id(), |mut state: Mut<MapState<K, V>>| match state
+            .entries
+            .get(&key)
+            .map(|prev| *prev == value)
+        {
+            Some(true) => {}
+            Some(false) => {
+                result = state.entries.insert(key.clone(), value);
+                state.log.record(MapChange::Set(key));
+            }
+            None => {
+                state.entries.insert(key.clone(), value);
+                state.log.record(MapChange::Insert(key));
+            }
+        });
+        result
+    }
+
+    /// Modify the value for `key` in place. Does nothing if there is no entry for the key.
+    pub fn update<W: WriteMutable, F: FnOnce(&mut V)>(&self, cx: &mut W, key: &K, f: F) {
+        cx.update_mutable(self.cell.id(), |mut state: Mut<MapState<K, V>>| {
+            if state.entries.contains_key(key) {
+                f(state.entries.get_mut(key).unwrap());
+                state.log.record(MapChange::Set(key.clone()));
+            }
+        });
+    }
+
+    /// Remove the entry for `key`, returning its value if there was one.
+    pub fn remove<W: WriteMutable>(&self, cx: &mut W, key: &K) -> Option<V> {
+        let mut result = None;
+        cx.update_mutable(self.cell.id(), |mut state: Mut<MapState<K, V>>| {
+            if state.entries.contains_key(key) {
+                result = state.entries.remove(key);
+                state.log.record(MapChange::Remove(key.clone()));
+            }
+        });
+        result
+    }
+
+    /// Remove all entries from the map.
+    pub fn clear<W: WriteMutable>(&self, cx: &mut W) {
+        self.replace(cx, HashMap::new());
+    }
+
+    /// Replace the entire contents of the map. Consumers of the change log will re-read the
+    /// whole map.
+    pub fn replace<W: WriteMutable>(&self, cx: &mut W, entries: HashMap<K, V>) {
+        cx.update_mutable(self.cell.id(), move |mut state: Mut<MapState<K, V>>| {
+            state.entries = entries;
+            state.log.reset();
+        });
+    }
+}
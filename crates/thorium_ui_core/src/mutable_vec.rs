@@ -0,0 +1,183 @@
+use bevy::prelude::*;
+
+use crate::{change_log::ChangeLog, Mutable, ReadMutable, WriteMutable};
+
+/// A fine-grained change to a [`MutableVec`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VecChange {
+    /// An item was inserted at the given index.
+    Insert(usize),
+    /// The item at the given index was removed.
+    Remove(usize),
+    /// The item at the given index was replaced or modified.
+    Set(usize),
+    /// An item was moved from one index to another, as if it had been removed and then
+    /// re-inserted.
+    Move { from: usize, to: usize },
+}
+
+/// The contents of a [`MutableVec`], along with its change log.
+pub(crate) struct VecState<T> {
+    pub(crate) items: Vec<T>,
+    pub(crate) log: ChangeLog<VecChange>,
+}
+
+/// A reactive vector. Unlike a `Mutable<Vec<T>>`, each modification is recorded in a change log,
+/// which allows list views such as [`ForVec`](crate::ForVec) to update only the
+/// rows which were affected, without comparing the old and new lists.
+///
+/// Created with [`CreateMutable::create_mutable_vec`](crate::CreateMutable::create_mutable_vec).
+pub struct MutableVec<T> {
+    pub(crate) cell: Mutable<VecState<T>>,
+}
+
+impl<T> Copy for MutableVec<T> {}
+impl<T> Clone for MutableVec<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> MutableVec<T>
+where
+    T: Send + Sync + 'static,
+{
+    pub(crate) fn new(cell: Mutable<VecState<T>>) -> Self {
+        Self { cell }
+    }
+
+    /// The entity that holds the vector.
+    pub fn id(&self) -> Entity {
+        self.cell.id()
+    }
+
+    /// Get a reference to the items of the vector. This adds the vector to the current tracking
+    /// scope.
+    pub fn as_slice<'a, R: ReadMutable>(&self, cx: &'a R) -> &'a [T] {
+        &cx.read_mutable_as_ref(&self.cell).items
+    }
+
+    /// The number of items in the vector.
+    pub fn len<R: ReadMutable>(&self, cx: &R) -> usize {
+        self.as_slice(cx).len()
+    }
+
+    /// True if the vector is empty.
+    pub fn is_empty<R: ReadMutable>(&self, cx: &R) -> bool {
+        self.as_slice(cx).is_empty()
+    }
+
+    /// Get a clone of the item at `index`, if there is one.
+    pub fn get_clone<R: ReadMutable>(&self, cx: &R, index: usize) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.as_slice(cx).get(index).cloned()
+    }
+
+    /// The current version of the vector, which is advanced by each change.
+    pub fn version<R: ReadMutable>(&self, cx: &R) -> usize {
+        cx.read_mutable_as_ref(&self.cell).log.version()
+    }
+
+    /// Returns the changes made since `version`, in order, or `None` if the log no longer
+    /// extends back that far (in which case the consumer should re-read the entire vector).
+    pub fn changes_since<R: ReadMutable>(&self, cx: &R, version: usize) -> Option<Vec<VecChange>> {
+        cx.read_mutable_as_ref(&self.cell).log.since(version)
+    }
+
+    /// Append an item to the end of the vector.
+    pub fn push<W: WriteMutable>(&self, cx: &mut W, item: T) {
+        self.modify(cx, move |state| {
+            state.items.push(item);
+            state.log.record(VecChange::Insert(state.items.len() - 1));
+        });
+    }
+
+    /// Remove the last item of the vector and return it.
+    pub fn pop<W: WriteMutable>(&self, cx: &mut W) -> Option<T> {
+        let mut result = None;
+        self.modify(cx, |state| {
+            result = state.items.pop();
+            if result.is_some() {
+                state.log.record(VecChange::Remove(state.items.len()));
+            }
+        });
+        result
+    }
+
+    /// Insert an item at `index`, shifting all items after it. Panics if `index` is greater
+    /// than the length of the vector.
+    pub fn insert<W: WriteMutable>(&self, cx: &mut W, index: usize, item: T) {
+        self.modify(cx, move |state| {
+            state.items.insert(index, item);
+            state.log.record(VecChange::Insert(index));
+        });
+    }
+
+    /// Remove and return the item at `index`, shifting all items after it. Panics if `index`
+    /// is out of bounds.
+    pub fn remove<W: WriteMutable>(&self, cx: &mut W, index: usize) -> T {
+        let mut result = None;
+        self.modify(cx, |state| {
+            result = Some(state.items.remove(index));
+            state.log.record(VecChange::Remove(index));
+        });
+        result.unwrap()
+    }
+
+    /// Replace the item at `index`. Does nothing if the new item is equal to the existing one.
+    /// Panics if `index` is out of bounds.
+    pub fn set<W: WriteMutable>(&self, cx: &mut W, index: usize, item: T)
+    where
+        T: PartialEq,
+    {
+        cx.update_mutable(self.cell.id(), move |mut state: Mut<VecState<T>>| {
+            if state.items[index] != item {
+                state.items[index] = item;
+                state.log.record(VecChange::Set(index));
+            }
+        });
+    }
+
+    /// Modify the item at `index` in place. Panics if `index` is out of bounds.
+    pub fn update<W: WriteMutable, F: FnOnce(&mut T)>(&self, cx: &mut W, index: usize, f: F) {
+        self.modify(cx, move |state| {
+            f(&mut state.items[index]);
+            state.log.record(VecChange::Set(index));
+        });
+    }
+
+    /// Move the item at `from` so that it ends up at index `to`. Panics if either index is out
+    /// of bounds.
+    pub fn move_item<W: WriteMutable>(&self, cx: &mut W, from: usize, to: usize) {
+        if from == to {
+            return;
+        }
+        self.modify(cx, move |state| {
+            let item = state.items.remove(from);
+            state.items.insert(to, item);
+            state.log.record(VecChange::Move { from, to });
+        });
+    }
+
+    /// Remove all items from the vector.
+    pub fn clear<W: WriteMutable>(&self, cx: &mut W) {
+        self.replace(cx, Vec::new());
+    }
+
+    /// Replace the entire contents of the vector. Consumers of the change log will re-read the
+    /// whole vector.
+    pub fn replace<W: WriteMutable>(&self, cx: &mut W, items: Vec<T>) {
+        self.modify(cx, move |state| {
+            state.items = items;
+            state.log.reset();
+        });
+    }
+
+    fn modify<W: WriteMutable, F: FnOnce(&mut VecState<T>)>(&self, cx: &mut W, f: F) {
+        cx.update_mutable(self.cell.id(), move |mut state: Mut<VecState<T>>| {
+            f(&mut state);
+        });
+    }
+}
@@ -0,0 +1,128 @@
+use bevy::{
+    ecs::{
+        bundle::{BundleEffect, DynamicBundle},
+        component::HookContext,
+        world::DeferredWorld,
+    },
+    prelude::*,
+};
+
+use crate::{mutable::MutableCell, CreateMutable, Mutable, ReadMutable, Signal};
+
+/// A reference to an entity which has not been spawned yet. A `NodeRef` is created empty, and
+/// is filled in when it is inserted into an entity's spawn bundle; it is cleared again when that
+/// entity is despawned. This lets closures and computations anywhere in a template refer to an
+/// entity without splitting up the spawn tuple to get its id:
+///
+/// ```rust,ignore
+/// let button = NodeRef::new(tc);
+/// tc.spawn((
+///     Node::default(),
+///     button,
+///     dyn_children![(
+///         Node::default(),
+///         computations![Calc::new(
+///             move |world: DeferredWorld| {
+///                 button.get(&world).is_some_and(|id| world.is_hovering(id))
+///             },
+///             |hovering, ent| { /* ... */ },
+///         )],
+///     )],
+/// ));
+/// ```
+///
+/// Reading a `NodeRef` adds it to the current tracking scope, like reading a [`Mutable`].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct NodeRef(Mutable<Option<Entity>>);
+
+impl NodeRef {
+    /// Construct a new, empty `NodeRef`.
+    pub fn new<C: CreateMutable>(cx: &mut C) -> Self {
+        Self(cx.create_mutable(None))
+    }
+
+    /// The referenced entity, or `None` if the entity has not been spawned yet, or has been
+    /// despawned.
+    pub fn get<R: ReadMutable>(&self, cx: &R) -> Option<Entity> {
+        self.0.get(cx)
+    }
+
+    /// Returns a signal for the referenced entity.
+    pub fn signal(&self) -> Signal<Option<Entity>> {
+        self.0.signal()
+    }
+}
+
+/// The node refs which refer to an entity, so that they can be cleared when it is despawned.
+#[derive(Component)]
+#[component(on_remove = on_remove_node_refs)]
+struct NodeRefTargets(Vec<NodeRef>);
+
+fn on_remove_node_refs(mut world: DeferredWorld, context: HookContext) {
+    let refs = std::mem::take(&mut world.get_mut::<NodeRefTargets>(context.entity).unwrap().0);
+    for node_ref in refs {
+        // The cell may already have been despawned along with its owner.
+        if let Some(mut cell) = world.get_mut::<MutableCell<Option<Entity>>>(node_ref.0.id()) {
+            if cell.0 == Some(context.entity) {
+                cell.0 = None;
+            }
+        }
+    }
+}
+
+/// Points `node_ref` at `entity`. If the mutable was created with deferred commands and does not
+/// exist yet, the update is deferred as well.
+fn set_node_ref(world: &mut World, node_ref: NodeRef, entity: Entity) {
+    if let Some(mut cell) = world.get_mut::<MutableCell<Option<Entity>>>(node_ref.0.id()) {
+        cell.0 = Some(entity);
+        return;
+    }
+    world.commands().queue(move |world: &mut World| {
+        if let Some(mut cell) = world.get_mut::<MutableCell<Option<Entity>>>(node_ref.0.id()) {
+            cell.0 = Some(entity);
+        }
+    });
+}
+
+unsafe impl Bundle for NodeRef {
+    fn component_ids(
+        _components: &mut bevy::ecs::component::Components,
+        _ids: &mut impl FnMut(bevy::ecs::component::ComponentId),
+    ) {
+    }
+
+    fn get_component_ids(
+        _components: &bevy::ecs::component::Components,
+        _ids: &mut impl FnMut(Option<bevy::ecs::component::ComponentId>),
+    ) {
+    }
+
+    fn register_required_components(
+        _components: &mut bevy::ecs::component::Components,
+        _required_components: &mut bevy::ecs::component::RequiredComponents,
+    ) {
+    }
+}
+
+impl DynamicBundle for NodeRef {
+    type Effect = Self;
+
+    fn get_components(
+        self,
+        _func: &mut impl FnMut(bevy::ecs::component::StorageType, bevy::ptr::OwningPtr<'_>),
+    ) -> Self::Effect {
+        self
+    }
+}
+
+impl BundleEffect for NodeRef {
+    fn apply(self, entity: &mut EntityWorldMut) {
+        let id = entity.id();
+        if let Some(mut targets) = entity.get_mut::<NodeRefTargets>() {
+            targets.0.push(self);
+        } else {
+            entity.insert(NodeRefTargets(vec![self]));
+        }
+        entity.world_scope(|world| set_node_ref(world, self, id));
+    }
+}
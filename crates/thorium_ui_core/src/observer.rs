@@ -0,0 +1,130 @@
+use bevy::{
+    ecs::{
+        bundle::{BundleEffect, DynamicBundle},
+        system::IntoObserverSystem,
+    },
+    prelude::*,
+};
+
+use crate::{owner::OwnedBy, TemplateContext};
+
+/// Methods for spawning scoped observers.
+pub trait CreateObserver {
+    /// Spawns an observer which watches the `target` entity, and which will be despawned when
+    /// the parent entity is despawned. This is useful for observing entities which outlive the
+    /// template, such as a window or a shared resource entity. To observe the parent entity
+    /// itself, use [`observers!`](crate::observers) or `.observe()`, which are cleaned up along
+    /// with the observed entity.
+    fn create_observer<E: Event, B: Bundle, M>(
+        &mut self,
+        target: Entity,
+        observer: impl IntoObserverSystem<E, B, M>,
+    ) -> Entity;
+}
+
+impl CreateObserver for EntityCommands<'_> {
+    fn create_observer<E: Event, B: Bundle, M>(
+        &mut self,
+        target: Entity,
+        observer: impl IntoObserverSystem<E, B, M>,
+    ) -> Entity {
+        let owner = self.id();
+        self.commands()
+            .spawn((Observer::new(observer).with_entity(target), OwnedBy(owner)))
+            .id()
+    }
+}
+
+impl CreateObserver for ChildSpawnerCommands<'_> {
+    fn create_observer<E: Event, B: Bundle, M>(
+        &mut self,
+        target: Entity,
+        observer: impl IntoObserverSystem<E, B, M>,
+    ) -> Entity {
+        let owner = self.target_entity();
+        self.commands()
+            .spawn((Observer::new(observer).with_entity(target), OwnedBy(owner)))
+            .id()
+    }
+}
+
+impl CreateObserver for TemplateContext<'_> {
+    fn create_observer<E: Event, B: Bundle, M>(
+        &mut self,
+        target: Entity,
+        observer: impl IntoObserverSystem<E, B, M>,
+    ) -> Entity {
+        let owner = self.target_entity();
+        self.commands()
+            .spawn((Observer::new(observer).with_entity(target), OwnedBy(owner)))
+            .id()
+    }
+}
+
+impl CreateObserver for EntityWorldMut<'_> {
+    fn create_observer<E: Event, B: Bundle, M>(
+        &mut self,
+        target: Entity,
+        observer: impl IntoObserverSystem<E, B, M>,
+    ) -> Entity {
+        let owner = self.id();
+        self.world_scope(|world| {
+            world
+                .spawn((Observer::new(observer).with_entity(target), OwnedBy(owner)))
+                .id()
+        })
+    }
+}
+
+/// A bundle which adds a list of observers to the entity it is inserted on. This is normally
+/// created with the [`observers!`](crate::observers) macro.
+pub struct EntityObservers(pub Vec<Observer>);
+
+unsafe impl Bundle for EntityObservers {
+    fn component_ids(
+        _components: &mut bevy::ecs::component::Components,
+        _ids: &mut impl FnMut(bevy::ecs::component::ComponentId),
+    ) {
+    }
+
+    fn get_component_ids(
+        _components: &bevy::ecs::component::Components,
+        _ids: &mut impl FnMut(Option<bevy::ecs::component::ComponentId>),
+    ) {
+    }
+
+    fn register_required_components(
+        _components: &mut bevy::ecs::component::Components,
+        _required_components: &mut bevy::ecs::component::RequiredComponents,
+    ) {
+    }
+}
+
+impl DynamicBundle for EntityObservers {
+    type Effect = Self;
+
+    fn get_components(
+        self,
+        _func: &mut impl FnMut(bevy::ecs::component::StorageType, bevy::ptr::OwningPtr<'_>),
+    ) -> Self::Effect {
+        self
+    }
+}
+
+impl BundleEffect for EntityObservers {
+    fn apply(self, entity: &mut EntityWorldMut) {
+        let id = entity.id();
+        entity.world_scope(|world| {
+            for observer in self.0 {
+                world.spawn(observer.with_entity(id));
+            }
+        });
+    }
+}
+
+#[macro_export]
+macro_rules! observers {
+    [$($observer:expr),*$(,)?] => {
+       $crate::EntityObservers(vec![$(Observer::new($observer)),*])
+    };
+}
@@ -0,0 +1,81 @@
+use bevy::{
+    ecs::bundle::{BundleEffect, DynamicBundle},
+    prelude::*,
+    ui::experimental::GhostNode,
+};
+
+use crate::{dyn_children::Fragment, OwnedBy, SpawnableListGen};
+
+/// Component placed on the container entity of a [`Portal`], which points back to the portal
+/// node. This is used to resolve contexts through the portal rather than through the target.
+#[derive(Component)]
+pub(crate) struct PortalContents(pub(crate) Entity);
+
+/// Control-flow node which spawns its contents under a different parent entity, such as an
+/// overlay layer. This allows things like menus, tooltips and dialogs to escape from clipping and
+/// `Overflow::Hidden` ancestors.
+///
+/// The contents are still owned by the portal: they are despawned when the portal is despawned,
+/// and contexts are inherited from the portal's ancestors rather than the target's.
+pub struct Portal<Contents: SpawnableListGen + Send + Sync + 'static> {
+    target: Entity,
+    contents: Contents,
+}
+
+impl<Contents: SpawnableListGen + Send + Sync + 'static> Portal<Contents> {
+    /// Construct a new portal which spawns `contents` as children of `target`.
+    pub fn new(target: Entity, contents: Contents) -> Self {
+        Self { target, contents }
+    }
+}
+
+unsafe impl<Contents: SpawnableListGen + Send + Sync + 'static> Bundle for Portal<Contents> {
+    fn component_ids(
+        _components: &mut bevy::ecs::component::Components,
+        _ids: &mut impl FnMut(bevy::ecs::component::ComponentId),
+    ) {
+    }
+
+    fn get_component_ids(
+        _components: &bevy::ecs::component::Components,
+        _ids: &mut impl FnMut(Option<bevy::ecs::component::ComponentId>),
+    ) {
+    }
+
+    fn register_required_components(
+        _components: &mut bevy::ecs::component::Components,
+        _required_components: &mut bevy::ecs::component::RequiredComponents,
+    ) {
+    }
+}
+
+impl<Contents: SpawnableListGen + Send + Sync + 'static> DynamicBundle for Portal<Contents> {
+    type Effect = Self;
+
+    fn get_components(
+        self,
+        _func: &mut impl FnMut(bevy::ecs::component::StorageType, bevy::ptr::OwningPtr<'_>),
+    ) -> Self::Effect {
+        self
+    }
+}
+
+impl<Contents: SpawnableListGen + Send + Sync + 'static> BundleEffect for Portal<Contents> {
+    fn apply(self, entity: &mut EntityWorldMut) {
+        let portal = entity.id();
+        // The portal itself is an empty fragment: it contributes nothing to its parent's
+        // children. The contents are spawned into a ghost node which is a child of the target.
+        entity.insert(Fragment);
+        entity.world_scope(|world| {
+            let container = world
+                .spawn((
+                    GhostNode,
+                    ChildOf(self.target),
+                    OwnedBy(portal),
+                    PortalContents(portal),
+                ))
+                .id();
+            self.contents.spawn(world, container);
+        });
+    }
+}
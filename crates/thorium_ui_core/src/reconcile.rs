@@ -0,0 +1,338 @@
+use std::{
+    any::{Any, TypeId},
+    collections::{HashMap, VecDeque},
+};
+
+use bevy::{ecs::spawn::SpawnableList, prelude::*, ui::experimental::GhostNode};
+
+use crate::{
+    dyn_children::Fragment, owner::Owned, Computations, CreateMutable, DynChildOf, DynChildren,
+    Mutable, ReadMutable, Signal, Template, TemplateContext, WriteMutable,
+};
+
+/// Marks the holder entity of a keyed template invocation, and remembers the template which
+/// built its contents, so that a later invocation with the same type and key can update it.
+#[derive(Component)]
+struct TemplateInstance {
+    key: String,
+    template: Box<dyn Any + Send + Sync>,
+}
+
+/// Keyed template instances which have been detached from a branch that is being rebuilt, and
+/// which are available to be reused by the new branch. There is one frame for each rebuild in
+/// progress, so that nested control-flow nodes only reuse their own instances.
+#[derive(Resource, Default)]
+struct RetiredInstances(Vec<HashMap<(TypeId, String), VecDeque<Entity>>>);
+
+/// Despawns the contents of `entity`, and then calls `build` to spawn new contents. Keyed
+/// template instances within the old contents are kept, and are updated rather than rebuilt if
+/// the new contents invoke a template with the same type and key.
+pub(crate) fn rebuild(world: &mut World, entity: Entity, build: impl FnOnce(&mut World)) {
+    rebuild_children(world, entity, |world| {
+        let mut entt = world.entity_mut(entity);
+        entt.despawn_related::<Computations>();
+        entt.despawn_related::<Owned>();
+        build(world);
+    });
+}
+
+/// Like [`rebuild`], but only despawns the children of `entity`, and keeps its computations and
+/// owned entities. This is used by function components, whose hook state is owned by the
+/// component instance.
+pub(crate) fn rebuild_children(world: &mut World, entity: Entity, build: impl FnOnce(&mut World)) {
+    begin_reconcile(world);
+    retire_instances(world, entity);
+    let mut entt = world.entity_mut(entity);
+    entt.remove::<Children>();
+    entt.despawn_related::<DynChildren>();
+    build(world);
+    end_reconcile(world);
+}
+
+/// Starts a new frame of retired instances.
+pub(crate) fn begin_reconcile(world: &mut World) {
+    world
+        .get_resource_or_insert_with(RetiredInstances::default)
+        .0
+        .push(HashMap::new());
+}
+
+/// Ends the current frame of retired instances, despawning any which were not reused.
+pub(crate) fn end_reconcile(world: &mut World) {
+    let Some(frame) = world
+        .get_resource_mut::<RetiredInstances>()
+        .and_then(|mut retired| retired.0.pop())
+    else {
+        return;
+    };
+    for holder in frame.into_values().flatten() {
+        world.entity_mut(holder).despawn();
+    }
+}
+
+/// Detaches the keyed template instances within the dynamic children of `entity`, and adds them
+/// to the current frame of retired instances. Does nothing if there is no current frame.
+pub(crate) fn retire_instances(world: &mut World, entity: Entity) {
+    if world
+        .get_resource::<RetiredInstances>()
+        .is_none_or(|retired| retired.0.is_empty())
+    {
+        return;
+    }
+    let mut found = Vec::new();
+    find_instances(world, entity, &mut found);
+    for (holder, id) in found {
+        world.entity_mut(holder).remove::<DynChildOf>();
+        detach_children(world, holder);
+        let mut retired = world.resource_mut::<RetiredInstances>();
+        let frame = retired.0.last_mut().unwrap();
+        frame.entry(id).or_default().push_back(holder);
+    }
+}
+
+/// Finds the outermost keyed template instances among the dynamic descendants of `entity`.
+fn find_instances(world: &World, entity: Entity, out: &mut Vec<(Entity, (TypeId, String))>) {
+    let Some(children) = world.get::<DynChildren>(entity) else {
+        return;
+    };
+    for child in children {
+        match world.get::<TemplateInstance>(*child) {
+            Some(instance) => out.push((
+                *child,
+                ((*instance.template).type_id(), instance.key.clone()),
+            )),
+            None => find_instances(world, *child, out),
+        }
+    }
+}
+
+/// Removes the `ChildOf` relationship from the entities which `holder` contributes to its
+/// parent's children, so that they are not despawned along with the parent. They are re-parented
+/// when the holder is inserted into a new parent.
+fn detach_children(world: &mut World, holder: Entity) {
+    let Some(children) = world.get::<DynChildren>(holder) else {
+        return;
+    };
+    for child in children.to_vec() {
+        if world.get::<Fragment>(child).is_some() {
+            detach_children(world, child);
+        } else {
+            world.entity_mut(child).remove::<ChildOf>();
+        }
+    }
+}
+
+/// Invokes `template` in a holder entity which is a dynamic child of `target`. If a retired
+/// instance with the same template type and key is available, it is reused: the template is
+/// given the chance to update the instance, and otherwise rebuilds it in place.
+pub(crate) fn invoke_keyed<T: Template + Send + Sync + 'static>(
+    world: &mut World,
+    target: Entity,
+    key: String,
+    template: T,
+) {
+    let id = (TypeId::of::<T>(), key);
+    let reused = world
+        .get_resource_mut::<RetiredInstances>()
+        .and_then(|mut retired| retired.0.last_mut()?.get_mut(&id)?.pop_front());
+
+    let Some(holder) = reused else {
+        let holder = world.spawn((GhostNode, Fragment, DynChildOf(target))).id();
+        template.build(&mut TemplateContext::new(holder, world));
+        world.entity_mut(holder).insert(TemplateInstance {
+            key: id.1,
+            template: Box::new(template),
+        });
+        return;
+    };
+
+    let mut entt = world.entity_mut(holder);
+    entt.insert(DynChildOf(target));
+    let instance = entt.take::<TemplateInstance>().unwrap();
+    let prev = instance.template.downcast_ref::<T>().unwrap();
+    if !template.update(prev, &mut TemplateContext::new(holder, world)) {
+        rebuild(world, holder, |world| {
+            template.build(&mut TemplateContext::new(holder, world));
+        });
+    }
+    world.entity_mut(holder).insert(TemplateInstance {
+        key: id.1,
+        template: Box::new(template),
+    });
+}
+
+/// Wrapper that invokes a template with a key. When the surrounding [`Cond`](crate::Cond),
+/// [`Switch`](crate::Switch) or [`For`](crate::For) rebuilds its contents, an instance of the
+/// same template type with the same key is updated using [`Template::update`] instead of being
+/// rebuilt. Created by [`Invoke::keyed`](crate::Invoke::keyed).
+pub struct InvokeKeyed<B> {
+    pub(crate) key: String,
+    pub(crate) template: B,
+}
+
+impl<B: Template + Send + Sync + 'static> SpawnableList<DynChildOf> for InvokeKeyed<B> {
+    fn spawn(self, world: &mut World, entity: Entity) {
+        invoke_keyed(world, entity, self.key, self.template);
+    }
+
+    fn size_hint(&self) -> usize {
+        1
+    }
+}
+
+/// A signal whose source can be replaced after it has been read. Templates use this to make
+/// their signal properties patchable in [`Template::update`]: the template builds with
+/// [`signal`](Self::signal), and the update replaces the source with the new property value,
+/// which re-runs anything that read the signal.
+pub struct SignalSlot<T>(Mutable<Signal<T>>);
+
+impl<T> Clone for SignalSlot<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for SignalSlot<T> {}
+
+impl<T: Clone + Send + Sync + 'static> SignalSlot<T> {
+    /// Construct a new slot whose source is `signal`.
+    pub fn new<C: CreateMutable>(cx: &mut C, signal: Signal<T>) -> Self {
+        Self(cx.create_mutable(signal))
+    }
+
+    /// Returns a signal which reads the current source of the slot.
+    pub fn signal(&self) -> Signal<T> {
+        let cell = self.0;
+        Signal::derived(move |world| {
+            world
+                .read_mutable_map(&cell, |source| source.clone())
+                .get_clone(world)
+        })
+    }
+
+    /// Replace the source of the slot.
+    pub fn set<W: WriteMutable>(&self, w: &mut W, signal: Signal<T>) {
+        self.0.update(w, |mut source| *source = signal);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{ecs::world::DeferredWorld, prelude::*};
+
+    use super::SignalSlot;
+    use crate::{
+        computations, effect_cell::update_effects, Calc, Cond, CreateMutable, DynChildren, Invoke,
+        Mutable, Signal, Template, TemplateContext, WriteMutable,
+    };
+
+    /// Number of times a keyed instance was updated rather than rebuilt.
+    #[derive(Resource, Default)]
+    struct Updates(usize);
+
+    /// The value displayed by a badge.
+    #[derive(Component, PartialEq, Debug)]
+    struct Shown(i32);
+
+    #[derive(Component)]
+    struct BadgeSlots {
+        count: SignalSlot<i32>,
+    }
+
+    struct Badge {
+        count: Signal<i32>,
+    }
+
+    impl Template for Badge {
+        fn build(&self, tc: &mut TemplateContext) {
+            let count = SignalSlot::new(tc, self.count.clone());
+            tc.spawn((
+                BadgeSlots { count },
+                computations![Calc::new(
+                    move |world: DeferredWorld| count.signal().get(&world),
+                    |count, ent| {
+                        ent.insert(Shown(count));
+                    }
+                )],
+            ));
+        }
+
+        fn update(&self, _prev: &Self, tc: &mut TemplateContext) -> bool {
+            let root = tc.spawned()[0];
+            let world = tc.world_mut();
+            world.resource_mut::<Updates>().0 += 1;
+            let count = world.get::<BadgeSlots>(root).unwrap().count;
+            count.set(world, self.count.clone());
+            true
+        }
+    }
+
+    fn frame(world: &mut World) {
+        world.increment_change_tick();
+        update_effects(world);
+    }
+
+    fn setup() -> (World, Entity, Mutable<bool>) {
+        let mut world = World::new();
+        world.init_resource::<Updates>();
+        let flag = world.create_mutable(true);
+        let cond = world
+            .spawn(Cond::new(
+                move |world: DeferredWorld| flag.get(&world),
+                || {
+                    Invoke(Badge {
+                        count: Signal::Constant(1),
+                    })
+                    .keyed("badge")
+                },
+                || {
+                    Invoke(Badge {
+                        count: Signal::Constant(2),
+                    })
+                    .keyed("badge")
+                },
+            ))
+            .id();
+        // The badge's computation is spawned during the first frame, and runs in the next one.
+        frame(&mut world);
+        frame(&mut world);
+        (world, cond, flag)
+    }
+
+    fn badge_root(world: &World, cond: Entity) -> Entity {
+        let holder = world.get::<DynChildren>(cond).unwrap()[0];
+        world.get::<DynChildren>(holder).unwrap()[0]
+    }
+
+    #[test]
+    fn test_cond_updates_keyed() {
+        let (mut world, cond, flag) = setup();
+        let root = badge_root(&world, cond);
+        assert_eq!(world.get::<Shown>(root), Some(&Shown(1)));
+        assert_eq!(world.resource::<Updates>().0, 0);
+
+        world.write_mutable(flag.id(), false);
+        frame(&mut world);
+        frame(&mut world);
+
+        assert_eq!(world.resource::<Updates>().0, 1);
+        assert_eq!(badge_root(&world, cond), root);
+        assert_eq!(world.get::<Shown>(root), Some(&Shown(2)));
+    }
+
+    #[test]
+    fn test_cond_updates_keyed_twice() {
+        let (mut world, cond, flag) = setup();
+        let root = badge_root(&world, cond);
+
+        world.write_mutable(flag.id(), false);
+        frame(&mut world);
+        world.write_mutable(flag.id(), true);
+        frame(&mut world);
+        frame(&mut world);
+
+        assert_eq!(world.resource::<Updates>().0, 2);
+        assert_eq!(badge_root(&world, cond), root);
+        assert_eq!(world.get::<Shown>(root), Some(&Shown(1)));
+    }
+}
@@ -1,16 +1,29 @@
-use crate::{mutable::ReadMutable, Memo, Mutable, ReadMemo};
+use std::time::Duration;
+
+use bevy::{ecs::world::DeferredWorld, prelude::*};
+
+use crate::{
+    derived::DerivedSignal, lens::LensSignal, mutable::ReadMutable, CreateMemo, CreateTimer, Memo,
+    Mutable, RateLimit, ReadMemo, ReadTracked, TimerClock,
+};
 
 /// What type of reactive node underlies this signal. "Signals" in this framework represent
 /// any kind of reactive data source, including mutable variables, memo signals, and memoized
 /// computations.
-#[derive(Copy, Debug)]
+#[derive(Debug)]
 pub enum Signal<T> {
     /// A mutable variable that can be read and written to.
     Mutable(Mutable<T>),
 
+    /// A field of a mutable variable, projected through a [`MutableLens`](crate::MutableLens).
+    Lens(LensSignal<T>),
+
     /// A readonly value that is computed from other signals.
     Memo(Memo<T>),
 
+    /// A readonly value that is computed by a closure whenever it is read.
+    Derived(DerivedSignal<T>),
+
     /// A constant value, mainly useful for establishing defaults.
     Constant(T),
 }
@@ -22,7 +35,9 @@ where
     fn clone(&self) -> Self {
         match self {
             Signal::Mutable(mutable) => Signal::Mutable(*mutable),
+            Signal::Lens(lens) => Signal::Lens(lens.clone()),
             Signal::Memo(memo) => Signal::Memo(memo.clone()),
+            Signal::Derived(derived) => Signal::Derived(derived.clone()),
             Signal::Constant(value) => Signal::Constant(value.clone()),
         }
     }
@@ -36,7 +51,9 @@ where
     pub fn get<R: ReadMutable + ReadMemo>(&self, rc: &R) -> T {
         match self {
             Signal::Mutable(mutable) => rc.read_mutable(mutable),
+            Signal::Lens(lens) => rc.read_lens_map(lens, |value| *value),
             Signal::Memo(memo) => rc.read_memo(*memo),
+            Signal::Derived(derived) => rc.read_derived(derived),
             Signal::Constant(value) => *value,
         }
     }
@@ -50,21 +67,170 @@ where
     pub fn get_clone<R: ReadMutable + ReadMemo>(&self, rc: &R) -> T {
         match self {
             Signal::Mutable(mutable) => rc.read_mutable_clone(mutable),
+            Signal::Lens(lens) => rc.read_lens_map(lens, |value| value.clone()),
             Signal::Memo(memo) => rc.read_memo(memo.clone()),
+            Signal::Derived(derived) => rc.read_derived(derived),
             Signal::Constant(value) => value.clone(),
         }
     }
+
+    /// Returns a derived signal which applies `f` to the value of this signal.
+    pub fn map_signal<U: Send + Sync + 'static, F: Fn(T) -> U + Send + Sync + 'static>(
+        &self,
+        f: F,
+    ) -> Signal<U> {
+        match self {
+            Signal::Constant(value) => Signal::Constant(f(value.clone())),
+            _ => {
+                let source = self.clone();
+                Signal::derived(move |world| f(source.get_clone(world)))
+            }
+        }
+    }
+
+    /// Returns a derived signal which combines the values of this signal and `other` into a
+    /// tuple.
+    pub fn zip<U: Clone + Send + Sync + 'static>(&self, other: &Signal<U>) -> Signal<(T, U)> {
+        match (self, other) {
+            (Signal::Constant(a), Signal::Constant(b)) => Signal::Constant((a.clone(), b.clone())),
+            _ => {
+                let (a, b) = (self.clone(), other.clone());
+                Signal::derived(move |world| (a.get_clone(world), b.get_clone(world)))
+            }
+        }
+    }
+
+    /// Returns a memoized version of this signal. The value is computed by a memo entity owned
+    /// by `cx`, so the computation only re-runs when its dependencies change, and readers are
+    /// only notified when the result actually changes. Until the memo has first run, its value
+    /// is `T::default()`.
+    pub fn memoize<C: CreateMemo>(&self, cx: &mut C) -> Signal<T>
+    where
+        T: PartialEq + Default,
+    {
+        match self {
+            Signal::Derived(_) => {
+                let source = self.clone();
+                Signal::Memo(cx.create_memo(
+                    move |world: DeferredWorld| source.get_clone(&world),
+                    T::default(),
+                ))
+            }
+            // Other signals are already cheap to read.
+            _ => self.clone(),
+        }
+    }
+
+    /// Returns a debounced version of this signal, which only changes once this signal has
+    /// stopped changing for `delay`. This is useful for things like search boxes, where an
+    /// expensive query should only run once the user stops typing. The timer is owned by `cx`.
+    /// Until the source has first been read, the value is `T::default()`.
+    pub fn debounce<C: CreateTimer>(
+        &self,
+        cx: &mut C,
+        delay: Duration,
+        clock: TimerClock,
+    ) -> Signal<T>
+    where
+        T: PartialEq + Default,
+    {
+        Signal::Mutable(cx.create_rate_limited(self.clone(), RateLimit::Debounce, delay, clock))
+    }
+
+    /// Returns a throttled version of this signal, which changes at most once per `delay`. The
+    /// first change is passed on immediately, and the latest value is passed on at the end of
+    /// the delay. The timer is owned by `cx`. Until the source has first been read, the value
+    /// is `T::default()`.
+    pub fn throttle<C: CreateTimer>(
+        &self,
+        cx: &mut C,
+        delay: Duration,
+        clock: TimerClock,
+    ) -> Signal<T>
+    where
+        T: PartialEq + Default,
+    {
+        Signal::Mutable(cx.create_rate_limited(self.clone(), RateLimit::Throttle, delay, clock))
+    }
+}
+
+impl<S: States> Signal<S> {
+    /// Construct a signal which reads the current value of the [`State<S>`] resource. Panics when
+    /// read if the state has not been initialized.
+    pub fn from_state() -> Self {
+        Signal::derived(|world| world.read_resource::<State<S>>().get().clone())
+    }
+}
+
+impl Signal<bool> {
+    /// Returns a derived signal which is true if both this signal and `other` are true.
+    pub fn and(&self, other: &Signal<bool>) -> Signal<bool> {
+        let (a, b) = (self.clone(), other.clone());
+        Signal::derived(move |world| a.get(world) && b.get(world))
+    }
+
+    /// Returns a derived signal which is true if either this signal or `other` is true.
+    pub fn or(&self, other: &Signal<bool>) -> Signal<bool> {
+        let (a, b) = (self.clone(), other.clone());
+        Signal::derived(move |world| a.get(world) || b.get(world))
+    }
+}
+
+/// Returns a derived signal which is the logical negation of the signal.
+impl std::ops::Not for Signal<bool> {
+    type Output = Signal<bool>;
+
+    fn not(self) -> Signal<bool> {
+        self.map_signal(|value| !value)
+    }
 }
 
 impl<T> Signal<T>
 where
     T: Send + Sync + 'static,
 {
+    /// Construct a derived signal from a closure. The closure is run each time the signal is read,
+    /// and any signals it reads are tracked as dependencies of the reader.
+    pub fn derived<F: Fn(&World) -> T + Send + Sync + 'static>(compute: F) -> Self {
+        Signal::Derived(DerivedSignal::new(compute))
+    }
+
+    /// Construct a signal which reads a value from a resource. The signal tracks the resource,
+    /// so anything which reads the signal will react when the resource changes. Panics when read
+    /// if the resource does not exist.
+    ///
+    /// ```rust,ignore
+    /// let volume = Signal::from_resource::<AudioSettings, _>(|settings| settings.volume);
+    /// ```
+    pub fn from_resource<R: Resource, F: Fn(&R) -> T + Send + Sync + 'static>(f: F) -> Self {
+        Signal::derived(move |world| f(world.read_resource::<R>()))
+    }
+
+    /// Construct a signal which reads a value from a component of `entity`. The signal tracks
+    /// the component, so anything which reads the signal will react when the component changes.
+    /// If the entity does not have the component, the value of the signal is `T::default()`.
+    pub fn from_component<C: Component, F: Fn(&C) -> T + Send + Sync + 'static>(
+        entity: Entity,
+        f: F,
+    ) -> Self
+    where
+        T: Default,
+    {
+        Signal::derived(move |world| {
+            world
+                .read_component::<C>(entity)
+                .map(&f)
+                .unwrap_or_default()
+        })
+    }
+
     /// Read the value of the signal using a mapping function.
     pub fn map<R: ReadMutable + ReadMemo, U, F: Fn(&T) -> U>(&self, rc: &R, f: F) -> U {
         match self {
             Signal::Mutable(mutable) => rc.read_mutable_map(mutable, f),
+            Signal::Lens(lens) => rc.read_lens_map(lens, f),
             Signal::Memo(memo) => rc.read_memo_map(memo, f),
+            Signal::Derived(derived) => f(&rc.read_derived(derived)),
             Signal::Constant(value) => f(value),
         }
     }
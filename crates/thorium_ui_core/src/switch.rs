@@ -8,10 +8,13 @@ use bevy::{
 };
 
 use crate::{
+    branch_cache::BranchCache,
     dyn_children::Fragment,
     effect_cell::{AnyEffect, EffectCell},
-    owner::Owned,
-    Computations, DynChildren, SpawnableListGen,
+    error_boundary::report_error,
+    reconcile::rebuild,
+    tracking::run_tracked,
+    SpawnableListGen,
 };
 
 pub struct CaseBuilder<'a, Value: Send + Sync> {
@@ -44,6 +47,7 @@ struct SwitchEffect<P> {
     value_sys: SystemId<(), P>,
     cases: Vec<(P, Box<dyn SpawnableListGen + Send + Sync>)>,
     fallback: Option<Box<dyn SpawnableListGen + Send + Sync>>,
+    cache: Option<BranchCache<usize>>,
 }
 
 impl<P: PartialEq + Send + Sync + 'static> SwitchEffect<P> {
@@ -65,35 +69,51 @@ impl<P: PartialEq + Send + Sync + 'static> SwitchEffect<P> {
 impl<P: PartialEq + Send + Sync + 'static> AnyEffect for SwitchEffect<P> {
     fn update(&mut self, world: &mut World, entity: Entity) {
         // Run the condition and see if the result changed.
-        let value = world.run_system(self.value_sys);
-        if let Ok(value) = value {
-            let index = self
-                .cases
-                .iter()
-                .enumerate()
-                .find_map(|(i, f)| if f.0 == value { Some(i) } else { None })
-                .unwrap_or(usize::MAX);
-
-            if self.switch_index != index {
-                self.switch_index = index;
-                let mut commands = world.commands();
-                let mut entt = commands.entity(entity);
-                entt.remove::<Children>();
-                entt.despawn_related::<DynChildren>();
-                entt.despawn_related::<Computations>();
-                entt.despawn_related::<Owned>();
+        let value = match run_tracked(world, entity, |world| world.run_system(self.value_sys)) {
+            Ok(value) => value,
+            Err(err) => {
+                report_error(world, entity, err.to_string());
+                return;
+            }
+        };
+        let index = self
+            .cases
+            .iter()
+            .enumerate()
+            .find_map(|(i, f)| if f.0 == value { Some(i) } else { None })
+            .unwrap_or(usize::MAX);
+
+        if self.switch_index != index {
+            self.switch_index = index;
+            if let Some(cache) = self.cache.as_mut() {
+                cache.activate(world, entity, index, |world, holder| {
+                    if index < self.cases.len() {
+                        self.cases[index].1.spawn(world, holder);
+                    } else if let Some(fallback) = self.fallback.as_ref() {
+                        fallback.spawn(world, holder);
+                    }
+                });
+                return;
+            }
+            rebuild(world, entity, |world| {
                 if index < self.cases.len() {
                     self.cases[index].1.spawn(world, entity);
                 } else if let Some(fallback) = self.fallback.as_mut() {
                     fallback.spawn(world, entity);
-                };
-            }
+                }
+            });
         }
     }
 
     fn cleanup(&self, world: &mut bevy::ecs::world::DeferredWorld, _entity: Entity) {
         world.commands().unregister_system(self.value_sys);
     }
+
+    fn evict(&mut self, world: &mut World, _entity: Entity) {
+        if let Some(cache) = self.cache.as_mut() {
+            cache.evict(world);
+        }
+    }
 }
 
 pub struct Switch<
@@ -104,6 +124,7 @@ pub struct Switch<
     value_fn: ValueFn,
     cases: Vec<(P, Box<dyn SpawnableListGen + Send + Sync>)>,
     fallback: Option<Box<dyn SpawnableListGen + Send + Sync>>,
+    cache: Option<BranchCache<usize>>,
     marker: std::marker::PhantomData<M>,
 }
 
@@ -127,9 +148,26 @@ impl<
             value_fn,
             cases,
             fallback,
+            cache: None,
             marker: std::marker::PhantomData,
         }
     }
+
+    /// Keep inactive cases alive rather than despawning them. When the value changes, the
+    /// outgoing case is detached and hidden, preserving its state, and is restored if that case
+    /// is selected again. Use [`EvictCachedBranches`](crate::EvictCachedBranches) to despawn
+    /// inactive cases explicitly.
+    pub fn keep_alive(mut self) -> Self {
+        self.cache = Some(BranchCache::new(None));
+        self
+    }
+
+    /// Like [`keep_alive`](Self::keep_alive), but keeps at most `limit` inactive cases. Beyond
+    /// that, the least recently used case is despawned.
+    pub fn keep_alive_limit(mut self, limit: usize) -> Self {
+        self.cache = Some(BranchCache::new(Some(limit)));
+        self
+    }
 }
 
 impl<
@@ -146,6 +184,7 @@ impl<
                 fallback: self.fallback,
                 value_sys,
                 switch_index: usize::MAX - 1, // Means no case selected yet.
+                cache: self.cache,
             }),
             // GhostNode::default(),
             Fragment,
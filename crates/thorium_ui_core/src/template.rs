@@ -2,11 +2,25 @@ use std::sync::Arc;
 
 use bevy::ecs::{prelude::*, spawn::SpawnableList};
 
-use crate::DynChildOf;
+use crate::{reconcile, DynChildOf, DynChildren, InvokeKeyed};
 
 /// Template that builds child elements for a parent entity.
 pub trait Template {
     fn build(&self, tc: &mut TemplateContext);
+
+    /// Update an instance of this template which was built from `prev`, so that it matches
+    /// `self`. This is called instead of [`build`](Self::build) when a keyed invocation (see
+    /// [`Invoke::keyed`]) reuses an existing instance. `tc` targets the instance's holder entity,
+    /// whose dynamic children are the entities spawned by the previous build. Returning `false`
+    /// means the template could not be updated in place, and causes the instance to be
+    /// rebuilt. The default implementation always returns `false`.
+    fn update(&self, _prev: &Self, _tc: &mut TemplateContext) -> bool
+    where
+        Self: Sized,
+    {
+        false
+    }
+
     fn size_hint(&self) -> usize {
         // TODO: Since most template have one root, should this default to 1?
         0
@@ -16,6 +30,17 @@ pub trait Template {
 /// Wrapper that invokes a template.
 pub struct Invoke<B>(pub B);
 
+impl<B: Template> Invoke<B> {
+    /// Invoke the template with a key, so that when the surrounding control-flow node rebuilds,
+    /// an existing instance with the same template type and key is updated rather than rebuilt.
+    pub fn keyed(self, key: impl Into<String>) -> InvokeKeyed<B> {
+        InvokeKeyed {
+            key: key.into(),
+            template: self.0,
+        }
+    }
+}
+
 impl<B: Template> SpawnableList<DynChildOf> for Invoke<B> {
     fn spawn(self, world: &mut World, entity: Entity) {
         let mut tc = TemplateContext::new(entity, world);
@@ -52,6 +77,13 @@ impl<S: SpawnableList<DynChildOf>, F: Fn() -> S + Send + Sync> SpawnableListGen
     }
 }
 
+impl<F: Fn(&mut TemplateContext) + Send + Sync> SpawnableListGen for InvokeWith<F> {
+    fn spawn(&self, world: &mut World, entity: Entity) {
+        let mut tc = TemplateContext::new(entity, world);
+        (self.0)(&mut tc);
+    }
+}
+
 /// Wrapper that invokes a function with shared reference to a function that can produce spawns.
 pub struct SpawnArc(pub Option<Arc<dyn SpawnableListGen + Send + Sync + 'static>>);
 
@@ -86,6 +118,14 @@ impl<'w> TemplateContext<'w> {
         self.target
     }
 
+    pub(crate) fn world(&self) -> &World {
+        self.world
+    }
+
+    pub(crate) fn world_mut(&mut self) -> &mut World {
+        self.world
+    }
+
     /// Spawns an entity with the given `bundle` and an `R` relationship targeting the `target`
     /// entity this spawner was initialized with.
     pub fn spawn(&mut self, bundle: impl Bundle) -> EntityWorldMut<'_> {
@@ -106,6 +146,32 @@ impl<'w> TemplateContext<'w> {
         self
     }
 
+    /// Invoke a template with a key. The template is built inside a holder entity; when the
+    /// surrounding control-flow node rebuilds, an existing instance with the same template type
+    /// and key is updated using [`Template::update`] instead of being rebuilt.
+    pub fn invoke_keyed<T: Template + Send + Sync + 'static>(
+        &mut self,
+        key: impl Into<String>,
+        template: T,
+    ) -> &mut Self {
+        reconcile::invoke_keyed(self.world, self.target, key.into(), template);
+        self
+    }
+
+    /// The entities which have been spawned into the target entity, in order.
+    pub fn spawned(&self) -> &[Entity] {
+        match self.world.get::<DynChildren>(self.target) {
+            Some(children) => &children[..],
+            None => &[],
+        }
+    }
+
+    /// Returns an [`EntityWorldMut`] for an entity, such as one of the [`spawned`](Self::spawned)
+    /// entities. This is mainly useful in [`Template::update`].
+    pub fn entity_mut(&mut self, entity: Entity) -> EntityWorldMut<'_> {
+        self.world.entity_mut(entity)
+    }
+
     /// Creates a new [`Commands`] instance that writes to the world's command queue
     /// Use [`World::flush`] to apply all queued commands
     pub fn commands(&mut self) -> Commands {
@@ -0,0 +1,749 @@
+use std::{any::Any, collections::HashMap, fmt, sync::Arc};
+
+use bevy::{
+    app::{App, Plugin},
+    asset::{io::Reader, AssetLoader, LoadContext},
+    ecs::{
+        bundle::{BundleEffect, DynamicBundle},
+        component::Tick,
+        world::DeferredWorld,
+    },
+    prelude::*,
+    ui::experimental::GhostNode,
+};
+use serde::Deserialize;
+
+use crate::{
+    dyn_children::Fragment,
+    effect_cell::{AnyEffect, EffectCell},
+    error_boundary::report_error,
+    tracking::run_tracked,
+    Calc, Cond, DynChildOf, DynChildren, For, ListItems, ReadTracked, Signal, SpawnableListGen,
+    StyleHandle, StyleTuple, Styles, Switch, TemplateContext,
+};
+
+/// A node in a [`TemplateAsset`].
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub enum TemplateNode {
+    /// A UI node, with a list of named styles from the [`TemplateRegistry`], and children.
+    Node {
+        #[serde(default)]
+        styles: Vec<String>,
+        #[serde(default)]
+        children: Vec<TemplateNode>,
+    },
+    /// Static text. Within the body of a `For`, `{item}` is replaced by the current item.
+    Text(String),
+    /// Text bound to a named `Signal<String>`.
+    BoundText(String),
+    /// Displays `then` or `otherwise` depending on a named `Signal<bool>`.
+    Cond {
+        signal: String,
+        #[serde(default)]
+        then: Vec<TemplateNode>,
+        #[serde(default)]
+        otherwise: Vec<TemplateNode>,
+    },
+    /// Displays the case matching the value of a named `Signal<String>`.
+    Switch {
+        signal: String,
+        #[serde(default)]
+        cases: Vec<(String, Vec<TemplateNode>)>,
+        #[serde(default)]
+        fallback: Vec<TemplateNode>,
+    },
+    /// Displays `each` for every item of a named `Signal<Vec<String>>`.
+    For {
+        signal: String,
+        #[serde(default)]
+        each: Vec<TemplateNode>,
+        #[serde(default)]
+        fallback: Vec<TemplateNode>,
+    },
+    /// Invokes a Rust template registered in the [`TemplateRegistry`].
+    Invoke(String),
+}
+
+/// A template loaded from a `.thorium.ron` file, which describes a tree of nodes:
+///
+/// ```ron
+/// (
+///     nodes: [
+///         Node(
+///             styles: ["panel"],
+///             children: [
+///                 BoundText("title"),
+///                 Cond(
+///                     signal: "show_details",
+///                     then: [Invoke("details")],
+///                     otherwise: [Text("No selection")],
+///                 ),
+///             ],
+///         ),
+///     ],
+/// )
+/// ```
+///
+/// Templates are instantiated with [`AssetTemplate`].
+#[derive(Asset, TypePath, Deserialize, Debug, Clone, PartialEq)]
+pub struct TemplateAsset {
+    pub nodes: Vec<TemplateNode>,
+}
+
+/// Errors that can occur while loading a [`TemplateAsset`].
+#[derive(Debug)]
+pub enum TemplateAssetError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+}
+
+impl fmt::Display for TemplateAssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateAssetError::Io(err) => write!(f, "Could not read template: {err}"),
+            TemplateAssetError::Ron(err) => write!(f, "Could not parse template: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for TemplateAssetError {}
+
+impl From<std::io::Error> for TemplateAssetError {
+    fn from(err: std::io::Error) -> Self {
+        TemplateAssetError::Io(err)
+    }
+}
+
+impl From<ron::error::SpannedError> for TemplateAssetError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        TemplateAssetError::Ron(err)
+    }
+}
+
+/// Asset loader for `.thorium.ron` template files.
+#[derive(Default)]
+pub struct TemplateAssetLoader;
+
+impl AssetLoader for TemplateAssetLoader {
+    type Asset = TemplateAsset;
+    type Settings = ();
+    type Error = TemplateAssetError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<TemplateAsset, TemplateAssetError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes::<TemplateAsset>(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["thorium.ron"]
+    }
+}
+
+type TemplateFn = Arc<dyn Fn(&mut TemplateContext) + Send + Sync>;
+
+/// Registry of the Rust templates and styles which can be referred to by name from a
+/// [`TemplateAsset`].
+#[derive(Resource, Default, Clone)]
+pub struct TemplateRegistry {
+    templates: HashMap<String, TemplateFn>,
+    styles: HashMap<String, StyleHandle>,
+}
+
+impl TemplateRegistry {
+    /// Register a template function, which can be used in a template asset with `Invoke`.
+    pub fn register_template<F: Fn(&mut TemplateContext) + Send + Sync + 'static>(
+        &mut self,
+        name: impl Into<String>,
+        template: F,
+    ) -> &mut Self {
+        self.templates.insert(name.into(), Arc::new(template));
+        self
+    }
+
+    /// Register a style, which can be used in the `styles` list of a template asset node.
+    pub fn register_style<S: StyleTuple + 'static>(
+        &mut self,
+        name: impl Into<String>,
+        style: S,
+    ) -> &mut Self {
+        self.styles.insert(name.into(), style.into_handle());
+        self
+    }
+}
+
+/// Plugin which registers the [`TemplateAsset`] type and its loader. When Bevy's `file_watcher`
+/// feature is enabled, edits to a template file are picked up while the app is running.
+pub struct TemplateAssetPlugin;
+
+impl Plugin for TemplateAssetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<TemplateAsset>()
+            .init_asset_loader::<TemplateAssetLoader>()
+            .init_resource::<TemplateRegistry>();
+    }
+}
+
+/// Everything needed to build template nodes: the registry, and the signals bound to an
+/// [`AssetTemplate`] instance.
+struct BuildEnv {
+    registry: TemplateRegistry,
+    signals: Arc<HashMap<String, Box<dyn Any + Send + Sync>>>,
+}
+
+impl BuildEnv {
+    fn signal<T: Clone + Send + Sync + 'static>(&self, name: &str) -> Result<Signal<T>, String> {
+        let signal = self
+            .signals
+            .get(name)
+            .ok_or_else(|| format!("Template signal '{name}' is not bound"))?;
+        signal.downcast_ref::<Signal<T>>().cloned().ok_or_else(|| {
+            format!(
+                "Template signal '{name}' is not a Signal<{}>",
+                std::any::type_name::<T>()
+            )
+        })
+    }
+}
+
+/// A list of template nodes which can be spawned as the branch of a `Cond`, `Switch` or `For`.
+struct NodeList {
+    nodes: Vec<TemplateNode>,
+    env: Arc<BuildEnv>,
+    item: Option<String>,
+}
+
+impl SpawnableListGen for NodeList {
+    fn spawn(&self, world: &mut World, entity: Entity) {
+        let mut tc = TemplateContext::new(entity, world);
+        build_nodes(&self.nodes, &mut tc, &self.env, self.item.as_deref());
+    }
+}
+
+fn build_nodes(
+    nodes: &[TemplateNode],
+    tc: &mut TemplateContext,
+    env: &Arc<BuildEnv>,
+    item: Option<&str>,
+) {
+    for node in nodes {
+        if let Err(message) = build_node(node, tc, env, item) {
+            let target = tc.target_entity();
+            report_error(tc.world_mut(), target, message);
+        }
+    }
+}
+
+fn build_node(
+    node: &TemplateNode,
+    tc: &mut TemplateContext,
+    env: &Arc<BuildEnv>,
+    item: Option<&str>,
+) -> Result<(), String> {
+    let node_list = |nodes: &Vec<TemplateNode>| NodeList {
+        nodes: nodes.clone(),
+        env: env.clone(),
+        item: item.map(str::to_string),
+    };
+    match node {
+        TemplateNode::Node { styles, children } => {
+            let id = spawn_element(styles, tc, env)?;
+            let mut child_tc = TemplateContext::new(id, tc.world_mut());
+            build_nodes(children, &mut child_tc, env, item);
+        }
+
+        TemplateNode::Text(text) => {
+            let text = match item {
+                Some(item) => text.replace("{item}", item),
+                None => text.clone(),
+            };
+            tc.spawn(Text::new(text));
+        }
+
+        TemplateNode::BoundText(name) => {
+            let signal = env.signal::<String>(name)?;
+            tc.spawn((
+                Text::default(),
+                crate::computations![Calc::new(
+                    move |world: DeferredWorld| signal.get_clone(&world),
+                    |text, ent| {
+                        ent.insert(Text::new(text));
+                    },
+                )],
+            ));
+        }
+
+        TemplateNode::Cond {
+            signal,
+            then,
+            otherwise,
+        } => {
+            let signal = env.signal::<bool>(signal)?;
+            tc.spawn(Cond::new(
+                move |world: DeferredWorld| signal.get(&world),
+                node_list(then),
+                node_list(otherwise),
+            ));
+        }
+
+        TemplateNode::Switch {
+            signal,
+            cases,
+            fallback,
+        } => {
+            let signal = env.signal::<String>(signal)?;
+            tc.spawn(Switch::new(
+                move |world: DeferredWorld| signal.get_clone(&world),
+                |builder| {
+                    for (value, nodes) in cases {
+                        builder.case(value.clone(), node_list(nodes));
+                    }
+                    builder.fallback(node_list(fallback));
+                },
+            ));
+        }
+
+        TemplateNode::For {
+            signal,
+            each,
+            fallback,
+        } => {
+            let signal = env.signal::<Vec<String>>(signal)?;
+            let each = each.clone();
+            let each_env = env.clone();
+            tc.spawn(For::each(
+                move |mut items: InMut<ListItems<String>>, world: DeferredWorld| {
+                    items.clone_from(&signal.get_clone(&world));
+                },
+                move |item: &String, tc: &mut TemplateContext| {
+                    build_nodes(&each, tc, &each_env, Some(item));
+                },
+                node_list(fallback),
+            ));
+        }
+
+        TemplateNode::Invoke(name) => {
+            let template = env
+                .registry
+                .templates
+                .get(name)
+                .ok_or_else(|| format!("Template '{name}' is not registered"))?;
+            template(tc);
+        }
+    }
+    Ok(())
+}
+
+/// Spawns the UI node for a [`TemplateNode::Node`], with its styles.
+fn spawn_element(
+    styles: &[String],
+    tc: &mut TemplateContext,
+    env: &BuildEnv,
+) -> Result<Entity, String> {
+    let id = tc.spawn(Node::default()).id();
+    for name in styles {
+        let style = env
+            .registry
+            .styles
+            .get(name)
+            .ok_or_else(|| format!("Template style '{name}' is not registered"))?;
+        tc.world_mut().entity_mut(id).insert(Styles(style.clone()));
+    }
+    Ok(id)
+}
+
+/// Instantiates a [`TemplateAsset`]. Named signals used by the template are bound with
+/// [`bind`](Self::bind):
+///
+/// ```rust,ignore
+/// AssetTemplate::new(asset_server.load("ui/inspector.thorium.ron"))
+///     .bind("title", title.into_signal())
+///     .bind("show_details", show_details.into_signal())
+/// ```
+///
+/// Nothing is displayed until the asset has loaded. When the asset is reloaded, only the nodes
+/// whose definitions have changed are rebuilt: a `Node` whose styles are unchanged is kept, and
+/// its children are compared in the same way. The [`TemplateRegistry`] is read again on each
+/// rebuild, and if it has changed, the whole template is rebuilt.
+pub struct AssetTemplate {
+    handle: Handle<TemplateAsset>,
+    signals: HashMap<String, Box<dyn Any + Send + Sync>>,
+}
+
+impl AssetTemplate {
+    /// Construct a new instance of a template asset.
+    pub fn new(handle: Handle<TemplateAsset>) -> Self {
+        Self {
+            handle,
+            signals: HashMap::new(),
+        }
+    }
+
+    /// Bind a signal to a name used in the template. The template expects a `Signal<bool>` for
+    /// `Cond`, a `Signal<String>` for `Switch` and `BoundText`, and a `Signal<Vec<String>>` for
+    /// `For`.
+    pub fn bind<T: Clone + Send + Sync + 'static>(
+        mut self,
+        name: impl Into<String>,
+        signal: Signal<T>,
+    ) -> Self {
+        self.signals.insert(name.into(), Box::new(signal));
+        self
+    }
+}
+
+unsafe impl Bundle for AssetTemplate {
+    fn component_ids(
+        _components: &mut bevy::ecs::component::Components,
+        _ids: &mut impl FnMut(bevy::ecs::component::ComponentId),
+    ) {
+    }
+
+    fn get_component_ids(
+        _components: &bevy::ecs::component::Components,
+        _ids: &mut impl FnMut(Option<bevy::ecs::component::ComponentId>),
+    ) {
+    }
+
+    fn register_required_components(
+        _components: &mut bevy::ecs::component::Components,
+        _required_components: &mut bevy::ecs::component::RequiredComponents,
+    ) {
+    }
+}
+
+impl DynamicBundle for AssetTemplate {
+    type Effect = Self;
+
+    fn get_components(
+        self,
+        _func: &mut impl FnMut(bevy::ecs::component::StorageType, bevy::ptr::OwningPtr<'_>),
+    ) -> Self::Effect {
+        self
+    }
+}
+
+impl BundleEffect for AssetTemplate {
+    fn apply(self, entity: &mut EntityWorldMut) {
+        entity.insert((
+            EffectCell::new(AssetTemplateEffect {
+                handle: self.handle,
+                signals: Arc::new(self.signals),
+                registry_changed: None,
+                built: Vec::new(),
+            }),
+            Fragment,
+        ));
+    }
+}
+
+/// A template node which has been built, along with the fragment which holds it. For a `Node`,
+/// the UI entity and the nodes built within it are kept as well, so that changes to its children
+/// can be patched without rebuilding it.
+struct BuiltNode {
+    node: TemplateNode,
+    holder: Entity,
+    element: Option<Entity>,
+    children: Vec<BuiltNode>,
+}
+
+/// Builds `node` in a new fragment which is a dynamic child of `parent`.
+fn build_tree(
+    world: &mut World,
+    parent: Entity,
+    node: &TemplateNode,
+    env: &Arc<BuildEnv>,
+) -> BuiltNode {
+    let holder = world.spawn((GhostNode, Fragment, DynChildOf(parent))).id();
+    let mut tc = TemplateContext::new(holder, world);
+    let mut element = None;
+    let mut children = Vec::new();
+    match node {
+        TemplateNode::Node {
+            styles,
+            children: child_nodes,
+        } => match spawn_element(styles, &mut tc, env) {
+            Ok(id) => {
+                element = Some(id);
+                children = child_nodes
+                    .iter()
+                    .map(|child| build_tree(world, id, child, env))
+                    .collect();
+            }
+            Err(message) => report_error(world, holder, message),
+        },
+        _ => build_nodes(std::slice::from_ref(node), &mut tc, env, None),
+    }
+    BuiltNode {
+        node: node.clone(),
+        holder,
+        element,
+        children,
+    }
+}
+
+/// Updates the nodes built within `parent` to match `nodes`. Nodes which haven't changed are
+/// kept, a `Node` whose styles haven't changed is kept and has its children patched, and any
+/// other node is rebuilt.
+fn patch_nodes(
+    world: &mut World,
+    parent: Entity,
+    prev: Vec<BuiltNode>,
+    nodes: &[TemplateNode],
+    env: &Arc<BuildEnv>,
+) -> Vec<BuiltNode> {
+    let mut changed = prev.len() != nodes.len();
+    let mut prev = prev.into_iter();
+    let mut built = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        let Some(prev) = prev.next() else {
+            built.push(build_tree(world, parent, node, env));
+            continue;
+        };
+        if prev.node == *node {
+            built.push(prev);
+            continue;
+        }
+        if let (
+            TemplateNode::Node { styles, children },
+            TemplateNode::Node {
+                styles: prev_styles,
+                ..
+            },
+            Some(element),
+        ) = (node, &prev.node, prev.element)
+        {
+            if styles == prev_styles {
+                let children = patch_nodes(world, element, prev.children, children, env);
+                built.push(BuiltNode {
+                    node: node.clone(),
+                    holder: prev.holder,
+                    element: Some(element),
+                    children,
+                });
+                continue;
+            }
+        }
+        changed = true;
+        world.entity_mut(prev.holder).despawn();
+        built.push(build_tree(world, parent, node, env));
+    }
+    for prev in prev {
+        world.entity_mut(prev.holder).despawn();
+    }
+
+    if changed {
+        let children: Vec<Entity> = built.iter().map(|built| built.holder).collect();
+        world.entity_mut(parent).remove::<DynChildren>();
+        world
+            .entity_mut(parent)
+            .add_related::<DynChildOf>(&children);
+    }
+    built
+}
+
+struct AssetTemplateEffect {
+    handle: Handle<TemplateAsset>,
+    signals: Arc<HashMap<String, Box<dyn Any + Send + Sync>>>,
+    /// When the [`TemplateRegistry`] was last changed, as of the last build.
+    registry_changed: Option<Tick>,
+    /// The top-level nodes which have been built.
+    built: Vec<BuiltNode>,
+}
+
+impl AnyEffect for AssetTemplateEffect {
+    fn update(&mut self, world: &mut World, entity: Entity) {
+        let handle = self.handle.clone();
+        let (nodes, registry) = run_tracked(world, entity, |world| {
+            let nodes = world
+                .read_resource_opt::<Assets<TemplateAsset>>()
+                .map(|assets| assets.get(&handle).map(|asset| asset.nodes.clone()));
+            // The registry is tracked too, so that templates and styles which are registered
+            // later are picked up.
+            let registry = world.read_resource_opt::<TemplateRegistry>().cloned();
+            let registry_changed = world
+                .get_resource_ref::<TemplateRegistry>()
+                .map(|registry| registry.last_changed());
+            (nodes, registry.zip(registry_changed))
+        });
+        let Some(nodes) = nodes else {
+            report_error(
+                world,
+                entity,
+                "TemplateAssetPlugin has not been added".to_string(),
+            );
+            return;
+        };
+        let Some(nodes) = nodes else {
+            // Not loaded yet.
+            return;
+        };
+
+        let (registry, registry_changed) = registry.unzip();
+        let mut prev = std::mem::take(&mut self.built);
+        if registry_changed != self.registry_changed {
+            // Any node may refer to something which was registered or replaced, so rebuild
+            // everything.
+            self.registry_changed = registry_changed;
+            for built in prev.drain(..) {
+                world.entity_mut(built.holder).despawn();
+            }
+        }
+        let env = Arc::new(BuildEnv {
+            registry: registry.unwrap_or_default(),
+            signals: self.signals.clone(),
+        });
+        self.built = patch_nodes(world, entity, prev, &nodes, &env);
+    }
+
+    fn cleanup(&self, _world: &mut DeferredWorld, _entity: Entity) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TemplateAsset, TemplateNode};
+
+    fn parse(text: &str) -> Result<TemplateAsset, ron::error::SpannedError> {
+        ron::de::from_str(text)
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(
+            parse("(nodes: [])").unwrap(),
+            TemplateAsset { nodes: vec![] }
+        );
+    }
+
+    #[test]
+    fn test_nodes() {
+        let asset = parse(
+            r#"(
+                nodes: [
+                    Node(
+                        styles: ["panel"],
+                        children: [
+                            BoundText("title"),
+                            Cond(
+                                signal: "show_details",
+                                then: [Invoke("details")],
+                                otherwise: [Text("No selection")],
+                            ),
+                        ],
+                    ),
+                ],
+            )"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            asset.nodes,
+            vec![TemplateNode::Node {
+                styles: vec!["panel".to_string()],
+                children: vec![
+                    TemplateNode::BoundText("title".to_string()),
+                    TemplateNode::Cond {
+                        signal: "show_details".to_string(),
+                        then: vec![TemplateNode::Invoke("details".to_string())],
+                        otherwise: vec![TemplateNode::Text("No selection".to_string())],
+                    },
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_defaults() {
+        let asset = parse(
+            r#"(
+                nodes: [
+                    Node(),
+                    Cond(signal: "visible"),
+                    Switch(signal: "mode"),
+                    For(signal: "items"),
+                ],
+            )"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            asset.nodes,
+            vec![
+                TemplateNode::Node {
+                    styles: vec![],
+                    children: vec![],
+                },
+                TemplateNode::Cond {
+                    signal: "visible".to_string(),
+                    then: vec![],
+                    otherwise: vec![],
+                },
+                TemplateNode::Switch {
+                    signal: "mode".to_string(),
+                    cases: vec![],
+                    fallback: vec![],
+                },
+                TemplateNode::For {
+                    signal: "items".to_string(),
+                    each: vec![],
+                    fallback: vec![],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_switch_and_for() {
+        let asset = parse(
+            r#"(
+                nodes: [
+                    Switch(
+                        signal: "mode",
+                        cases: [("edit", [Text("Editing")]), ("view", [])],
+                        fallback: [Text("Unknown")],
+                    ),
+                    For(
+                        signal: "items",
+                        each: [Text("{item}")],
+                        fallback: [Text("None")],
+                    ),
+                ],
+            )"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            asset.nodes,
+            vec![
+                TemplateNode::Switch {
+                    signal: "mode".to_string(),
+                    cases: vec![
+                        (
+                            "edit".to_string(),
+                            vec![TemplateNode::Text("Editing".to_string())]
+                        ),
+                        ("view".to_string(), vec![]),
+                    ],
+                    fallback: vec![TemplateNode::Text("Unknown".to_string())],
+                },
+                TemplateNode::For {
+                    signal: "items".to_string(),
+                    each: vec![TemplateNode::Text("{item}".to_string())],
+                    fallback: vec![TemplateNode::Text("None".to_string())],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(parse("(nodes: [Button()])").is_err());
+        assert!(parse("(nodes: [Cond(then: [])])").is_err());
+        assert!(parse("(nodes: [Text(\"unterminated)])").is_err());
+    }
+}
@@ -0,0 +1,567 @@
+use std::{marker::PhantomData, sync::Arc, time::Duration};
+
+use bevy::{
+    ecs::{system::SystemId, world::DeferredWorld},
+    prelude::*,
+    time::{Real, Virtual},
+};
+
+use crate::{
+    effect_cell::{AnyEffect, EffectCell},
+    mutable::MutableCell,
+    owner::OwnedBy,
+    tracking::run_tracked,
+    Mutable, Signal, TemplateContext, WriteMutable,
+};
+
+/// Which clock drives a timer.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum TimerClock {
+    /// Game time, from [`Time<Virtual>`]. This clock stops while the game is paused, and is
+    /// affected by the relative speed.
+    #[default]
+    Virtual,
+
+    /// Wall-clock time, from [`Time<Real>`]. This clock keeps running while the game is paused,
+    /// which is usually what UI timing wants.
+    Real,
+}
+
+/// What a timer does when it fires.
+#[derive(Clone)]
+pub enum TimerAction {
+    /// Run a one-shot system.
+    Callback(SystemId),
+
+    /// Run a function with exclusive world access.
+    Update(Arc<dyn Fn(&mut World) + Send + Sync>),
+}
+
+impl TimerAction {
+    /// An action which sets `mutable` to `value`.
+    pub fn set<T: PartialEq + Clone + Send + Sync + 'static>(
+        mutable: Mutable<T>,
+        value: T,
+    ) -> Self {
+        Self::Update(Arc::new(move |world| {
+            world.write_mutable(mutable.id(), value.clone());
+        }))
+    }
+
+    /// An action which modifies the value of `mutable` in place.
+    pub fn update<T: Send + Sync + 'static, F: Fn(Mut<T>) + Send + Sync + 'static>(
+        mutable: Mutable<T>,
+        updater: F,
+    ) -> Self {
+        Self::Update(Arc::new(move |world| {
+            mutable.update(world, &updater);
+        }))
+    }
+
+    /// An action which does nothing.
+    fn none() -> Self {
+        Self::Update(Arc::new(|_| {}))
+    }
+
+    fn run(&self, world: &mut World) {
+        match self {
+            Self::Callback(callback) => {
+                world.commands().run_system(*callback);
+            }
+            Self::Update(update) => update(world),
+        }
+    }
+}
+
+impl From<SystemId> for TimerAction {
+    fn from(callback: SystemId) -> Self {
+        Self::Callback(callback)
+    }
+}
+
+/// How [`CreateTimer::create_rate_limited`] limits the rate of changes to a signal.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RateLimit {
+    /// Wait until the signal has stopped changing for the delay, then pass on the latest value.
+    Debounce,
+
+    /// Pass on a change immediately, then ignore further changes until the delay has elapsed,
+    /// after which the latest value is passed on. Passing on the latest value starts a new delay,
+    /// so changes are never passed on more often than once per delay.
+    Throttle,
+}
+
+/// A timer which is ticked by the [`Time`] of its clock.
+#[derive(Component)]
+struct ScopedTimer {
+    timer: Timer,
+    clock: TimerClock,
+    action: TimerAction,
+    /// Whether to despawn the entity, rather than just remove the timer, when a one-shot timer
+    /// finishes.
+    despawn_on_finish: bool,
+}
+
+impl ScopedTimer {
+    fn new(
+        duration: Duration,
+        mode: TimerMode,
+        clock: TimerClock,
+        action: TimerAction,
+        despawn_on_finish: bool,
+    ) -> Self {
+        Self {
+            timer: Timer::new(duration, mode),
+            clock,
+            action,
+            despawn_on_finish,
+        }
+    }
+}
+
+/// Ticks all timers, and runs the actions of the ones which fired.
+pub(crate) fn tick_timers(world: &mut World) {
+    let virtual_delta = world
+        .get_resource::<Time<Virtual>>()
+        .map(|time| time.delta())
+        .unwrap_or_default();
+    let real_delta = world
+        .get_resource::<Time<Real>>()
+        .map(|time| time.delta())
+        .unwrap_or_default();
+
+    let mut fired: Vec<Entity> = Vec::new();
+    let mut query = world.query::<(Entity, &mut ScopedTimer)>();
+    for (entity, mut timer) in query.iter_mut(world) {
+        let delta = match timer.clock {
+            TimerClock::Virtual => virtual_delta,
+            TimerClock::Real => real_delta,
+        };
+        if timer.timer.tick(delta).just_finished() {
+            fired.push(entity);
+        }
+    }
+
+    for entity in fired {
+        // An earlier action may have despawned this timer.
+        let Ok(mut entt) = world.get_entity_mut(entity) else {
+            continue;
+        };
+        let Some(timer) = entt.get::<ScopedTimer>() else {
+            continue;
+        };
+        let action = if timer.timer.mode() == TimerMode::Repeating {
+            timer.action.clone()
+        } else {
+            let timer = entt.take::<ScopedTimer>().unwrap();
+            if timer.despawn_on_finish {
+                entt.despawn();
+            }
+            timer.action
+        };
+        action.run(world);
+    }
+}
+
+/// Effect which copies a source signal into an output mutable, limiting the rate of changes.
+struct RateLimitEffect<T> {
+    source: Signal<T>,
+    output: Mutable<T>,
+    limit: RateLimit,
+    delay: Duration,
+    clock: TimerClock,
+    first: bool,
+}
+
+impl<T: PartialEq + Clone + Send + Sync + 'static> AnyEffect for RateLimitEffect<T> {
+    fn update(&mut self, world: &mut World, entity: Entity) {
+        let value = run_tracked(world, entity, |world| self.source.get_clone(&*world));
+        if std::mem::replace(&mut self.first, false) {
+            world.write_mutable(self.output.id(), value);
+            return;
+        }
+        let mut entt = world.entity_mut(entity);
+        match self.limit {
+            RateLimit::Debounce => {
+                // Restart the timer.
+                entt.insert(ScopedTimer::new(
+                    self.delay,
+                    TimerMode::Once,
+                    self.clock,
+                    TimerAction::set(self.output, value),
+                    false,
+                ));
+            }
+            RateLimit::Throttle => {
+                if let Some(mut timer) = entt.get_mut::<ScopedTimer>() {
+                    // Within the delay, pass on the latest value once it has elapsed.
+                    timer.action = self.trailing(entity, value);
+                    return;
+                }
+                entt.insert(ScopedTimer::new(
+                    self.delay,
+                    TimerMode::Once,
+                    self.clock,
+                    TimerAction::none(),
+                    false,
+                ));
+                world.write_mutable(self.output.id(), value);
+            }
+        }
+    }
+
+    fn cleanup(&self, _world: &mut DeferredWorld, _entity: Entity) {}
+}
+
+impl<T: PartialEq + Clone + Send + Sync + 'static> RateLimitEffect<T> {
+    /// The action which passes on the latest value at the end of a throttle delay. This also
+    /// starts a new delay, so that a change which arrives just after the latest value was passed
+    /// on is not passed on immediately as well.
+    fn trailing(&self, entity: Entity, value: T) -> TimerAction {
+        let output = self.output;
+        let delay = self.delay;
+        let clock = self.clock;
+        TimerAction::Update(Arc::new(move |world| {
+            world.write_mutable(output.id(), value.clone());
+            if let Ok(mut entt) = world.get_entity_mut(entity) {
+                entt.insert(ScopedTimer::new(
+                    delay,
+                    TimerMode::Once,
+                    clock,
+                    TimerAction::none(),
+                    false,
+                ));
+            }
+        }))
+    }
+}
+
+fn timeout_bundle(
+    delay: Duration,
+    clock: TimerClock,
+    action: TimerAction,
+    owner: Entity,
+) -> impl Bundle {
+    (
+        ScopedTimer::new(delay, TimerMode::Once, clock, action, true),
+        OwnedBy(owner),
+    )
+}
+
+fn interval_bundle(
+    period: Duration,
+    clock: TimerClock,
+    action: TimerAction,
+    owner: Entity,
+) -> impl Bundle {
+    (
+        ScopedTimer::new(period, TimerMode::Repeating, clock, action, false),
+        OwnedBy(owner),
+    )
+}
+
+fn spawn_rate_limited<T: PartialEq + Clone + Default + Send + Sync + 'static>(
+    commands: &mut Commands,
+    owner: Entity,
+    source: Signal<T>,
+    limit: RateLimit,
+    delay: Duration,
+    clock: TimerClock,
+) -> Mutable<T> {
+    let output = Mutable {
+        cell: commands
+            .spawn((MutableCell(T::default()), OwnedBy(owner)))
+            .id(),
+        marker: PhantomData,
+    };
+    commands.spawn((
+        EffectCell::new(RateLimitEffect {
+            source,
+            output,
+            limit,
+            delay,
+            clock,
+            first: true,
+        }),
+        OwnedBy(owner),
+    ));
+    output
+}
+
+/// Methods for creating scoped timers. Timers are owned by the parent entity, and stop when it is
+/// despawned. Each method returns the timer entity, which can be despawned to cancel the timer.
+pub trait CreateTimer {
+    /// Creates a timer which performs `action` once, after `delay`.
+    fn create_timeout(
+        &mut self,
+        delay: Duration,
+        clock: TimerClock,
+        action: impl Into<TimerAction>,
+    ) -> Entity;
+
+    /// Creates a timer which performs `action` repeatedly, every `period`.
+    fn create_interval(
+        &mut self,
+        period: Duration,
+        clock: TimerClock,
+        action: impl Into<TimerAction>,
+    ) -> Entity;
+
+    /// Creates a mutable which follows `source`, but whose rate of change is limited. Until
+    /// `source` has first been read, the value of the mutable is `T::default()`. This is used to
+    /// implement [`Signal::debounce`] and [`Signal::throttle`].
+    fn create_rate_limited<T: PartialEq + Clone + Default + Send + Sync + 'static>(
+        &mut self,
+        source: Signal<T>,
+        limit: RateLimit,
+        delay: Duration,
+        clock: TimerClock,
+    ) -> Mutable<T>;
+}
+
+impl CreateTimer for EntityCommands<'_> {
+    fn create_timeout(
+        &mut self,
+        delay: Duration,
+        clock: TimerClock,
+        action: impl Into<TimerAction>,
+    ) -> Entity {
+        let owner = self.id();
+        self.commands()
+            .spawn(timeout_bundle(delay, clock, action.into(), owner))
+            .id()
+    }
+
+    fn create_interval(
+        &mut self,
+        period: Duration,
+        clock: TimerClock,
+        action: impl Into<TimerAction>,
+    ) -> Entity {
+        let owner = self.id();
+        self.commands()
+            .spawn(interval_bundle(period, clock, action.into(), owner))
+            .id()
+    }
+
+    fn create_rate_limited<T: PartialEq + Clone + Default + Send + Sync + 'static>(
+        &mut self,
+        source: Signal<T>,
+        limit: RateLimit,
+        delay: Duration,
+        clock: TimerClock,
+    ) -> Mutable<T> {
+        let owner = self.id();
+        spawn_rate_limited(&mut self.commands(), owner, source, limit, delay, clock)
+    }
+}
+
+impl CreateTimer for ChildSpawnerCommands<'_> {
+    fn create_timeout(
+        &mut self,
+        delay: Duration,
+        clock: TimerClock,
+        action: impl Into<TimerAction>,
+    ) -> Entity {
+        let owner = self.target_entity();
+        self.commands()
+            .spawn(timeout_bundle(delay, clock, action.into(), owner))
+            .id()
+    }
+
+    fn create_interval(
+        &mut self,
+        period: Duration,
+        clock: TimerClock,
+        action: impl Into<TimerAction>,
+    ) -> Entity {
+        let owner = self.target_entity();
+        self.commands()
+            .spawn(interval_bundle(period, clock, action.into(), owner))
+            .id()
+    }
+
+    fn create_rate_limited<T: PartialEq + Clone + Default + Send + Sync + 'static>(
+        &mut self,
+        source: Signal<T>,
+        limit: RateLimit,
+        delay: Duration,
+        clock: TimerClock,
+    ) -> Mutable<T> {
+        let owner = self.target_entity();
+        spawn_rate_limited(&mut self.commands(), owner, source, limit, delay, clock)
+    }
+}
+
+impl CreateTimer for TemplateContext<'_> {
+    fn create_timeout(
+        &mut self,
+        delay: Duration,
+        clock: TimerClock,
+        action: impl Into<TimerAction>,
+    ) -> Entity {
+        let owner = self.target_entity();
+        self.commands()
+            .spawn(timeout_bundle(delay, clock, action.into(), owner))
+            .id()
+    }
+
+    fn create_interval(
+        &mut self,
+        period: Duration,
+        clock: TimerClock,
+        action: impl Into<TimerAction>,
+    ) -> Entity {
+        let owner = self.target_entity();
+        self.commands()
+            .spawn(interval_bundle(period, clock, action.into(), owner))
+            .id()
+    }
+
+    fn create_rate_limited<T: PartialEq + Clone + Default + Send + Sync + 'static>(
+        &mut self,
+        source: Signal<T>,
+        limit: RateLimit,
+        delay: Duration,
+        clock: TimerClock,
+    ) -> Mutable<T> {
+        let owner = self.target_entity();
+        spawn_rate_limited(&mut self.commands(), owner, source, limit, delay, clock)
+    }
+}
+
+impl CreateTimer for EntityWorldMut<'_> {
+    fn create_timeout(
+        &mut self,
+        delay: Duration,
+        clock: TimerClock,
+        action: impl Into<TimerAction>,
+    ) -> Entity {
+        let owner = self.id();
+        let action = action.into();
+        self.world_scope(|world| {
+            world
+                .spawn(timeout_bundle(delay, clock, action, owner))
+                .id()
+        })
+    }
+
+    fn create_interval(
+        &mut self,
+        period: Duration,
+        clock: TimerClock,
+        action: impl Into<TimerAction>,
+    ) -> Entity {
+        let owner = self.id();
+        let action = action.into();
+        self.world_scope(|world| {
+            world
+                .spawn(interval_bundle(period, clock, action, owner))
+                .id()
+        })
+    }
+
+    fn create_rate_limited<T: PartialEq + Clone + Default + Send + Sync + 'static>(
+        &mut self,
+        source: Signal<T>,
+        limit: RateLimit,
+        delay: Duration,
+        clock: TimerClock,
+    ) -> Mutable<T> {
+        let owner = self.id();
+        self.world_scope(|world| {
+            let output =
+                spawn_rate_limited(&mut world.commands(), owner, source, limit, delay, clock);
+            world.flush();
+            output
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::{prelude::*, time::Virtual};
+
+    use super::{tick_timers, CreateTimer, RateLimit, TimerClock};
+    use crate::{effect_cell::update_effects, CreateMutable, Mutable, ReadMutable, WriteMutable};
+
+    const DELAY: Duration = Duration::from_millis(100);
+
+    /// A world with a source mutable, and a rate-limited mutable which follows it.
+    fn setup(limit: RateLimit) -> (World, Mutable<i32>, Mutable<i32>) {
+        let mut world = World::new();
+        world.insert_resource(Time::<Virtual>::default());
+        let source = world.create_mutable(1);
+        let output = world.spawn_empty().create_rate_limited(
+            source.signal(),
+            limit,
+            DELAY,
+            TimerClock::Virtual,
+        );
+        frame(&mut world, 0);
+        (world, source, output)
+    }
+
+    /// Advances the clock by `millis`, then runs the timers and effects, as a frame would.
+    fn frame(world: &mut World, millis: u64) {
+        world
+            .resource_mut::<Time<Virtual>>()
+            .advance_by(Duration::from_millis(millis));
+        world.increment_change_tick();
+        tick_timers(world);
+        update_effects(world);
+    }
+
+    /// Sets the source, and runs a frame without advancing the clock.
+    fn set(world: &mut World, source: Mutable<i32>, value: i32) {
+        world.write_mutable(source.id(), value);
+        frame(world, 0);
+    }
+
+    #[test]
+    fn test_debounce() {
+        let (mut world, source, output) = setup(RateLimit::Debounce);
+        assert_eq!(world.read_mutable(&output), 1);
+
+        set(&mut world, source, 2);
+        frame(&mut world, 60);
+        assert_eq!(world.read_mutable(&output), 1);
+
+        // A change within the delay restarts it.
+        set(&mut world, source, 3);
+        frame(&mut world, 60);
+        assert_eq!(world.read_mutable(&output), 1);
+
+        frame(&mut world, 50);
+        assert_eq!(world.read_mutable(&output), 3);
+    }
+
+    #[test]
+    fn test_throttle() {
+        let (mut world, source, output) = setup(RateLimit::Throttle);
+        assert_eq!(world.read_mutable(&output), 1);
+
+        // The first change is passed on immediately, and the latest of the changes within the
+        // delay is passed on once it has elapsed.
+        set(&mut world, source, 2);
+        assert_eq!(world.read_mutable(&output), 2);
+        set(&mut world, source, 3);
+        set(&mut world, source, 4);
+        assert_eq!(world.read_mutable(&output), 2);
+        frame(&mut world, 100);
+        assert_eq!(world.read_mutable(&output), 4);
+
+        // Passing on the latest value starts a new delay.
+        set(&mut world, source, 5);
+        assert_eq!(world.read_mutable(&output), 4);
+        frame(&mut world, 100);
+        assert_eq!(world.read_mutable(&output), 5);
+
+        // Once a delay passes with no changes, the next change is passed on immediately.
+        frame(&mut world, 100);
+        set(&mut world, source, 6);
+        assert_eq!(world.read_mutable(&output), 6);
+    }
+}
@@ -0,0 +1,255 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use bevy::{
+    ecs::{
+        component::{ComponentId, Tick},
+        world::DeferredWorld,
+    },
+    prelude::*,
+};
+
+use crate::effect_cell::invalidate_effect_order;
+
+/// Records the reactive dependencies of an effect: the mutables, memos, components and resources
+/// that were read the last time the effect ran. The effect is only run again when one of these
+/// dependencies changes.
+///
+/// An effect which reads nothing through a tracked API (for example, a system which only uses
+/// `Res` or `Query` parameters) has an empty scope, and will be polled every frame.
+#[derive(Component, Default)]
+pub struct TrackingScope {
+    /// Components (including mutable and memo cells) that were read, along with whether the
+    /// component was present at the time.
+    component_deps: HashMap<(Entity, ComponentId), bool>,
+
+    /// Resources that were read, along with whether the resource was present at the time.
+    resource_deps: HashMap<ComponentId, bool>,
+
+    /// If true, the effect read something that could not be tracked, and must be polled.
+    poll: bool,
+
+    /// If true, the effect failed the last time it ran. A failed effect is not polled, so that
+    /// the error is only reported once; it is only run again when one of its dependencies
+    /// changes.
+    errored: bool,
+
+    /// World change tick at the time the effect last ran.
+    tick: Tick,
+}
+
+impl TrackingScope {
+    /// True if the effect needs to run: either one of its dependencies has changed since it
+    /// last ran, or it has no tracked dependencies at all.
+    pub fn should_run(&self, world: &World) -> bool {
+        if self.errored {
+            return self.dependencies_changed(world);
+        }
+        self.poll
+            || (self.component_deps.is_empty() && self.resource_deps.is_empty())
+            || self.dependencies_changed(world)
+    }
+
+    /// Mark the effect as having failed.
+    pub(crate) fn set_errored(&mut self) {
+        self.errored = true;
+    }
+
+    /// The entities whose components were read by the effect. This is used to order effects
+    /// so that an effect runs after any memos that it depends on.
+    pub(crate) fn dependencies(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.component_deps.keys().map(|(entity, _)| *entity)
+    }
+
+    /// True if this scope has the same component dependencies as `other`, and so would give the
+    /// same effect order.
+    fn same_dependencies(&self, other: &TrackingScope) -> bool {
+        self.component_deps.len() == other.component_deps.len()
+            && self
+                .component_deps
+                .keys()
+                .all(|key| other.component_deps.contains_key(key))
+    }
+
+    /// True if any of the dependencies recorded in this scope have changed since the effect
+    /// last ran.
+    pub fn dependencies_changed(&self, world: &World) -> bool {
+        let this_run = world.read_change_tick();
+        self.component_deps
+            .iter()
+            .any(|(&(entity, component), &present)| {
+                // A despawned entity has lost the component, which is a change if the component
+                // was present when the effect ran.
+                let Ok(entity) = world.get_entity(entity) else {
+                    return present;
+                };
+                match entity.get_change_ticks_by_id(component) {
+                    Some(ticks) => !present || ticks.is_changed(self.tick, this_run),
+                    None => present,
+                }
+            })
+            || self.resource_deps.iter().any(|(&resource, &present)| {
+                match world.get_resource_change_ticks_by_id(resource) {
+                    Some(ticks) => !present || ticks.is_changed(self.tick, this_run),
+                    None => present,
+                }
+            })
+    }
+}
+
+thread_local! {
+    /// Stack of tracking scopes for effects which are currently running.
+    static TRACKING: RefCell<Vec<TrackingScope>> = const { RefCell::new(Vec::new()) };
+
+    /// Scopes of tracked functions which panicked, along with their effect entities. These are
+    /// stored by [`restore_panicked_scopes`] once the panic has been caught.
+    static PANICKED: RefCell<Vec<(Entity, TrackingScope)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Removes any tracking scopes left on the stack if a tracked function panics, so that the
+/// panic can be caught by an error boundary without leaving the stack in a bad state. The
+/// reads made before the panic are kept, so that the effect can be re-run when they change.
+struct UnwindGuard {
+    depth: usize,
+    entity: Entity,
+}
+
+impl Drop for UnwindGuard {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            let scope = TRACKING.with_borrow_mut(|stack| stack.drain(self.depth..).next());
+            if let Some(scope) = scope {
+                PANICKED.with_borrow_mut(|panicked| panicked.push((self.entity, scope)));
+            }
+        }
+    }
+}
+
+/// Stores the scopes of tracked functions which panicked on their effect entities. This should
+/// be called after catching a panic from an effect.
+pub(crate) fn restore_panicked_scopes(world: &mut World) {
+    let panicked = PANICKED.with_borrow_mut(std::mem::take);
+    if panicked.is_empty() {
+        return;
+    }
+    let tick = world.read_change_tick();
+    for (entity, mut scope) in panicked {
+        scope.tick = tick;
+        if let Ok(mut entt) = world.get_entity_mut(entity) {
+            entt.insert(scope);
+        }
+    }
+    invalidate_effect_order(&mut DeferredWorld::from(&mut *world));
+}
+
+/// Run a function within a new tracking scope. Any tracked reads made by the function are
+/// recorded, and the resulting scope is stored on the effect `entity`.
+pub(crate) fn run_tracked<O>(
+    world: &mut World,
+    entity: Entity,
+    f: impl FnOnce(&mut World) -> O,
+) -> O {
+    let depth = TRACKING.with_borrow_mut(|stack| {
+        stack.push(TrackingScope::default());
+        stack.len() - 1
+    });
+    let guard = UnwindGuard { depth, entity };
+    let result = f(world);
+    drop(guard);
+    let mut scope = TRACKING
+        .with_borrow_mut(|stack| stack.pop())
+        .unwrap_or_default();
+    scope.tick = world.read_change_tick();
+    if let Ok(mut entt) = world.get_entity_mut(entity) {
+        let reordered = entt
+            .get::<TrackingScope>()
+            .is_none_or(|prev| !prev.same_dependencies(&scope));
+        entt.insert(scope);
+        if reordered {
+            invalidate_effect_order(&mut DeferredWorld::from(&mut *world));
+        }
+    }
+    result
+}
+
+/// Add a component read to the current tracking scope, if there is one.
+pub(crate) fn track_component<C: Component>(world: &World, entity: Entity, present: bool) {
+    TRACKING.with_borrow_mut(|stack| {
+        if let Some(scope) = stack.last_mut() {
+            match world.component_id::<C>() {
+                Some(component) => {
+                    scope.component_deps.insert((entity, component), present);
+                }
+                // The component has never been registered, so there are no change ticks
+                // to compare against. Fall back to polling.
+                None => scope.poll = true,
+            }
+        }
+    });
+}
+
+/// Add a resource read to the current tracking scope, if there is one.
+pub(crate) fn track_resource<R: Resource>(world: &World, present: bool) {
+    TRACKING.with_borrow_mut(|stack| {
+        if let Some(scope) = stack.last_mut() {
+            match world.resource_id::<R>() {
+                Some(resource) => {
+                    scope.resource_deps.insert(resource, present);
+                }
+                None => scope.poll = true,
+            }
+        }
+    });
+}
+
+/// Trait for reading resources and components such that the read is recorded as a dependency
+/// of the current tracking scope. Effects which use these methods will re-run when the resource
+/// or component changes, or is added or removed.
+pub trait ReadTracked {
+    /// Read a resource, adding it to the current tracking scope. Panics if the resource does
+    /// not exist.
+    fn read_resource<R: Resource>(&self) -> &R;
+
+    /// Read a resource if it exists, adding it to the current tracking scope.
+    fn read_resource_opt<R: Resource>(&self) -> Option<&R>;
+
+    /// Read a component of an entity, adding it to the current tracking scope.
+    fn read_component<C: Component>(&self, entity: Entity) -> Option<&C>;
+}
+
+impl ReadTracked for World {
+    fn read_resource<R: Resource>(&self) -> &R {
+        track_resource::<R>(self, true);
+        self.resource::<R>()
+    }
+
+    fn read_resource_opt<R: Resource>(&self) -> Option<&R> {
+        let resource = self.get_resource::<R>();
+        track_resource::<R>(self, resource.is_some());
+        resource
+    }
+
+    fn read_component<C: Component>(&self, entity: Entity) -> Option<&C> {
+        let component = self.get::<C>(entity);
+        track_component::<C>(self, entity, component.is_some());
+        component
+    }
+}
+
+impl ReadTracked for DeferredWorld<'_> {
+    fn read_resource<R: Resource>(&self) -> &R {
+        track_resource::<R>(self, true);
+        self.resource::<R>()
+    }
+
+    fn read_resource_opt<R: Resource>(&self) -> Option<&R> {
+        let resource = self.get_resource::<R>();
+        track_resource::<R>(self, resource.is_some());
+        resource
+    }
+
+    fn read_component<C: Component>(&self, entity: Entity) -> Option<&C> {
+        let component = self.get::<C>(entity);
+        track_component::<C>(self, entity, component.is_some());
+        component
+    }
+}
@@ -0,0 +1,594 @@
+#![allow(clippy::type_complexity)]
+use bevy::{
+    ecs::{
+        bundle::{BundleEffect, DynamicBundle},
+        spawn::SpawnableList,
+        system::SystemId,
+        world::DeferredWorld,
+    },
+    prelude::*,
+    ui::experimental::GhostNode,
+};
+
+use crate::{
+    dyn_children::Fragment,
+    effect_cell::{AnyEffect, EffectCell},
+    error_boundary::report_error,
+    tracking::run_tracked,
+    CreateMutable, DynChildOf, Mutable, OwnedBy, ReadMutable, ReadTracked, WriteMutable,
+};
+
+/// Tracks an enter / exit transition. This is useful for widgets like dialog boxes and popup
+/// menus which have an opening and closing animation.
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub enum BistableTransitionState {
+    /// Opening animation.
+    Entering,
+
+    /// Fully open.
+    Entered,
+
+    /// Closing animation.
+    Exiting,
+
+    /// Fully closed.
+    #[default]
+    Exited,
+}
+
+impl BistableTransitionState {
+    /// Convert the state into a readable string.
+    pub fn as_name(&self) -> &str {
+        match self {
+            BistableTransitionState::Entering => "entering",
+            BistableTransitionState::Entered => "entered",
+            BistableTransitionState::Exiting => "exiting",
+            BistableTransitionState::Exited => "exited",
+        }
+    }
+}
+
+type TransitionBranchFn =
+    Box<dyn Fn(Mutable<BistableTransitionState>, &mut World, Entity) + Send + Sync>;
+
+fn branch_fn<
+    S: SpawnableList<DynChildOf>,
+    F: Fn(Mutable<BistableTransitionState>) -> S + Send + Sync + 'static,
+>(
+    f: F,
+) -> TransitionBranchFn {
+    Box::new(move |state, world, entity| f(state).spawn(world, entity))
+}
+
+/// A branch which is either active or in the process of exiting.
+struct TransitionBranch<K> {
+    key: K,
+    holder: Entity,
+    state: Mutable<BistableTransitionState>,
+    elapsed: f32,
+}
+
+/// The set of live branches for a `TransitionCond` or `TransitionSwitch`.
+///
+/// Each branch is built inside its own fragment entity, along with a mutable holding its
+/// transition state. When a branch is deselected it stays in place, in the `Exiting` state, and
+/// is only despawned once it reports that its exit transition has completed, by setting its state
+/// to `Exited`. If there is a timeout, branches which haven't reported by then are despawned
+/// anyway, and entering branches are moved to `Entered`.
+struct TransitionBranches<K: PartialEq + Copy> {
+    /// How long, in seconds, to wait for a transition before completing it regardless.
+    timeout: Option<f32>,
+    /// The currently selected branch.
+    active: Option<TransitionBranch<K>>,
+    /// Branches which are playing their exit transition.
+    exiting: Vec<TransitionBranch<K>>,
+    /// Elapsed time when the transitions were last advanced.
+    last_time: Option<f32>,
+    /// One-shot system to run when a branch has finished exiting, and the key of the branch it
+    /// applies to (or `None` for any branch).
+    on_exited: Option<(Option<K>, SystemId)>,
+}
+
+impl<K: PartialEq + Copy> TransitionBranches<K> {
+    fn new(timeout: Option<f32>) -> Self {
+        Self {
+            timeout,
+            active: None,
+            exiting: Vec::new(),
+            last_time: None,
+            on_exited: None,
+        }
+    }
+
+    /// True if there is a timeout, and any branch is entering or exiting.
+    fn is_animating(&self) -> bool {
+        let Some(timeout) = self.timeout else {
+            return false;
+        };
+        !self.exiting.is_empty()
+            || self
+                .active
+                .as_ref()
+                .is_some_and(|branch| branch.elapsed <= timeout)
+    }
+
+    /// Make the branch for `key` the selected branch of `parent`. The previously selected branch
+    /// starts exiting. If the branch for `key` is still exiting, it is reversed, otherwise it is
+    /// built by calling `build`.
+    fn select(
+        &mut self,
+        world: &mut World,
+        parent: Entity,
+        key: K,
+        build: impl FnOnce(Mutable<BistableTransitionState>, &mut World, Entity),
+    ) {
+        if self.active.as_ref().is_some_and(|branch| branch.key == key) {
+            return;
+        }
+
+        if let Some(mut branch) = self.active.take() {
+            world.write_mutable(branch.state.id(), BistableTransitionState::Exiting);
+            branch.elapsed = 0.;
+            self.exiting.push(branch);
+        }
+
+        let branch = match self.exiting.iter().position(|branch| branch.key == key) {
+            Some(index) => {
+                let mut branch = self.exiting.remove(index);
+                world.write_mutable(branch.state.id(), BistableTransitionState::Entering);
+                branch.elapsed = 0.;
+                branch
+            }
+            None => {
+                let holder = world.spawn((GhostNode, Fragment, DynChildOf(parent))).id();
+                // The transition state is owned by the branch, so it is despawned along with it.
+                let state = world.create_mutable(BistableTransitionState::Entering);
+                world.entity_mut(state.id()).insert(OwnedBy(holder));
+                build(state, world, holder);
+                TransitionBranch {
+                    key,
+                    holder,
+                    state,
+                    elapsed: 0.,
+                }
+            }
+        };
+        self.active = Some(branch);
+    }
+
+    /// Despawn a branch which has finished exiting, and run the `on_exited` callback.
+    fn finish(
+        world: &mut World,
+        branch: &TransitionBranch<K>,
+        on_exited: Option<(Option<K>, SystemId)>,
+    ) {
+        if let Ok(entt) = world.get_entity_mut(branch.holder) {
+            entt.despawn();
+        }
+        if let Some((key, on_exited)) = on_exited {
+            if key.is_none_or(|key| key == branch.key) {
+                world.commands().run_system(on_exited);
+            }
+        }
+    }
+
+    /// Despawn the exiting branches which have reported that they have exited. `exited` has an
+    /// entry for each exiting branch.
+    fn remove_exited(&mut self, world: &mut World, exited: &[bool]) {
+        let on_exited = self.on_exited;
+        let mut exited = exited.iter();
+        self.exiting.retain(|branch| {
+            if !exited.next().copied().unwrap_or(false) {
+                return true;
+            }
+            Self::finish(world, branch, on_exited);
+            false
+        });
+    }
+
+    /// Advance the timeouts to the time `now`, completing the transitions of branches which
+    /// have run out of time.
+    fn advance(&mut self, world: &mut World, now: f32) {
+        let Some(timeout) = self.timeout else {
+            return;
+        };
+        let delta = now - self.last_time.unwrap_or(now);
+        self.last_time = Some(now);
+
+        if let Some(branch) = self.active.as_mut() {
+            if branch.elapsed <= timeout {
+                branch.elapsed += delta;
+                if branch.elapsed > timeout
+                    && world.read_mutable(&branch.state) == BistableTransitionState::Entering
+                {
+                    world.write_mutable(branch.state.id(), BistableTransitionState::Entered);
+                }
+            }
+        }
+
+        let on_exited = self.on_exited;
+        self.exiting.retain_mut(|branch| {
+            branch.elapsed += delta;
+            if branch.elapsed <= timeout {
+                return true;
+            }
+            Self::finish(world, branch, on_exited);
+            false
+        });
+    }
+
+    /// Run a transition update from within an effect. `key_fn` is called from within a tracking
+    /// scope, and returns the key of the branch to select. The scope also depends on the state of
+    /// each branch, so that the effect re-runs when a branch reports that it has exited, and
+    /// while any timeout is pending, on [`Time`], so that the effect is re-run every frame until
+    /// the timeouts have elapsed.
+    fn update(
+        &mut self,
+        world: &mut World,
+        entity: Entity,
+        key_fn: impl FnOnce(&mut World) -> Result<K, String>,
+        build: impl FnOnce(&K, Mutable<BistableTransitionState>, &mut World, Entity),
+    ) {
+        let result = run_tracked(world, entity, |world| {
+            let key = key_fn(world)?;
+            if let Some(branch) = self.active.as_ref() {
+                // The active branch will start exiting if the key has changed.
+                world.read_mutable(&branch.state);
+            }
+            let exited: Vec<bool> = self
+                .exiting
+                .iter()
+                .map(|branch| world.read_mutable(&branch.state) == BistableTransitionState::Exited)
+                .collect();
+            let changed = self.active.as_ref().is_none_or(|branch| branch.key != key);
+            let now = (self.timeout.is_some() && (changed || self.is_animating()))
+                .then(|| world.read_resource::<Time>().elapsed_secs());
+            Ok((key, exited, now))
+        });
+        let (key, exited, now) = match result {
+            Ok(result) => result,
+            Err(message) => {
+                report_error(world, entity, message);
+                return;
+            }
+        };
+
+        self.remove_exited(world, &exited);
+        if let Some(now) = now {
+            self.advance(world, now);
+        }
+        self.select(world, entity, key, |state, world, holder| {
+            build(&key, state, world, holder)
+        });
+    }
+}
+
+/// Conditional control-flow node which plays enter and exit transitions. It works like
+/// [`Cond`](crate::Cond), except that each branch is passed a mutable containing its
+/// [`BistableTransitionState`]. When the condition changes, the outgoing branch is kept alive in
+/// the `Exiting` state while the incoming branch enters. The outgoing branch reports that its
+/// exit transition has completed by setting the state to `Exited`, after which it is despawned.
+pub struct TransitionCond<M: Send + Sync + 'static, TestFn: IntoSystem<(), bool, M>> {
+    test_fn: TestFn,
+    timeout: Option<f32>,
+    pos: TransitionBranchFn,
+    neg: TransitionBranchFn,
+    on_exited: Option<SystemId>,
+    marker: std::marker::PhantomData<M>,
+}
+
+impl<M: Send + Sync + 'static, TestFn: IntoSystem<(), bool, M> + Send + Sync + 'static>
+    TransitionCond<M, TestFn>
+{
+    /// Construct a new transition-aware conditional.
+    pub fn new<
+        PS: SpawnableList<DynChildOf>,
+        Pos: Fn(Mutable<BistableTransitionState>) -> PS + Send + Sync + 'static,
+        NS: SpawnableList<DynChildOf>,
+        Neg: Fn(Mutable<BistableTransitionState>) -> NS + Send + Sync + 'static,
+    >(
+        test_fn: TestFn,
+        pos: Pos,
+        neg: Neg,
+    ) -> Self {
+        Self {
+            test_fn,
+            timeout: None,
+            pos: branch_fn(pos),
+            neg: branch_fn(neg),
+            on_exited: None,
+            marker: std::marker::PhantomData,
+        }
+    }
+
+    /// Sets a fallback timeout, in seconds. A branch which hasn't reported that it has exited
+    /// within this time of starting to exit is despawned anyway, and a branch which is still
+    /// `Entering` after this time is moved to `Entered`.
+    pub fn timeout(mut self, seconds: f32) -> Self {
+        self.timeout = Some(seconds);
+        self
+    }
+
+    /// Sets a one-shot system to run when the positive branch has completed its exit transition,
+    /// that is, once the condition has become false and the exit animation has finished.
+    pub fn on_exited(mut self, on_exited: SystemId) -> Self {
+        self.on_exited = Some(on_exited);
+        self
+    }
+}
+
+unsafe impl<M: Send + Sync + 'static, TestFn: IntoSystem<(), bool, M> + Send + Sync + 'static>
+    Bundle for TransitionCond<M, TestFn>
+{
+    fn component_ids(
+        _components: &mut bevy::ecs::component::Components,
+        _ids: &mut impl FnMut(bevy::ecs::component::ComponentId),
+    ) {
+    }
+
+    fn get_component_ids(
+        _components: &bevy::ecs::component::Components,
+        _ids: &mut impl FnMut(Option<bevy::ecs::component::ComponentId>),
+    ) {
+    }
+
+    fn register_required_components(
+        _components: &mut bevy::ecs::component::Components,
+        _required_components: &mut bevy::ecs::component::RequiredComponents,
+    ) {
+    }
+}
+
+impl<M: Send + Sync + 'static, TestFn: IntoSystem<(), bool, M> + Send + Sync + 'static>
+    DynamicBundle for TransitionCond<M, TestFn>
+{
+    type Effect = Self;
+
+    fn get_components(
+        self,
+        _func: &mut impl FnMut(bevy::ecs::component::StorageType, bevy::ptr::OwningPtr<'_>),
+    ) -> Self::Effect {
+        self
+    }
+}
+
+impl<M: Send + Sync + 'static, TestFn: IntoSystem<(), bool, M> + Send + Sync + 'static> BundleEffect
+    for TransitionCond<M, TestFn>
+{
+    fn apply(self, entity: &mut EntityWorldMut) {
+        let test_sys = entity.world_scope(|world| world.register_system(self.test_fn));
+        entity.insert((
+            EffectCell::new(TransitionCondEffect {
+                test_sys,
+                pos: self.pos,
+                neg: self.neg,
+                branches: TransitionBranches {
+                    on_exited: self.on_exited.map(|on_exited| (Some(true), on_exited)),
+                    ..TransitionBranches::new(self.timeout)
+                },
+            }),
+            Fragment,
+        ));
+    }
+}
+
+struct TransitionCondEffect {
+    test_sys: SystemId<(), bool>,
+    pos: TransitionBranchFn,
+    neg: TransitionBranchFn,
+    branches: TransitionBranches<bool>,
+}
+
+impl AnyEffect for TransitionCondEffect {
+    fn update(&mut self, world: &mut World, entity: Entity) {
+        let test_sys = self.test_sys;
+        let (pos, neg) = (&self.pos, &self.neg);
+        self.branches.update(
+            world,
+            entity,
+            |world| world.run_system(test_sys).map_err(|err| err.to_string()),
+            |test, state, world, holder| {
+                if *test {
+                    pos(state, world, holder);
+                } else {
+                    neg(state, world, holder);
+                }
+            },
+        );
+    }
+
+    fn cleanup(&self, world: &mut DeferredWorld, _entity: Entity) {
+        world.commands().unregister_system(self.test_sys);
+    }
+}
+
+pub struct TransitionCaseBuilder<'a, Value: Send + Sync> {
+    cases: &'a mut Vec<(Value, TransitionBranchFn)>,
+    fallback: &'a mut Option<TransitionBranchFn>,
+}
+
+impl<Value: Send + Sync> TransitionCaseBuilder<'_, Value> {
+    pub fn case<
+        S: SpawnableList<DynChildOf>,
+        CF: Fn(Mutable<BistableTransitionState>) -> S + Send + Sync + 'static,
+    >(
+        &mut self,
+        value: Value,
+        case_fn: CF,
+    ) -> &mut Self {
+        self.cases.push((value, branch_fn(case_fn)));
+        self
+    }
+
+    pub fn fallback<
+        S: SpawnableList<DynChildOf>,
+        FF: Fn(Mutable<BistableTransitionState>) -> S + Send + Sync + 'static,
+    >(
+        &mut self,
+        fallback_fn: FF,
+    ) -> &mut Self {
+        *self.fallback = Some(branch_fn(fallback_fn));
+        self
+    }
+}
+
+/// Switch control-flow node which plays enter and exit transitions. It works like
+/// [`Switch`](crate::Switch), except that each case is passed a mutable containing its
+/// [`BistableTransitionState`]. When the value changes, the outgoing case is kept alive in the
+/// `Exiting` state while the incoming case enters. The outgoing case reports that its exit
+/// transition has completed by setting the state to `Exited`, after which it is despawned.
+pub struct TransitionSwitch<
+    M: Send + Sync + 'static,
+    P: PartialEq + Send + Sync + 'static,
+    ValueFn: IntoSystem<(), P, M> + Send + Sync + 'static,
+> {
+    value_fn: ValueFn,
+    timeout: Option<f32>,
+    cases: Vec<(P, TransitionBranchFn)>,
+    fallback: Option<TransitionBranchFn>,
+    on_exited: Option<SystemId>,
+    marker: std::marker::PhantomData<M>,
+}
+
+impl<
+        M: Send + Sync + 'static,
+        P: PartialEq + Send + Sync + 'static,
+        ValueFn: IntoSystem<(), P, M> + Send + Sync + 'static,
+    > TransitionSwitch<M, P, ValueFn>
+{
+    /// Construct a new transition-aware switch.
+    pub fn new<CF: Fn(&mut TransitionCaseBuilder<P>)>(value_fn: ValueFn, cases_fn: CF) -> Self {
+        let mut cases: Vec<(P, TransitionBranchFn)> = Vec::new();
+        let mut fallback: Option<TransitionBranchFn> = None;
+
+        let mut case_builder = TransitionCaseBuilder {
+            cases: &mut cases,
+            fallback: &mut fallback,
+        };
+        cases_fn(&mut case_builder);
+
+        Self {
+            value_fn,
+            timeout: None,
+            cases,
+            fallback,
+            on_exited: None,
+            marker: std::marker::PhantomData,
+        }
+    }
+
+    /// Sets a fallback timeout, in seconds. A case which hasn't reported that it has exited
+    /// within this time of starting to exit is despawned anyway, and a case which is still
+    /// `Entering` after this time is moved to `Entered`.
+    pub fn timeout(mut self, seconds: f32) -> Self {
+        self.timeout = Some(seconds);
+        self
+    }
+
+    /// Sets a one-shot system to run whenever a case has completed its exit transition.
+    pub fn on_exited(mut self, on_exited: SystemId) -> Self {
+        self.on_exited = Some(on_exited);
+        self
+    }
+}
+
+unsafe impl<
+        M: Send + Sync + 'static,
+        P: PartialEq + Send + Sync + 'static,
+        ValueFn: IntoSystem<(), P, M> + Send + Sync + 'static,
+    > Bundle for TransitionSwitch<M, P, ValueFn>
+{
+    fn component_ids(
+        _components: &mut bevy::ecs::component::Components,
+        _ids: &mut impl FnMut(bevy::ecs::component::ComponentId),
+    ) {
+    }
+
+    fn get_component_ids(
+        _components: &bevy::ecs::component::Components,
+        _ids: &mut impl FnMut(Option<bevy::ecs::component::ComponentId>),
+    ) {
+    }
+
+    fn register_required_components(
+        _components: &mut bevy::ecs::component::Components,
+        _required_components: &mut bevy::ecs::component::RequiredComponents,
+    ) {
+    }
+}
+
+impl<
+        M: Send + Sync + 'static,
+        P: PartialEq + Send + Sync + 'static,
+        ValueFn: IntoSystem<(), P, M> + Send + Sync + 'static,
+    > DynamicBundle for TransitionSwitch<M, P, ValueFn>
+{
+    type Effect = Self;
+
+    fn get_components(
+        self,
+        _func: &mut impl FnMut(bevy::ecs::component::StorageType, bevy::ptr::OwningPtr<'_>),
+    ) -> Self::Effect {
+        self
+    }
+}
+
+impl<
+        M: Send + Sync + 'static,
+        P: PartialEq + Send + Sync + 'static,
+        ValueFn: IntoSystem<(), P, M> + Send + Sync + 'static,
+    > BundleEffect for TransitionSwitch<M, P, ValueFn>
+{
+    fn apply(self, entity: &mut EntityWorldMut) {
+        let value_sys = entity.world_scope(|world| world.register_system(self.value_fn));
+        entity.insert((
+            EffectCell::new(TransitionSwitchEffect {
+                value_sys,
+                cases: self.cases,
+                fallback: self.fallback,
+                branches: TransitionBranches {
+                    on_exited: self.on_exited.map(|on_exited| (None, on_exited)),
+                    ..TransitionBranches::new(self.timeout)
+                },
+            }),
+            Fragment,
+        ));
+    }
+}
+
+struct TransitionSwitchEffect<P> {
+    value_sys: SystemId<(), P>,
+    cases: Vec<(P, TransitionBranchFn)>,
+    fallback: Option<TransitionBranchFn>,
+    branches: TransitionBranches<usize>,
+}
+
+impl<P: PartialEq + Send + Sync + 'static> AnyEffect for TransitionSwitchEffect<P> {
+    fn update(&mut self, world: &mut World, entity: Entity) {
+        let value_sys = self.value_sys;
+        let (cases, fallback) = (&self.cases, &self.fallback);
+        self.branches.update(
+            world,
+            entity,
+            |world| {
+                let value = world.run_system(value_sys).map_err(|err| err.to_string())?;
+                // The fallback case is `usize::MAX`.
+                Ok(cases
+                    .iter()
+                    .position(|(case_value, _)| *case_value == value)
+                    .unwrap_or(usize::MAX))
+            },
+            |index, state, world, holder| {
+                if let Some((_, case)) = cases.get(*index) {
+                    case(state, world, holder);
+                } else if let Some(fallback) = fallback {
+                    fallback(state, world, holder);
+                }
+            },
+        );
+    }
+
+    fn cleanup(&self, world: &mut DeferredWorld, _entity: Entity) {
+        world.commands().unregister_system(self.value_sys);
+    }
+}
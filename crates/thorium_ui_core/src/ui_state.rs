@@ -0,0 +1,357 @@
+use std::{collections::HashMap, fmt, path::Path};
+
+use bevy::{
+    ecs::{component::HookContext, world::DeferredWorld},
+    prelude::*,
+    reflect::{
+        serde::{ReflectDeserializer, ReflectSerializer},
+        GetTypeRegistration, TypeRegistry,
+    },
+};
+use serde::{
+    de::{DeserializeSeed, MapAccess, Visitor},
+    ser::SerializeMap,
+    Deserializer, Serialize, Serializer,
+};
+
+use crate::mutable::MutableCell;
+
+type SaveFn = fn(&World, Entity) -> Option<Box<dyn PartialReflect>>;
+type LoadFn = fn(&mut DeferredWorld, Entity, &dyn PartialReflect);
+
+/// Marks a mutable whose value is persisted in the [`UiStateSnapshot`] under a stable key.
+#[derive(Component)]
+#[component(on_add = on_add_state_key, on_replace = on_replace_state_key)]
+pub(crate) struct StateKey {
+    key: String,
+    save: SaveFn,
+    load: LoadFn,
+    register: fn(&mut TypeRegistry),
+}
+
+impl StateKey {
+    pub(crate) fn new<T>(key: impl Into<String>) -> Self
+    where
+        T: Reflect + FromReflect + GetTypeRegistration + Clone,
+    {
+        Self {
+            key: key.into(),
+            save: save_state::<T>,
+            load: load_state::<T>,
+            register: register_type::<T>,
+        }
+    }
+}
+
+/// When a keyed mutable is created, register its type so that it can be deserialized, and
+/// restore its value from the snapshot if there is one.
+fn on_add_state_key(mut world: DeferredWorld, context: HookContext) {
+    let state_key = world.get::<StateKey>(context.entity).unwrap();
+    let (key, load, register) = (state_key.key.clone(), state_key.load, state_key.register);
+    if let Some(registry) = world.get_resource::<AppTypeRegistry>() {
+        register(&mut registry.write());
+    }
+    // Take the value out of the snapshot while it is applied, then put it back.
+    let Some(value) = world
+        .get_resource_mut::<UiStateSnapshot>()
+        .and_then(|mut snapshot| snapshot.entries.remove(&key))
+    else {
+        return;
+    };
+    load(&mut world, context.entity, value.as_ref());
+    if let Some(mut snapshot) = world.get_resource_mut::<UiStateSnapshot>() {
+        snapshot.entries.insert(key, value);
+    }
+}
+
+/// When a keyed mutable is despawned, save its value in the snapshot, so that it can be restored
+/// when the template which owned it is rebuilt.
+fn on_replace_state_key(mut world: DeferredWorld, context: HookContext) {
+    let state_key = world.get::<StateKey>(context.entity).unwrap();
+    let key = state_key.key.clone();
+    let Some(value) = (state_key.save)(&world, context.entity) else {
+        return;
+    };
+    if let Some(mut snapshot) = world.get_resource_mut::<UiStateSnapshot>() {
+        snapshot.entries.insert(key, value);
+    }
+}
+
+fn save_state<T: Reflect + Clone>(
+    world: &World,
+    entity: Entity,
+) -> Option<Box<dyn PartialReflect>> {
+    let cell = world.get::<MutableCell<T>>(entity)?;
+    Some(Box::new(cell.0.clone()))
+}
+
+fn load_state<T: FromReflect>(
+    world: &mut DeferredWorld,
+    entity: Entity,
+    value: &dyn PartialReflect,
+) {
+    let Some(value) = T::from_reflect(value) else {
+        warn!(
+            "Saved UI state for {entity} could not be converted to {}",
+            std::any::type_name::<T>()
+        );
+        return;
+    };
+    if let Some(mut cell) = world.get_mut::<MutableCell<T>>(entity) {
+        cell.0 = value;
+    }
+}
+
+fn register_type<T: GetTypeRegistration>(registry: &mut TypeRegistry) {
+    registry.register::<T>();
+}
+
+/// A snapshot of the values of keyed mutables, which are created with
+/// [`CreateKeyedMutable::create_keyed_mutable`](crate::CreateKeyedMutable::create_keyed_mutable).
+/// This can be saved to and loaded from a RON file, so that UI-local state such as expanded tree
+/// nodes or splitter positions can be preserved across sessions.
+///
+/// When a `UiStateSnapshot` is present as a resource, keyed mutables take their initial value
+/// from it when they are created, and save their value back into it when they are despawned.
+/// Keys should be unique among the mutables which exist at any one time.
+///
+/// Values are serialized using reflection. The types of keyed mutables are registered
+/// automatically when the mutables are created, but the types must also be registered before
+/// loading a snapshot which contains them, for example with `App::register_type`.
+#[derive(Resource, Default)]
+pub struct UiStateSnapshot {
+    entries: HashMap<String, Box<dyn PartialReflect>>,
+}
+
+impl UiStateSnapshot {
+    /// Construct a snapshot of all keyed mutables which currently exist.
+    pub fn capture(world: &mut World) -> Self {
+        let mut snapshot = Self::default();
+        snapshot.update(world);
+        snapshot
+    }
+
+    /// Record the values of all keyed mutables which currently exist. Entries for keys which
+    /// don't currently exist are kept.
+    pub fn update(&mut self, world: &mut World) {
+        let mut query = world.query::<(Entity, &StateKey)>();
+        for (entity, state_key) in query.iter(world) {
+            if let Some(value) = (state_key.save)(world, entity) {
+                self.entries.insert(state_key.key.clone(), value);
+            }
+        }
+    }
+
+    /// Set the values of all keyed mutables which currently exist and have an entry in the
+    /// snapshot.
+    pub fn restore(&self, world: &mut World) {
+        let mut query = world.query::<(Entity, &StateKey)>();
+        let targets: Vec<(Entity, LoadFn, &dyn PartialReflect)> = query
+            .iter(world)
+            .filter_map(|(entity, state_key)| {
+                let value = self.entries.get(&state_key.key)?;
+                Some((entity, state_key.load, value.as_ref()))
+            })
+            .collect();
+        let mut world: DeferredWorld = world.into();
+        for (entity, load, value) in targets {
+            load(&mut world, entity, value);
+        }
+    }
+
+    /// The number of entries in the snapshot.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// True if the snapshot has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// True if the snapshot has an entry for `key`.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Remove the entry for `key`, so that the mutable with that key will start from its initial
+    /// value.
+    pub fn remove(&mut self, key: &str) {
+        self.entries.remove(key);
+    }
+
+    /// Serialize the snapshot as RON.
+    pub fn to_ron(&self, registry: &TypeRegistry) -> Result<String, String> {
+        ron::ser::to_string_pretty(
+            &SnapshotSerializer {
+                entries: &self.entries,
+                registry,
+            },
+            ron::ser::PrettyConfig::default(),
+        )
+        .map_err(|err| err.to_string())
+    }
+
+    /// Deserialize a snapshot from RON.
+    pub fn from_ron(text: &str, registry: &TypeRegistry) -> Result<Self, String> {
+        let mut deserializer = ron::Deserializer::from_str(text).map_err(|err| err.to_string())?;
+        let entries = SnapshotDeserializer { registry }
+            .deserialize(&mut deserializer)
+            .map_err(|err| err.to_string())?;
+        Ok(Self { entries })
+    }
+
+    /// Save the snapshot to a RON file, using the type registry of `world`.
+    pub fn save(&self, world: &World, path: impl AsRef<Path>) -> Result<(), String> {
+        let text = self.to_ron(&world.resource::<AppTypeRegistry>().read())?;
+        std::fs::write(path, text).map_err(|err| err.to_string())
+    }
+
+    /// Load a snapshot from a RON file, using the type registry of `world`.
+    pub fn load(world: &World, path: impl AsRef<Path>) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+        Self::from_ron(&text, &world.resource::<AppTypeRegistry>().read())
+    }
+}
+
+/// Serializes the snapshot as a map from key to reflected value, in key order so that the output
+/// is stable.
+struct SnapshotSerializer<'a> {
+    entries: &'a HashMap<String, Box<dyn PartialReflect>>,
+    registry: &'a TypeRegistry,
+}
+
+impl Serialize for SnapshotSerializer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut keys: Vec<&String> = self.entries.keys().collect();
+        keys.sort();
+        let mut map = serializer.serialize_map(Some(keys.len()))?;
+        for key in keys {
+            map.serialize_entry(
+                key,
+                &ReflectSerializer::new(self.entries[key].as_ref(), self.registry),
+            )?;
+        }
+        map.end()
+    }
+}
+
+struct SnapshotDeserializer<'a> {
+    registry: &'a TypeRegistry,
+}
+
+impl<'de> DeserializeSeed<'de> for SnapshotDeserializer<'_> {
+    type Value = HashMap<String, Box<dyn PartialReflect>>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for SnapshotDeserializer<'_> {
+    type Value = HashMap<String, Box<dyn PartialReflect>>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map of UI state keys to values")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut entries = HashMap::new();
+        while let Some(key) = map.next_key::<String>()? {
+            let value = map.next_value_seed(ReflectDeserializer::new(self.registry))?;
+            entries.insert(key, value);
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::reflect::{FromReflect, Reflect, TypeRegistry};
+
+    use super::UiStateSnapshot;
+
+    #[derive(Reflect, Clone, PartialEq, Debug)]
+    struct Split {
+        ratio: f32,
+        open: bool,
+    }
+
+    fn registry() -> TypeRegistry {
+        let mut registry = TypeRegistry::new();
+        registry.register::<Split>();
+        registry
+    }
+
+    fn get<T: FromReflect>(snapshot: &UiStateSnapshot, key: &str) -> Option<T> {
+        T::from_reflect(snapshot.entries.get(key)?.as_ref())
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let registry = registry();
+        let mut snapshot = UiStateSnapshot::default();
+        snapshot
+            .entries
+            .insert("tree.expanded".to_string(), Box::new(true));
+        snapshot.entries.insert(
+            "panel.split".to_string(),
+            Box::new(Split {
+                ratio: 0.25,
+                open: false,
+            }),
+        );
+        snapshot
+            .entries
+            .insert("search".to_string(), Box::new("thorium".to_string()));
+
+        let text = snapshot.to_ron(&registry).unwrap();
+        let loaded = UiStateSnapshot::from_ron(&text, &registry).unwrap();
+
+        assert_eq!(loaded.len(), 3);
+        assert_eq!(get::<bool>(&loaded, "tree.expanded"), Some(true));
+        assert_eq!(
+            get::<Split>(&loaded, "panel.split"),
+            Some(Split {
+                ratio: 0.25,
+                open: false,
+            })
+        );
+        assert_eq!(
+            get::<String>(&loaded, "search"),
+            Some("thorium".to_string())
+        );
+
+        // Entries are written in key order, so the output is stable.
+        assert_eq!(loaded.to_ron(&registry).unwrap(), text);
+        let split = text.find("panel.split").unwrap();
+        let search = text.find("search").unwrap();
+        let tree = text.find("tree.expanded").unwrap();
+        assert!(split < search && search < tree);
+    }
+
+    #[test]
+    fn test_empty() {
+        let registry = registry();
+        let text = UiStateSnapshot::default().to_ron(&registry).unwrap();
+
+        assert!(UiStateSnapshot::from_ron(&text, &registry)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_unregistered_type() {
+        let mut snapshot = UiStateSnapshot::default();
+        snapshot.entries.insert(
+            "panel.split".to_string(),
+            Box::new(Split {
+                ratio: 0.5,
+                open: true,
+            }),
+        );
+        let text = snapshot.to_ron(&registry()).unwrap();
+
+        assert!(UiStateSnapshot::from_ron(&text, &TypeRegistry::new()).is_err());
+    }
+}
@@ -0,0 +1,567 @@
+use std::any::{Any, TypeId};
+
+use bevy::{
+    ecs::{component::Mutable as MutableComponent, world::DeferredWorld},
+    prelude::*,
+};
+
+use crate::{mutable::MutableCell, Mutable, Signal, WriteMutable};
+
+type Snapshot = Box<dyn Any + Send + Sync>;
+type CaptureFn = fn(&World, Option<Entity>) -> Option<Snapshot>;
+type RestoreFn = fn(&mut World, Option<Entity>, &Snapshot);
+
+/// The recorded state of one mutable, component or resource, before and after a transaction.
+struct UndoEntry {
+    entity: Option<Entity>,
+    type_id: TypeId,
+    before: Snapshot,
+    after: Option<Snapshot>,
+    capture: CaptureFn,
+    restore: RestoreFn,
+}
+
+impl UndoEntry {
+    fn is_same_target(&self, other: &UndoEntry) -> bool {
+        self.entity == other.entity && self.type_id == other.type_id
+    }
+}
+
+/// A committed transaction, which can be undone and redone as a single step.
+struct UndoStep {
+    label: String,
+    merge_key: Option<String>,
+    /// The elapsed time when the step was committed, or `None` if there was no [`Time`].
+    time: Option<f32>,
+    entries: Vec<UndoEntry>,
+}
+
+/// A group of writes to mutables, components and resources which is undone and redone as a
+/// single step.
+///
+/// Writes made through the transaction record the previous value of whatever they modify; writes
+/// made by other means can be included by calling one of the `record_` methods beforehand. When
+/// the transaction is committed, the new values are captured and the transaction is added to the
+/// [`UndoHistory`].
+///
+/// ```rust,ignore
+/// let mut tx = Transaction::new("Change color");
+/// tx.set_mutable(&mut world, color, new_color);
+/// tx.commit(&mut world);
+/// ```
+///
+/// Transaction methods take a [`DeferredWorld`]; code which has a `&mut World` can convert it
+/// with `&mut world.into()`.
+pub struct Transaction {
+    label: String,
+    merge_key: Option<String>,
+    entries: Vec<UndoEntry>,
+}
+
+impl Transaction {
+    /// Construct a new transaction. The label is what will be shown to the user, for example
+    /// in an "Undo ..." menu item.
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            merge_key: None,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Allow this transaction to be merged into the previous one, if the previous transaction
+    /// has the same merge key and was committed recently. This is used for continuous edits,
+    /// such as dragging a slider, where each intermediate value is committed as it happens but
+    /// the whole gesture should be undone in one step. Transactions are never merged if there
+    /// is no [`Time`] resource.
+    pub fn merge_key(mut self, key: impl Into<String>) -> Self {
+        self.merge_key = Some(key.into());
+        self
+    }
+
+    /// True if nothing has been recorded by this transaction.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Record the current value of a mutable, so that later changes to it can be undone.
+    pub fn record_mutable<T>(&mut self, world: &World, mutable: Mutable<T>)
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        self.record::<MutableCell<T>>(
+            world,
+            Some(mutable.id()),
+            capture_mutable::<T>,
+            restore_mutable::<T>,
+        );
+    }
+
+    /// Record the current value of a component, so that later changes to it can be undone.
+    /// If the entity does not have the component, undoing will remove it again.
+    pub fn record_component<C>(&mut self, world: &World, entity: Entity)
+    where
+        C: Component<Mutability = MutableComponent> + Clone,
+    {
+        self.record::<C>(
+            world,
+            Some(entity),
+            capture_component::<C>,
+            restore_component::<C>,
+        );
+    }
+
+    /// Record the current value of a resource, so that later changes to it can be undone.
+    pub fn record_resource<R>(&mut self, world: &World)
+    where
+        R: Resource + Clone,
+    {
+        self.record::<R>(world, None, capture_resource::<R>, restore_resource::<R>);
+    }
+
+    /// Set the value of a mutable, recording its previous value.
+    pub fn set_mutable<T>(&mut self, world: &mut DeferredWorld, mutable: Mutable<T>, value: T)
+    where
+        T: Clone + PartialEq + Send + Sync + 'static,
+    {
+        self.record_mutable(world, mutable);
+        world.write_mutable(mutable.id(), value);
+    }
+
+    /// Update a mutable in place, recording its previous value.
+    pub fn update_mutable<T, F: FnOnce(Mut<T>)>(
+        &mut self,
+        world: &mut DeferredWorld,
+        mutable: Mutable<T>,
+        updater: F,
+    ) where
+        T: Clone + Send + Sync + 'static,
+    {
+        self.record_mutable(world, mutable);
+        world.update_mutable(mutable.id(), updater);
+    }
+
+    /// Update a component in place, recording its previous value. Does nothing if the entity
+    /// does not have the component.
+    pub fn update_component<C, F: FnOnce(Mut<C>)>(
+        &mut self,
+        world: &mut DeferredWorld,
+        entity: Entity,
+        updater: F,
+    ) where
+        C: Component<Mutability = MutableComponent> + Clone,
+    {
+        self.record_component::<C>(world, entity);
+        if let Some(component) = world.get_mut::<C>(entity) {
+            updater(component);
+        }
+    }
+
+    /// Update a resource in place, recording its previous value. Does nothing if the resource
+    /// does not exist.
+    pub fn update_resource<R, F: FnOnce(Mut<R>)>(&mut self, world: &mut DeferredWorld, updater: F)
+    where
+        R: Resource + Clone,
+    {
+        self.record_resource::<R>(world);
+        if let Some(resource) = world.get_resource_mut::<R>() {
+            updater(resource);
+        }
+    }
+
+    /// Capture the new values of everything recorded, and add the transaction to the
+    /// [`UndoHistory`]. Empty transactions are discarded.
+    pub fn commit(mut self, world: &mut DeferredWorld) {
+        if self.entries.is_empty() {
+            return;
+        }
+        for entry in self.entries.iter_mut() {
+            entry.after = (entry.capture)(world, entry.entity);
+        }
+        let time = world.get_resource::<Time>().map(|time| time.elapsed_secs());
+        let Some(mut history) = world.get_resource_mut::<UndoHistory>() else {
+            warn!("Transaction '{}' committed with no UndoHistory", self.label);
+            return;
+        };
+        history.push(UndoStep {
+            label: self.label,
+            merge_key: self.merge_key,
+            time,
+            entries: self.entries,
+        });
+    }
+
+    fn record<T: 'static>(
+        &mut self,
+        world: &World,
+        entity: Option<Entity>,
+        capture: CaptureFn,
+        restore: RestoreFn,
+    ) {
+        let type_id = TypeId::of::<T>();
+        if self
+            .entries
+            .iter()
+            .any(|entry| entry.entity == entity && entry.type_id == type_id)
+        {
+            // Only the value before the first write matters.
+            return;
+        }
+        if let Some(before) = capture(world, entity) {
+            self.entries.push(UndoEntry {
+                entity,
+                type_id,
+                before,
+                after: None,
+                capture,
+                restore,
+            });
+        }
+    }
+}
+
+/// The history of committed [`Transaction`]s. Undo and redo are performed with the [`Undo`] and
+/// [`Redo`] commands.
+#[derive(Resource)]
+pub struct UndoHistory {
+    undo: Vec<UndoStep>,
+    redo: Vec<UndoStep>,
+    limit: usize,
+    merge_window: f32,
+    /// If true, the next transaction won't be merged into the previous one.
+    sealed: bool,
+}
+
+impl Default for UndoHistory {
+    fn default() -> Self {
+        Self {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            limit: 100,
+            merge_window: 1.0,
+            sealed: true,
+        }
+    }
+}
+
+impl UndoHistory {
+    /// True if there is a transaction which can be undone.
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// True if there is a transaction which can be redone.
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// The label of the transaction which would be undone.
+    pub fn undo_label(&self) -> Option<&str> {
+        self.undo.last().map(|step| step.label.as_str())
+    }
+
+    /// The label of the transaction which would be redone.
+    pub fn redo_label(&self) -> Option<&str> {
+        self.redo.last().map(|step| step.label.as_str())
+    }
+
+    /// A signal which is true if there is a transaction which can be undone.
+    pub fn can_undo_signal() -> Signal<bool> {
+        Signal::from_resource::<UndoHistory, _>(UndoHistory::can_undo)
+    }
+
+    /// A signal which is true if there is a transaction which can be redone.
+    pub fn can_redo_signal() -> Signal<bool> {
+        Signal::from_resource::<UndoHistory, _>(UndoHistory::can_redo)
+    }
+
+    /// Set the maximum number of transactions which can be undone. The default is 100.
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.trim();
+    }
+
+    /// Set how long, in seconds, a transaction with a merge key remains open for merging. The
+    /// default is one second.
+    pub fn set_merge_window(&mut self, seconds: f32) {
+        self.merge_window = seconds;
+    }
+
+    /// Prevent the next transaction from being merged into the previous one, for example at the
+    /// end of a drag gesture.
+    pub fn seal(&mut self) {
+        self.sealed = true;
+    }
+
+    /// Discard all history.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.sealed = true;
+    }
+
+    fn push(&mut self, mut step: UndoStep) {
+        self.redo.clear();
+        if let Some(prev) = self.undo.last_mut() {
+            let recent = match (prev.time, step.time) {
+                (Some(prev_time), Some(time)) => time - prev_time <= self.merge_window,
+                _ => false,
+            };
+            if !self.sealed
+                && step.merge_key.is_some()
+                && prev.merge_key == step.merge_key
+                && recent
+            {
+                // Keep the earliest "before" value of each target, and the latest "after".
+                for entry in step.entries.drain(..) {
+                    match prev.entries.iter_mut().find(|e| e.is_same_target(&entry)) {
+                        Some(existing) => existing.after = entry.after,
+                        None => prev.entries.push(entry),
+                    }
+                }
+                prev.time = step.time;
+                return;
+            }
+        }
+        self.undo.push(step);
+        self.sealed = false;
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        if self.undo.len() > self.limit {
+            let excess = self.undo.len() - self.limit;
+            self.undo.drain(..excess);
+        }
+    }
+}
+
+/// Command which undoes the most recent transaction in the [`UndoHistory`].
+pub struct Undo;
+
+impl Command for Undo {
+    fn apply(self, world: &mut World) {
+        let Some(step) = world
+            .get_resource_mut::<UndoHistory>()
+            .and_then(|mut history| history.undo.pop())
+        else {
+            return;
+        };
+        for entry in step.entries.iter().rev() {
+            (entry.restore)(world, entry.entity, &entry.before);
+        }
+        let mut history = world.resource_mut::<UndoHistory>();
+        history.redo.push(step);
+        history.sealed = true;
+    }
+}
+
+/// Command which redoes the most recently undone transaction in the [`UndoHistory`].
+pub struct Redo;
+
+impl Command for Redo {
+    fn apply(self, world: &mut World) {
+        let Some(step) = world
+            .get_resource_mut::<UndoHistory>()
+            .and_then(|mut history| history.redo.pop())
+        else {
+            return;
+        };
+        for entry in step.entries.iter() {
+            if let Some(after) = entry.after.as_ref() {
+                (entry.restore)(world, entry.entity, after);
+            }
+        }
+        let mut history = world.resource_mut::<UndoHistory>();
+        history.undo.push(step);
+        history.sealed = true;
+    }
+}
+
+fn capture_mutable<T: Clone + Send + Sync + 'static>(
+    world: &World,
+    entity: Option<Entity>,
+) -> Option<Snapshot> {
+    let cell = world.get::<MutableCell<T>>(entity?)?;
+    Some(Box::new(cell.0.clone()))
+}
+
+fn restore_mutable<T: Clone + Send + Sync + 'static>(
+    world: &mut World,
+    entity: Option<Entity>,
+    snapshot: &Snapshot,
+) {
+    let (Some(entity), Some(value)) = (entity, snapshot.downcast_ref::<T>()) else {
+        return;
+    };
+    if let Some(mut cell) = world.get_mut::<MutableCell<T>>(entity) {
+        cell.0 = value.clone();
+    }
+}
+
+fn capture_component<C: Component + Clone>(
+    world: &World,
+    entity: Option<Entity>,
+) -> Option<Snapshot> {
+    let entity = entity?;
+    world.get_entity(entity).ok()?;
+    Some(Box::new(world.get::<C>(entity).cloned()))
+}
+
+fn restore_component<C: Component<Mutability = MutableComponent> + Clone>(
+    world: &mut World,
+    entity: Option<Entity>,
+    snapshot: &Snapshot,
+) {
+    let (Some(entity), Some(value)) = (entity, snapshot.downcast_ref::<Option<C>>()) else {
+        return;
+    };
+    let Ok(mut entt) = world.get_entity_mut(entity) else {
+        return;
+    };
+    match value {
+        Some(value) => {
+            if let Some(mut component) = entt.get_mut::<C>() {
+                *component = value.clone();
+            } else {
+                entt.insert(value.clone());
+            }
+        }
+        None => {
+            entt.remove::<C>();
+        }
+    }
+}
+
+fn capture_resource<R: Resource + Clone>(world: &World, _: Option<Entity>) -> Option<Snapshot> {
+    Some(Box::new(world.get_resource::<R>()?.clone()))
+}
+
+fn restore_resource<R: Resource + Clone>(
+    world: &mut World,
+    _: Option<Entity>,
+    snapshot: &Snapshot,
+) {
+    if let (Some(mut resource), Some(value)) =
+        (world.get_resource_mut::<R>(), snapshot.downcast_ref::<R>())
+    {
+        *resource = value.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::TypeId;
+
+    use bevy::prelude::*;
+
+    use super::{capture_resource, restore_resource, Redo, Undo, UndoEntry, UndoHistory, UndoStep};
+
+    #[derive(Resource, Clone, PartialEq, Debug)]
+    struct Value(i32);
+
+    fn step(merge_key: Option<&str>, time: Option<f32>, before: i32, after: i32) -> UndoStep {
+        UndoStep {
+            label: "Edit".to_string(),
+            merge_key: merge_key.map(String::from),
+            time,
+            entries: vec![UndoEntry {
+                entity: None,
+                type_id: TypeId::of::<Value>(),
+                before: Box::new(Value(before)),
+                after: Some(Box::new(Value(after))),
+                capture: capture_resource::<Value>,
+                restore: restore_resource::<Value>,
+            }],
+        }
+    }
+
+    /// The "before" and "after" values of each step which can be undone.
+    fn values(history: &UndoHistory) -> Vec<(i32, i32)> {
+        history
+            .undo
+            .iter()
+            .map(|step| {
+                let entry = &step.entries[0];
+                let before = entry.before.downcast_ref::<Value>().unwrap();
+                let after = entry
+                    .after
+                    .as_ref()
+                    .unwrap()
+                    .downcast_ref::<Value>()
+                    .unwrap();
+                (before.0, after.0)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_merge_within_window() {
+        let mut history = UndoHistory::default();
+        history.push(step(Some("drag"), Some(0.0), 0, 1));
+        history.push(step(Some("drag"), Some(0.5), 1, 2));
+
+        assert_eq!(values(&history), vec![(0, 2)]);
+
+        // The window is measured from the latest merged step.
+        history.push(step(Some("drag"), Some(1.2), 2, 3));
+        assert_eq!(values(&history), vec![(0, 3)]);
+    }
+
+    #[test]
+    fn test_no_merge_outside_window() {
+        let mut history = UndoHistory::default();
+        history.push(step(Some("drag"), Some(0.0), 0, 1));
+        history.push(step(Some("drag"), Some(1.5), 1, 2));
+
+        assert_eq!(values(&history), vec![(0, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn test_no_merge_without_time() {
+        let mut history = UndoHistory::default();
+        history.push(step(Some("drag"), None, 0, 1));
+        history.push(step(Some("drag"), None, 1, 2));
+
+        assert_eq!(values(&history), vec![(0, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn test_no_merge_different_key() {
+        let mut history = UndoHistory::default();
+        history.push(step(Some("drag"), Some(0.0), 0, 1));
+        history.push(step(Some("type"), Some(0.1), 1, 2));
+        history.push(step(None, Some(0.2), 2, 3));
+        history.push(step(None, Some(0.3), 3, 4));
+
+        assert_eq!(values(&history), vec![(0, 1), (1, 2), (2, 3), (3, 4)]);
+    }
+
+    #[test]
+    fn test_no_merge_after_seal() {
+        let mut history = UndoHistory::default();
+        history.push(step(Some("drag"), Some(0.0), 0, 1));
+        history.seal();
+        history.push(step(Some("drag"), Some(0.1), 1, 2));
+
+        assert_eq!(values(&history), vec![(0, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn test_undo_redo_merged() {
+        let mut world = World::new();
+        world.insert_resource(Value(2));
+        let mut history = UndoHistory::default();
+        history.push(step(Some("drag"), Some(0.0), 0, 1));
+        history.push(step(Some("drag"), Some(0.5), 1, 2));
+        world.insert_resource(history);
+
+        Undo.apply(&mut world);
+        assert_eq!(*world.resource::<Value>(), Value(0));
+        assert!(!world.resource::<UndoHistory>().can_undo());
+
+        Redo.apply(&mut world);
+        assert_eq!(*world.resource::<Value>(), Value(2));
+        assert!(!world.resource::<UndoHistory>().can_redo());
+    }
+}
@@ -0,0 +1,225 @@
+//! Tests for the expansion of the `template!` and `ui!` macros. These are integration tests so
+//! that the macros are expanded in a crate other than `thorium_ui_core`, the way that
+//! applications use them.
+
+use bevy::prelude::*;
+use thorium_ui_core::{
+    template, ui, CreateMutable, DynChildren, Mutable, Template, TemplateContext,
+    ThoriumUiCorePlugin,
+};
+
+/// Marker inserted by a style.
+#[derive(Component)]
+struct Styled;
+
+/// Value applied by a `@calc`.
+#[derive(Component, PartialEq, Debug)]
+struct Value(i32);
+
+/// Marker inserted by an `@insert_when`.
+#[derive(Component)]
+struct Highlighted;
+
+#[derive(Event)]
+struct Poke;
+
+/// Number of times the `@on` observer was triggered.
+#[derive(Resource, Default)]
+struct Pokes(usize);
+
+fn style_panel(ec: &mut EntityCommands) {
+    ec.insert(Styled);
+}
+
+struct Label(&'static str);
+
+impl Template for Label {
+    fn build(&self, tc: &mut TemplateContext) {
+        tc.spawn(Text::new(self.0));
+    }
+}
+
+fn setup() -> (App, Entity) {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, ThoriumUiCorePlugin));
+    app.init_resource::<Pokes>();
+    let root = app.world_mut().spawn_empty().id();
+    (app, root)
+}
+
+/// Run enough frames for effects spawned by other effects to have run.
+fn frames(app: &mut App) {
+    for _ in 0..3 {
+        app.update();
+    }
+}
+
+/// The text of all of the nodes below `entity`, in order.
+fn texts(world: &World, entity: Entity) -> Vec<String> {
+    let mut out = Vec::new();
+    collect_texts(world, entity, &mut out);
+    out
+}
+
+fn collect_texts(world: &World, entity: Entity, out: &mut Vec<String>) {
+    if let Some(text) = world.get::<Text>(entity) {
+        out.push(text.0.clone());
+    }
+    if let Some(children) = world.get::<DynChildren>(entity) {
+        for child in children {
+            collect_texts(world, *child, out);
+        }
+    }
+}
+
+/// The first entity below `entity` which has a `T` component.
+fn find<T: Component>(world: &World, entity: Entity) -> Option<Entity> {
+    if world.get::<T>(entity).is_some() {
+        return Some(entity);
+    }
+    world
+        .get::<DynChildren>(entity)?
+        .into_iter()
+        .find_map(|child| find::<T>(world, *child))
+}
+
+#[test]
+fn test_elements() {
+    let (mut app, root) = setup();
+    let value = app.world_mut().create_mutable(1);
+    let highlight = app.world_mut().create_mutable(false);
+    let mut tc = TemplateContext::new(root, app.world_mut());
+    template!(&mut tc,
+        "Title"
+        let label = "Label";
+        (Node::default(), Name::new("Panel")) {
+            @style(style_panel)
+            @calc(value.get(&world) => |v, ent| {
+                ent.insert(Value(v));
+            })
+            @insert_when(highlight.get(&world) => Highlighted)
+            @on(|_: Trigger<Poke>, mut pokes: ResMut<Pokes>| {
+                pokes.0 += 1;
+            })
+            "Child"
+            Text::new(label);
+        }
+        @invoke(Label("Invoked"))
+        @with(|tc: &mut TemplateContext| {
+            tc.spawn(Text::new("With"));
+        })
+    );
+    frames(&mut app);
+
+    let world = app.world_mut();
+    assert_eq!(
+        texts(world, root),
+        ["Title", "Child", "Label", "Invoked", "With"]
+    );
+    let panel = find::<Name>(world, root).unwrap();
+    assert!(world.get::<Styled>(panel).is_some());
+    assert_eq!(world.get::<Value>(panel), Some(&Value(1)));
+    assert!(world.get::<Highlighted>(panel).is_none());
+
+    value.set(world, 2);
+    highlight.set(world, true);
+    world.trigger_targets(Poke, panel);
+    frames(&mut app);
+
+    let world = app.world();
+    assert_eq!(world.get::<Value>(panel), Some(&Value(2)));
+    assert!(world.get::<Highlighted>(panel).is_some());
+    assert_eq!(world.resource::<Pokes>().0, 1);
+}
+
+#[test]
+fn test_if() {
+    let (mut app, root) = setup();
+    let count: Mutable<i32> = app.world_mut().create_mutable(0);
+    let mut tc = TemplateContext::new(root, app.world_mut());
+    template!(
+        &mut tc,
+        if count.get(&world) > 1 {
+            "Many"
+        } else if count.get(&world) > 0 {
+            "One"
+        } else {
+            "None"
+        }
+    );
+
+    for (value, expected) in [(0, "None"), (1, "One"), (2, "Many"), (0, "None")] {
+        count.set(app.world_mut(), value);
+        frames(&mut app);
+        assert_eq!(texts(app.world(), root), [expected]);
+    }
+}
+
+#[test]
+fn test_match() {
+    let (mut app, root) = setup();
+    let count: Mutable<i32> = app.world_mut().create_mutable(0);
+    let mut tc = TemplateContext::new(root, app.world_mut());
+    template!(
+        &mut tc,
+        match count.get(&world) {
+            0 => {
+                "Zero"
+            }
+            1 | 2 => {
+                "Few"
+            }
+            _ => {
+                "Many"
+            }
+        }
+    );
+
+    for (value, expected) in [
+        (0, "Zero"),
+        (1, "Few"),
+        (2, "Few"),
+        (3, "Many"),
+        (0, "Zero"),
+    ] {
+        count.set(app.world_mut(), value);
+        frames(&mut app);
+        assert_eq!(texts(app.world(), root), [expected]);
+    }
+}
+
+#[test]
+fn test_for() {
+    let (mut app, root) = setup();
+    let items: Mutable<Vec<String>> = app.world_mut().create_mutable(Vec::new());
+    let signal = items.signal();
+    let mut tc = TemplateContext::new(root, app.world_mut());
+    template!(&mut tc,
+        for item in signal.get_clone(&world) {
+            Text::new(item);
+        } else {
+            "Empty"
+        }
+    );
+    frames(&mut app);
+    assert_eq!(texts(app.world(), root), ["Empty"]);
+
+    items.set_clone(app.world_mut(), vec!["A".to_string(), "B".to_string()]);
+    frames(&mut app);
+    assert_eq!(texts(app.world(), root), ["A", "B"]);
+}
+
+#[test]
+fn test_ui() {
+    let (mut app, _) = setup();
+    let list = app
+        .world_mut()
+        .spawn(DynChildren::spawn(ui! {
+            "A"
+            Text::new("B");
+            @invoke(Label("C"))
+        }))
+        .id();
+    frames(&mut app);
+    assert_eq!(texts(app.world(), list), ["A", "B", "C"]);
+}
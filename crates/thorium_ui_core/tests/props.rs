@@ -0,0 +1,86 @@
+//! Tests for `#[derive(TemplateProps)]`, covering the builder method generated for each kind of
+//! field.
+
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use thorium_ui_core::{Signal, SpawnableListGen, StyleHandle, TemplateProps};
+
+#[derive(TemplateProps)]
+struct Props {
+    /// A signal.
+    value: Signal<f32>,
+
+    /// A signal with a default.
+    #[props(default = 10.)]
+    max: Signal<f32>,
+
+    /// An optional signal.
+    disabled: Option<Signal<bool>>,
+
+    /// A style.
+    style: StyleHandle,
+
+    /// Child content.
+    contents: Option<Arc<dyn SpawnableListGen + Send + Sync>>,
+
+    /// A string.
+    caption: String,
+
+    /// An optional string.
+    tooltip: Option<String>,
+
+    /// Any other optional value.
+    tab_index: Option<i32>,
+
+    /// Any other value.
+    #[props(default = 3)]
+    size: usize,
+
+    /// A field without a builder method.
+    #[props(skip, default = 7)]
+    internal: i32,
+}
+
+fn style_props(ec: &mut EntityCommands) {
+    ec.insert(Visibility::Hidden);
+}
+
+#[test]
+fn test_defaults() {
+    let props = Props::new();
+    assert!(matches!(props.value, Signal::Constant(value) if value == 0.));
+    assert!(matches!(props.max, Signal::Constant(max) if max == 10.));
+    assert!(props.disabled.is_none());
+    assert!(props.style.style.is_none());
+    assert!(props.contents.is_none());
+    assert_eq!(props.caption, "");
+    assert_eq!(props.tooltip, None);
+    assert_eq!(props.tab_index, None);
+    assert_eq!(props.size, 3);
+    assert_eq!(props.internal, 7);
+}
+
+#[test]
+fn test_builders() {
+    let props = Props::new()
+        .value(0.5)
+        .max(2.)
+        .disabled(true)
+        .style(style_props)
+        .contents(|| ())
+        .caption("Caption")
+        .tooltip(String::from("Tooltip"))
+        .tab_index(1)
+        .size(4);
+    assert!(matches!(props.value, Signal::Constant(value) if value == 0.5));
+    assert!(matches!(props.max, Signal::Constant(max) if max == 2.));
+    assert!(matches!(props.disabled, Some(Signal::Constant(true))));
+    assert!(props.style.style.is_some());
+    assert!(props.contents.is_some());
+    assert_eq!(props.caption, "Caption");
+    assert_eq!(props.tooltip.as_deref(), Some("Tooltip"));
+    assert_eq!(props.tab_index, Some(1));
+    assert_eq!(props.size, 4);
+    assert_eq!(props.internal, 7);
+}
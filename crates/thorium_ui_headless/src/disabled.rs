@@ -3,6 +3,7 @@ use bevy::{
     ecs::{component::HookContext, world::DeferredWorld},
     prelude::{Component, Entity, World},
 };
+use thorium_ui_core::ReadTracked;
 
 /// A marker component to indicate that a widget is disabled and should be "grayed out".
 #[derive(Component, Debug, Clone, Copy)]
@@ -33,12 +34,12 @@ pub trait IsInteractionDisabled {
 
 impl IsInteractionDisabled for DeferredWorld<'_> {
     fn is_interaction_disabled(&self, entity: Entity) -> bool {
-        self.get::<InteractionDisabled>(entity).is_some()
+        self.read_component::<InteractionDisabled>(entity).is_some()
     }
 }
 
 impl IsInteractionDisabled for World {
     fn is_interaction_disabled(&self, entity: Entity) -> bool {
-        self.get::<InteractionDisabled>(entity).is_some()
+        self.read_component::<InteractionDisabled>(entity).is_some()
     }
 }
@@ -3,7 +3,7 @@ use bevy::{
     picking::{hover::HoverMap, pointer::PointerId},
     prelude::*,
 };
-// use thorium_ui_core::Signal;
+use thorium_ui_core::ReadTracked;
 
 /// Component which indicates that the entity is interested in knowing when the mouse is hovering
 /// over it or any of its children.
@@ -40,8 +40,7 @@ pub trait IsHovering {
 
 impl IsHovering for DeferredWorld<'_> {
     fn is_hovering(&self, entity: Entity) -> bool {
-        self.entity(entity)
-            .get::<Hovering>()
+        self.read_component::<Hovering>(entity)
             .map(|h| h.0)
             .unwrap_or(false)
     }
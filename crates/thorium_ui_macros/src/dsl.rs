@@ -0,0 +1,572 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote, ToTokens};
+use syn::{
+    braced, parenthesized,
+    parse::{Parse, ParseStream},
+    Error, Expr, Ident, LitStr, Pat, Result, Stmt, Token,
+};
+
+use crate::core_path;
+
+/// Input to the `template!` macro: a template context, followed by a list of items.
+pub(crate) struct TemplateInput {
+    context: Expr,
+    items: Items,
+}
+
+impl Parse for TemplateInput {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let context = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let items = Items::parse_list(input, None)?;
+        Ok(Self { context, items })
+    }
+}
+
+impl TemplateInput {
+    pub(crate) fn expand(&self) -> TokenStream {
+        let core = core_path();
+        let tc = tc_ident();
+        let context = &self.context;
+        let items = self.items.expand();
+        quote! {
+            {
+                let #tc: &mut #core::TemplateContext = &mut *(#context);
+                #items
+            }
+        }
+    }
+}
+
+/// Input to the `ui!` macro: a list of items.
+pub(crate) struct UiInput {
+    items: Items,
+}
+
+impl Parse for UiInput {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let items = Items::parse_list(input, None)?;
+        Ok(Self { items })
+    }
+}
+
+impl UiInput {
+    pub(crate) fn expand(&self) -> TokenStream {
+        self.items.expand_branch()
+    }
+}
+
+/// A sequence of items, which are spawned as siblings.
+#[derive(Clone)]
+struct Items(Vec<Item>);
+
+#[derive(Clone)]
+enum Item {
+    /// A `let` statement, whose bindings are visible to the following items.
+    Let(Stmt),
+    /// A text node.
+    Text(LitStr),
+    /// An entity, with attributes and children.
+    Element(Box<Element>),
+    /// `@invoke(template)`
+    Invoke(Expr),
+    /// `@with(|tc| ...)`
+    With(Expr),
+    If(Box<IfNode>),
+    Match(Box<MatchNode>),
+    For(Box<ForNode>),
+}
+
+/// Attributes of an element.
+#[derive(Clone, Default)]
+struct Attrs {
+    styles: Vec<Expr>,
+    computations: Vec<Computation>,
+    observers: Vec<Expr>,
+}
+
+#[derive(Clone)]
+enum Computation {
+    /// `@calc(expr => |value, entity| ...)`
+    Calc { deps: Expr, effect: Expr },
+    /// `@insert_when(expr => bundle)`
+    InsertWhen { test: Expr, bundle: Expr },
+}
+
+#[derive(Clone)]
+struct Element {
+    bundle: Expr,
+    attrs: Attrs,
+    children: Items,
+}
+
+#[derive(Clone)]
+struct IfNode {
+    test: Expr,
+    then: Items,
+    otherwise: Option<Else>,
+}
+
+#[derive(Clone)]
+enum Else {
+    If(Box<IfNode>),
+    Block(Items),
+}
+
+#[derive(Clone)]
+struct MatchNode {
+    value: Expr,
+    cases: Vec<(Expr, Items)>,
+    fallback: Option<Items>,
+}
+
+#[derive(Clone)]
+struct ForNode {
+    pat: Pat,
+    items: Expr,
+    each: Items,
+    fallback: Option<Items>,
+}
+
+impl Items {
+    /// Parse items until the end of the input. If `attrs` is present, element attributes are
+    /// also accepted.
+    fn parse_list(input: ParseStream, mut attrs: Option<&mut Attrs>) -> Result<Self> {
+        let mut items = Vec::new();
+        while !input.is_empty() {
+            if input.peek(Token![;]) {
+                input.parse::<Token![;]>()?;
+                continue;
+            }
+            if input.peek(Token![,]) {
+                input.parse::<Token![,]>()?;
+                continue;
+            }
+            if input.peek(Token![let]) {
+                let stmt: Stmt = input.parse()?;
+                if !matches!(stmt, Stmt::Local(_)) {
+                    return Err(Error::new_spanned(stmt, "expected a `let` statement"));
+                }
+                items.push(Item::Let(stmt));
+            } else if input.peek(LitStr) {
+                items.push(Item::Text(input.parse()?));
+            } else if input.peek(Token![@]) {
+                input.parse::<Token![@]>()?;
+                let name: Ident = input.parse()?;
+                let content;
+                parenthesized!(content in input);
+                match (name.to_string().as_str(), attrs.as_deref_mut()) {
+                    ("invoke", _) => items.push(Item::Invoke(content.parse()?)),
+                    ("with", _) => items.push(Item::With(content.parse()?)),
+                    ("style", Some(attrs)) => attrs.styles.push(content.parse()?),
+                    ("on", Some(attrs)) => attrs.observers.push(content.parse()?),
+                    ("calc", Some(attrs)) => {
+                        let deps = content.parse()?;
+                        content.parse::<Token![=>]>()?;
+                        let effect = content.parse()?;
+                        attrs.computations.push(Computation::Calc { deps, effect });
+                    }
+                    ("insert_when", Some(attrs)) => {
+                        let test = content.parse()?;
+                        content.parse::<Token![=>]>()?;
+                        let bundle = content.parse()?;
+                        attrs
+                            .computations
+                            .push(Computation::InsertWhen { test, bundle });
+                    }
+                    ("style" | "on" | "calc" | "insert_when", None) => {
+                        return Err(Error::new(
+                            name.span(),
+                            format!("`@{name}` can only be used inside an element"),
+                        ));
+                    }
+                    _ => {
+                        return Err(Error::new(
+                            name.span(),
+                            format!("unknown directive `@{name}`"),
+                        ));
+                    }
+                }
+            } else if input.peek(Token![if]) {
+                items.push(Item::If(Box::new(input.parse()?)));
+            } else if input.peek(Token![match]) {
+                items.push(Item::Match(Box::new(input.parse()?)));
+            } else if input.peek(Token![for]) {
+                items.push(Item::For(Box::new(input.parse()?)));
+            } else {
+                items.push(Item::Element(Box::new(input.parse()?)));
+            }
+        }
+        Ok(Self(items))
+    }
+
+    fn parse_braced(input: ParseStream) -> Result<Self> {
+        let content;
+        braced!(content in input);
+        Self::parse_list(&content, None)
+    }
+
+    /// Statements which spawn the items into the template context.
+    fn expand(&self) -> TokenStream {
+        let items = self.0.iter().map(Item::expand);
+        quote! { #(#items)* }
+    }
+
+    /// An `InvokeWith` which spawns the items, for use as a branch of a control-flow node.
+    fn expand_branch(&self) -> TokenStream {
+        let core = core_path();
+        let tc = tc_ident();
+        let items = self.expand();
+        quote! {
+            #core::InvokeWith(move |#tc: &mut #core::TemplateContext| {
+                #items
+            })
+        }
+    }
+}
+
+fn parse_else(input: ParseStream) -> Result<Option<Items>> {
+    if input.peek(Token![else]) {
+        input.parse::<Token![else]>()?;
+        Ok(Some(Items::parse_braced(input)?))
+    } else {
+        Ok(None)
+    }
+}
+
+impl Parse for Element {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let bundle = Expr::parse_without_eager_brace(input)?;
+        let mut attrs = Attrs::default();
+        let children = if input.peek(syn::token::Brace) {
+            let content;
+            braced!(content in input);
+            Items::parse_list(&content, Some(&mut attrs))?
+        } else if input.is_empty() || input.peek(Token![;]) || input.peek(Token![,]) {
+            Items(Vec::new())
+        } else {
+            return Err(input.error("expected `{`, `;` or `,` after element"));
+        };
+        Ok(Self {
+            bundle,
+            attrs,
+            children,
+        })
+    }
+}
+
+impl Parse for IfNode {
+    fn parse(input: ParseStream) -> Result<Self> {
+        input.parse::<Token![if]>()?;
+        let test = Expr::parse_without_eager_brace(input)?;
+        let then = Items::parse_braced(input)?;
+        let otherwise = if input.peek(Token![else]) {
+            input.parse::<Token![else]>()?;
+            if input.peek(Token![if]) {
+                Some(Else::If(Box::new(input.parse()?)))
+            } else {
+                Some(Else::Block(Items::parse_braced(input)?))
+            }
+        } else {
+            None
+        };
+        Ok(Self {
+            test,
+            then,
+            otherwise,
+        })
+    }
+}
+
+impl Parse for MatchNode {
+    fn parse(input: ParseStream) -> Result<Self> {
+        input.parse::<Token![match]>()?;
+        let value = Expr::parse_without_eager_brace(input)?;
+        let content;
+        braced!(content in input);
+        let mut cases = Vec::new();
+        let mut fallback = None;
+        while !content.is_empty() {
+            let pat = Pat::parse_multi_with_leading_vert(&content)?;
+            if content.peek(Token![if]) {
+                return Err(content.error("match guards are not supported"));
+            }
+            content.parse::<Token![=>]>()?;
+            let body = Items::parse_braced(&content)?;
+            if content.peek(Token![,]) {
+                content.parse::<Token![,]>()?;
+            }
+            match pat {
+                Pat::Wild(_) => {
+                    if fallback.is_some() {
+                        return Err(Error::new_spanned(pat, "duplicate `_` case"));
+                    }
+                    fallback = Some(body);
+                }
+                Pat::Or(or) => {
+                    // Each alternative becomes a separate case with the same body.
+                    let values = or
+                        .cases
+                        .iter()
+                        .map(pattern_value)
+                        .collect::<Result<Vec<_>>>()?;
+                    for value in values {
+                        cases.push((value, Items(body.0.clone())));
+                    }
+                }
+                pat => cases.push((pattern_value(&pat)?, body)),
+            }
+        }
+        Ok(Self {
+            value,
+            cases,
+            fallback,
+        })
+    }
+}
+
+/// Convert a match pattern into the value which is compared against the switch value.
+fn pattern_value(pat: &Pat) -> Result<Expr> {
+    match pat {
+        Pat::Lit(_) | Pat::Path(_) | Pat::Tuple(_) | Pat::TupleStruct(_) | Pat::Struct(_) => {
+            syn::parse2(pat.to_token_stream())
+        }
+        _ => Err(Error::new_spanned(
+            pat,
+            "match patterns must be values which can be compared using `==`",
+        )),
+    }
+}
+
+impl Parse for ForNode {
+    fn parse(input: ParseStream) -> Result<Self> {
+        input.parse::<Token![for]>()?;
+        let pat = Pat::parse_multi_with_leading_vert(input)?;
+        input.parse::<Token![in]>()?;
+        let items = Expr::parse_without_eager_brace(input)?;
+        let each = Items::parse_braced(input)?;
+        let fallback = parse_else(input)?;
+        Ok(Self {
+            pat,
+            items,
+            each,
+            fallback,
+        })
+    }
+}
+
+impl Item {
+    fn expand(&self) -> TokenStream {
+        let core = core_path();
+        let tc = tc_ident();
+        match self {
+            Item::Let(stmt) => stmt.to_token_stream(),
+
+            Item::Text(text) => quote! {
+                #tc.spawn(#core::__bevy::prelude::Text::new(#text));
+            },
+
+            Item::Element(element) => element.expand(),
+
+            Item::Invoke(template) => quote! {
+                #tc.invoke(#template);
+            },
+
+            Item::With(func) => quote! {
+                (#func)(&mut *#tc);
+            },
+
+            Item::If(node) => {
+                let cond = node.expand();
+                quote! { #tc.spawn(#cond); }
+            }
+
+            Item::Match(node) => {
+                let value = reactive(&node.value);
+                let branches: Vec<Ident> = (0..node.cases.len())
+                    .map(|index| format_ident!("__case_{}", index, span = Span::mixed_site()))
+                    .collect();
+                let bodies = node.cases.iter().map(|(_, body)| body.expand_branch());
+                let values = node.cases.iter().map(|(value, _)| value);
+                let (fallback_cell, fallback) = match &node.fallback {
+                    Some(fallback) => {
+                        let fallback = fallback.expand_branch();
+                        (
+                            quote! { let __fallback = ::core::cell::Cell::new(Some(#fallback)); },
+                            quote! { __cases.fallback(__fallback.take().unwrap()); },
+                        )
+                    }
+                    None => (TokenStream::new(), TokenStream::new()),
+                };
+                // The case builder is an `Fn`, so the branches are moved into it via cells.
+                quote! {
+                    {
+                        #(let #branches = ::core::cell::Cell::new(Some(#bodies));)*
+                        #fallback_cell
+                        #tc.spawn(#core::Switch::new(#value, |__cases| {
+                            #(__cases.case(#values, #branches.take().unwrap());)*
+                            #fallback
+                        }));
+                    }
+                }
+            }
+
+            Item::For(node) => {
+                let world = world_ident();
+                let pat = &node.pat;
+                let items = &node.items;
+                let each = node.each.expand();
+                let fallback = optional_branch(node.fallback.as_ref());
+                quote! {
+                    #tc.spawn(#core::For::each(
+                        move |mut __items: #core::__bevy::ecs::system::InMut<#core::ListItems<_>>,
+                              #world: #core::__bevy::ecs::world::DeferredWorld| {
+                            let _ = &#world;
+                            __items.clone_from_iter(::core::iter::IntoIterator::into_iter(#items));
+                        },
+                        move |__item, #tc: &mut #core::TemplateContext| {
+                            let #pat = ::core::clone::Clone::clone(__item);
+                            #each
+                        },
+                        #fallback,
+                    ));
+                }
+            }
+        }
+    }
+}
+
+impl Element {
+    fn expand(&self) -> TokenStream {
+        let core = core_path();
+        let tc = tc_ident();
+        let bundle = &self.bundle;
+        let mut components = vec![bundle.to_token_stream()];
+
+        match self.attrs.styles.as_slice() {
+            [] => {}
+            [style] => components.push(quote! { #core::Styles(#style) }),
+            styles => components.push(quote! { #core::Styles((#(#styles),*)) }),
+        }
+
+        let computations: Vec<TokenStream> = self
+            .attrs
+            .computations
+            .iter()
+            .map(|computation| {
+                let computation = computation.expand();
+                quote! { #core::__bevy::ecs::spawn::Spawn(#computation) }
+            })
+            .collect();
+        match computations.as_slice() {
+            [] => {}
+            [computation] => components.push(quote! { #core::Computations::spawn(#computation) }),
+            computations => {
+                components.push(quote! { #core::Computations::spawn((#(#computations),*)) })
+            }
+        }
+
+        let spawn = match components.as_slice() {
+            [bundle] => quote! { #tc.spawn(#bundle) },
+            components => quote! { #tc.spawn((#(#components),*)) },
+        };
+
+        let observers = &self.attrs.observers;
+        if observers.is_empty() && self.children.0.is_empty() {
+            return quote! { #spawn; };
+        }
+
+        let children = if self.children.0.is_empty() {
+            TokenStream::new()
+        } else {
+            let children = self.children.expand();
+            quote! {
+                let __id = __entity.id();
+                let mut __context = #core::TemplateContext::new(__id, __entity.into_world_mut());
+                let #tc = &mut __context;
+                #children
+            }
+        };
+        let entity_mut = if observers.is_empty() {
+            TokenStream::new()
+        } else {
+            quote! { mut }
+        };
+        quote! {
+            {
+                let #entity_mut __entity = #spawn;
+                #(__entity.observe(#observers);)*
+                #children
+            }
+        }
+    }
+}
+
+impl Computation {
+    fn expand(&self) -> TokenStream {
+        let core = core_path();
+        match self {
+            Computation::Calc { deps, effect } => {
+                let deps = reactive(deps);
+                quote! { #core::Calc::new(#deps, #effect) }
+            }
+            Computation::InsertWhen { test, bundle } => {
+                let test = reactive(test);
+                quote! { #core::InsertWhen::new(#test, move || #bundle) }
+            }
+        }
+    }
+}
+
+impl IfNode {
+    /// A `Cond` node; `else if` chains become nested `Cond`s in the negative branch.
+    fn expand(&self) -> TokenStream {
+        let core = core_path();
+        let tc = tc_ident();
+        let test = reactive(&self.test);
+        let then = self.then.expand_branch();
+        let otherwise = match &self.otherwise {
+            Some(Else::If(node)) => {
+                let cond = node.expand();
+                quote! {
+                    #core::InvokeWith(move |#tc: &mut #core::TemplateContext| {
+                        #tc.spawn(#cond);
+                    })
+                }
+            }
+            Some(Else::Block(items)) => items.expand_branch(),
+            None => quote! { || () },
+        };
+        quote! { #core::Cond::new(#test, #then, #otherwise) }
+    }
+}
+
+fn optional_branch(items: Option<&Items>) -> TokenStream {
+    match items {
+        Some(items) => items.expand_branch(),
+        None => quote! { || () },
+    }
+}
+
+/// A system which evaluates `expr` with `world` in scope. Since the system runs in a tracking
+/// scope, it is re-run whenever the signals or components which it reads change.
+fn reactive(expr: &Expr) -> TokenStream {
+    let core = core_path();
+    let world = world_ident();
+    quote! {
+        move |#world: #core::__bevy::ecs::world::DeferredWorld| {
+            let _ = &#world;
+            #expr
+        }
+    }
+}
+
+/// The template context. This is hygienic, so it can't be seen by user code.
+fn tc_ident() -> Ident {
+    Ident::new("__tc", Span::mixed_site())
+}
+
+/// The world which is visible to reactive expressions.
+fn world_ident() -> Ident {
+    Ident::new("world", Span::call_site())
+}
@@ -0,0 +1,131 @@
+//! Procedural macros for Thorium UI. These are re-exported by `thorium_ui_core`, and should be
+//! used from there.
+
+use proc_macro::TokenStream;
+use proc_macro_crate::{crate_name, FoundCrate};
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, DeriveInput};
+
+mod dsl;
+mod props;
+
+/// The path to `thorium_ui_core` from the crate which invoked the macro. This is either the
+/// crate itself, the name it was given in the dependent's `Cargo.toml`, or, if only `thorium_ui`
+/// is a dependency, `thorium_ui`, which re-exports the core crate's items.
+pub(crate) fn core_path() -> proc_macro2::TokenStream {
+    match crate_name("thorium_ui_core").or_else(|_| crate_name("thorium_ui")) {
+        Ok(FoundCrate::Itself) => quote! { crate },
+        Ok(FoundCrate::Name(name)) => {
+            let name = format_ident!("{}", name);
+            quote! { ::#name }
+        }
+        Err(_) => quote! { ::thorium_ui_core },
+    }
+}
+
+/// Spawns a tree of entities into a `TemplateContext`.
+///
+/// The first argument is the template context, and the rest is a list of items:
+///
+/// * An element: a bundle expression followed by a block containing its attributes and
+///   children, or by `;` if it has neither. Attributes are:
+///   * `@style(style)`: adds the style to the element's `Styles`.
+///   * `@calc(expr => |value, entity| ...)`: a `Calc` which re-evaluates `expr` when its
+///     dependencies change, and applies the result to the entity.
+///   * `@insert_when(expr => bundle)`: an `InsertWhen` which inserts `bundle` while `expr` is
+///     true.
+///   * `@on(observer)`: adds an observer to the element.
+/// * A string literal, which spawns a `Text` node.
+/// * `@invoke(template)`: invokes a `Template`.
+/// * `@with(|tc| ...)`: calls a function with the template context.
+/// * `let` statements, whose bindings are visible to the following items. This is useful for
+///   cloning signals before they are moved into a reactive expression.
+/// * `if expr { ... } else { ... }`, which becomes a `Cond`.
+/// * `match expr { value => { ... }, _ => { ... } }`, which becomes a `Switch`. Patterns must
+///   be values which can be compared using `==`.
+/// * `for pattern in expr { ... } else { ... }`, which becomes a `For::each`. The optional
+///   `else` block is displayed when the list is empty.
+///
+/// Reactive expressions (conditions, match values, list expressions and `@calc` and
+/// `@insert_when` dependencies) are evaluated in a closure with `world: DeferredWorld` in
+/// scope, and are re-evaluated when the signals they read change. Like the closures passed to
+/// `Cond::new`, they are `move` closures.
+///
+/// ```rust,ignore
+/// template!(builder,
+///     Node::default() {
+///         @style(style_panel)
+///         @calc(disabled.get(&world) => |disabled, ent| {
+///             ent.insert(BackgroundColor(if disabled { GRAY } else { BLUE }.into()));
+///         })
+///         "Items:"
+///         for item in items.get_clone(&world) {
+///             (Text::new(item), UseInheritedTextStyles);
+///         } else {
+///             "None"
+///         }
+///     }
+/// );
+/// ```
+#[proc_macro]
+pub fn template(input: TokenStream) -> TokenStream {
+    parse_macro_input!(input as dsl::TemplateInput)
+        .expand()
+        .into()
+}
+
+/// Like [`template!`], but instead of spawning the items immediately, returns an `InvokeWith`
+/// which spawns them. This can be used wherever a `SpawnableList` or `SpawnableListGen` is
+/// expected, such as a widget's label or `DynChildren::spawn`.
+///
+/// ```rust,ignore
+/// Checkbox::new().label(ui! {
+///     "Show "
+///     (Text::new("hidden"), TextColor(GRAY.into()));
+///     " files"
+/// })
+/// ```
+#[proc_macro]
+pub fn ui(input: TokenStream) -> TokenStream {
+    parse_macro_input!(input as dsl::UiInput).expand().into()
+}
+
+/// Derives `Default`, a `new()` constructor and a builder method for each field of a template's
+/// properties struct. The builder method's parameter depends on the type of the field:
+///
+/// * `Signal<T>` and `Option<Signal<T>>`: `impl IntoSignal<T>`.
+/// * `StyleHandle`: any `StyleTuple`.
+/// * `Option<Arc<dyn SpawnableListGen + Send + Sync>>`: any `SpawnableListGen`.
+/// * `String` and `Option<String>`: `impl Into<String>`.
+/// * `Option<T>`: `T`.
+/// * Anything else: the field type.
+///
+/// Builder methods are documented with the field's doc comment. Fields accept these options:
+///
+/// * `#[props(default = expr)]`: the default value. For signal fields, this can be anything
+///   that converts into the signal.
+/// * `#[props(skip)]`: don't generate a builder method for this field.
+///
+/// ```rust,ignore
+/// #[derive(TemplateProps)]
+/// pub struct Slider {
+///     /// Current slider value.
+///     pub value: Signal<f32>,
+///
+///     /// Maximum slider value.
+///     #[props(default = 1.)]
+///     pub max: Signal<f32>,
+///
+///     /// Style handle for slider root element.
+///     pub style: StyleHandle,
+///
+///     /// Callback called when value changes
+///     pub on_change: Option<SystemId<In<f32>>>,
+/// }
+/// ```
+#[proc_macro_derive(TemplateProps, attributes(props))]
+pub fn derive_template_props(input: TokenStream) -> TokenStream {
+    props::derive_template_props(parse_macro_input!(input as DeriveInput))
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
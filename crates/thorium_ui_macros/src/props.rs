@@ -0,0 +1,218 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    Attribute, Data, DeriveInput, Error, Expr, Fields, GenericArgument, PathArguments, Result,
+    Type, TypePath,
+};
+
+use crate::core_path;
+
+/// How a builder method converts its argument into the field value.
+enum FieldKind {
+    /// `Signal<T>`: takes `impl IntoSignal<T>`.
+    Signal(Type),
+    /// `Option<Signal<T>>`: takes `impl IntoSignal<T>`, and wraps it in `Some`.
+    OptionSignal(Type),
+    /// `StyleHandle`: takes any `StyleTuple`.
+    Style,
+    /// `Option<Arc<dyn SpawnableListGen + ...>>`: takes any `SpawnableListGen`.
+    Children,
+    /// `String`: takes `impl Into<String>`.
+    String,
+    /// `Option<String>`: takes `impl Into<String>`, and wraps it in `Some`.
+    OptionString,
+    /// `Option<T>`: takes `T`, and wraps it in `Some`.
+    Option(Type),
+    /// Any other type: takes the value as-is.
+    Plain,
+}
+
+/// Options from a field's `#[props(...)]` attribute.
+#[derive(Default)]
+struct FieldOptions {
+    default: Option<Expr>,
+    skip: bool,
+}
+
+pub(crate) fn derive_template_props(input: DeriveInput) -> Result<TokenStream> {
+    let core = core_path();
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "TemplateProps can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "TemplateProps requires a struct with named fields",
+        ));
+    };
+
+    let mut defaults = Vec::new();
+    let mut methods = Vec::new();
+    for field in &fields.named {
+        let name = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        let options = field_options(&field.attrs)?;
+        let kind = field_kind(ty);
+
+        defaults.push(match (&options.default, &kind) {
+            (Some(default), FieldKind::Signal(inner)) => {
+                quote! { #name: #core::IntoSignal::<#inner>::into_signal(#default) }
+            }
+            (Some(default), FieldKind::OptionSignal(inner)) => quote! {
+                #name: Some(#core::IntoSignal::<#inner>::into_signal(#default))
+            },
+            (Some(default), _) => quote! { #name: #default },
+            (None, _) => quote! { #name: ::core::default::Default::default() },
+        });
+
+        if options.skip {
+            continue;
+        }
+        let docs = field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("doc"));
+        let (generics, param, value) = match &kind {
+            FieldKind::Signal(inner) => (
+                quote! {},
+                quote! { impl #core::IntoSignal<#inner> },
+                quote! { #core::IntoSignal::into_signal(#name) },
+            ),
+            FieldKind::OptionSignal(inner) => (
+                quote! {},
+                quote! { impl #core::IntoSignal<#inner> },
+                quote! { Some(#core::IntoSignal::into_signal(#name)) },
+            ),
+            FieldKind::Style => (
+                quote! { <S: #core::StyleTuple + 'static> },
+                quote! { S },
+                quote! { #core::StyleTuple::into_handle(#name) },
+            ),
+            FieldKind::Children => (
+                quote! { <L: #core::SpawnableListGen + Send + Sync + 'static> },
+                quote! { L },
+                quote! { Some(::std::sync::Arc::new(#name)) },
+            ),
+            FieldKind::String => (
+                quote! {},
+                quote! { impl Into<String> },
+                quote! { #name.into() },
+            ),
+            FieldKind::OptionString => (
+                quote! {},
+                quote! { impl Into<String> },
+                quote! { Some(#name.into()) },
+            ),
+            FieldKind::Option(inner) => (quote! {}, quote! { #inner }, quote! { Some(#name) }),
+            FieldKind::Plain => (quote! {}, quote! { #ty }, quote! { #name }),
+        };
+        methods.push(quote! {
+            #(#docs)*
+            pub fn #name #generics(mut self, #name: #param) -> Self {
+                self.#name = #value;
+                self
+            }
+        });
+    }
+
+    let new_doc = format!("Construct a new `{ident}` with default properties.");
+    Ok(quote! {
+        impl #impl_generics ::core::default::Default for #ident #ty_generics #where_clause {
+            fn default() -> Self {
+                Self {
+                    #(#defaults,)*
+                }
+            }
+        }
+
+        impl #impl_generics #ident #ty_generics #where_clause {
+            #[doc = #new_doc]
+            pub fn new() -> Self {
+                ::core::default::Default::default()
+            }
+
+            #(#methods)*
+        }
+    })
+}
+
+fn field_options(attrs: &[Attribute]) -> Result<FieldOptions> {
+    let mut options = FieldOptions::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("props")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("default") {
+                options.default = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("skip") {
+                options.skip = true;
+                Ok(())
+            } else {
+                Err(meta.error("expected `default = ...` or `skip`"))
+            }
+        })?;
+    }
+    Ok(options)
+}
+
+fn field_kind(ty: &Type) -> FieldKind {
+    if let Some(inner) = generic_arg(ty, "Signal") {
+        return FieldKind::Signal(inner.clone());
+    }
+    if is_type(ty, "StyleHandle") {
+        return FieldKind::Style;
+    }
+    if is_type(ty, "String") {
+        return FieldKind::String;
+    }
+    if let Some(inner) = generic_arg(ty, "Option") {
+        if let Some(signal) = generic_arg(inner, "Signal") {
+            return FieldKind::OptionSignal(signal.clone());
+        }
+        if is_type(inner, "String") {
+            return FieldKind::OptionString;
+        }
+        if let Some(Type::TraitObject(object)) = generic_arg(inner, "Arc") {
+            let is_gen = object.bounds.iter().any(|bound| match bound {
+                syn::TypeParamBound::Trait(bound) => bound
+                    .path
+                    .segments
+                    .last()
+                    .is_some_and(|segment| segment.ident == "SpawnableListGen"),
+                _ => false,
+            });
+            if is_gen {
+                return FieldKind::Children;
+            }
+        }
+        return FieldKind::Option(inner.clone());
+    }
+    FieldKind::Plain
+}
+
+/// If `ty` is a path whose last segment is `name`, returns that segment.
+fn last_segment<'a>(ty: &'a Type, name: &str) -> Option<&'a syn::PathSegment> {
+    let Type::Path(TypePath { qself: None, path }) = ty else {
+        return None;
+    };
+    path.segments.last().filter(|segment| segment.ident == name)
+}
+
+fn is_type(ty: &Type, name: &str) -> bool {
+    last_segment(ty, name).is_some_and(|segment| segment.arguments.is_empty())
+}
+
+/// If `ty` is `name<T>`, returns `T`.
+fn generic_arg<'a>(ty: &'a Type, name: &str) -> Option<&'a Type> {
+    let PathArguments::AngleBracketed(args) = &last_segment(ty, name)?.arguments else {
+        return None;
+    };
+    match args.args.first() {
+        Some(GenericArgument::Type(inner)) if args.args.len() == 1 => Some(inner),
+        _ => None,
+    }
+}
@@ -5,8 +5,8 @@ use bevy::{
 use thorium_ui::{
     computations,
     hover::{Hovering, IsHovering},
-    Calc, CreateCallback, CreateMemo, CreateMutable, DynChildren, Invoke, InvokeWith, Styles,
-    Template, ThoriumUiCorePlugin,
+    Calc, CreateCallback, CreateMemo, CreateMutable, DynChildren, Invoke, InvokeWith, ReadTracked,
+    Styles, Template, ThoriumUiCorePlugin,
 };
 use thorium_ui_controls::{
     animation::{BistableTransition, BistableTransitionState},
@@ -68,8 +68,7 @@ fn setup_view_root(mut commands: Commands) {
                 let row_id = row.id();
                 let color = row.create_memo(
                     move |world: DeferredWorld| match world
-                        .entity(row_id)
-                        .get::<BistableTransition>()
+                        .read_component::<BistableTransition>(row_id)
                         .unwrap()
                         .state
                     {